@@ -3,13 +3,24 @@ use std::fs;
 use std::path::{Path, PathBuf};
 
 fn main() {
-    let sdk_home = env::var("ECOS_SDK_HOME").expect("ECOS_SDK_HOME not set");
-    let sdk_path = PathBuf::from(&sdk_home);
+    // 在宿主机上（如 `cargo test`）只编译分配器等与硬件无关的部分，不需要 SDK 和 bindgen
+    if env::var("CARGO_CFG_TARGET_ARCH").as_deref() == Ok("riscv32") {
+        let sdk_home = env::var("ECOS_SDK_HOME").expect("ECOS_SDK_HOME not set");
+        let sdk_path = PathBuf::from(&sdk_home);
 
-    let include_dirs = scan_sdk_directories(&sdk_path);
-    generate_bindings(&sdk_path, &include_dirs);
+        let include_dirs = scan_sdk_directories(&sdk_path);
+        generate_bindings(&sdk_path, &include_dirs);
+    }
+    generate_heap_config();
+    generate_log_config();
 
     println!("cargo:rerun-if-env-changed=ECOS_SDK_HOME");
+    println!("cargo:rerun-if-env-changed=ECOS_HEAP_END");
+    println!("cargo:rerun-if-env-changed=ECOS_HEAP_RESERVED");
+    println!("cargo:rerun-if-env-changed=ECOS_ALLOC_TRACE_EVENTS");
+    println!("cargo:rerun-if-env-changed=ECOS_LOG");
+    println!("cargo:rerun-if-env-changed=ECOS_LOG_DEFERRED_BUFFER");
+    println!("cargo:rerun-if-env-changed=ECOS_LOG_RETAIN_BUFFER");
     println!("cargo:rerun-if-changed=include/wrapper.h");
 }
 
@@ -89,3 +100,86 @@ fn generate_bindings(_sdk_path: &Path, include_dirs: &[PathBuf]) {
         .write_to_file(PathBuf::from(&out_dir).join("bindings.rs"))
         .expect("write bindings failed");
 }
+
+/// 堆区域的编译期配置，写入 `OUT_DIR/heap_config.rs` 供 `features::alloc` 使用
+///
+/// - `ECOS_HEAP_END`: 直接指定堆结束地址（不含），如 `0x04700000`
+/// - `ECOS_HEAP_RESERVED`: 从 RAM 顶部预留给应用（DMA/帧缓冲等）的字节数
+///
+/// 两者都未设置时，堆结束地址由 autoconf 的 `CONFIG_PSRAM_NUM` 推导
+fn generate_heap_config() {
+    let heap_end = env::var("ECOS_HEAP_END")
+        .ok()
+        .map(|v| parse_usize(&v).expect("ECOS_HEAP_END is not a valid integer"));
+    let heap_reserved = env::var("ECOS_HEAP_RESERVED")
+        .ok()
+        .map(|v| parse_usize(&v).expect("ECOS_HEAP_RESERVED is not a valid integer"))
+        .unwrap_or(0);
+    let trace_events = env::var("ECOS_ALLOC_TRACE_EVENTS")
+        .ok()
+        .map(|v| parse_usize(&v).expect("ECOS_ALLOC_TRACE_EVENTS is not a valid integer"))
+        .unwrap_or(256);
+    assert!(
+        trace_events > 0,
+        "ECOS_ALLOC_TRACE_EVENTS must be greater than 0"
+    );
+
+    let heap_end = match heap_end {
+        Some(end) => format!("Some(0x{:08x})", end),
+        None => "None".to_string(),
+    };
+
+    let config = format!(
+        "pub const HEAP_END: Option<usize> = {};\npub const HEAP_RESERVED: usize = 0x{:x};\npub const TRACE_EVENTS: usize = {};\n",
+        heap_end, heap_reserved, trace_events
+    );
+
+    let out_dir = env::var("OUT_DIR").expect("OUT_DIR not set");
+    fs::write(PathBuf::from(&out_dir).join("heap_config.rs"), config)
+        .expect("write heap config failed");
+}
+
+/// 日志的编译期配置，写入 `OUT_DIR/log_config.rs` 供 `features::log` 使用
+///
+/// - `ECOS_LOG`: 默认的过滤规则，格式同 `env_logger`，如 `info,my_app::sensors=trace`
+/// - `ECOS_LOG_DEFERRED_BUFFER`: `log-deferred` 记录队列的字节数，默认 1K
+/// - `ECOS_LOG_RETAIN_BUFFER`: `log-retain` 跨复位保留的日志字节数，默认 1K
+fn generate_log_config() {
+    let filter = match env::var("ECOS_LOG") {
+        Ok(spec) if !spec.trim().is_empty() => format!("Some({:?})", spec.trim()),
+        _ => "None".to_string(),
+    };
+    let deferred_buffer = env::var("ECOS_LOG_DEFERRED_BUFFER")
+        .ok()
+        .map(|v| parse_usize(&v).expect("ECOS_LOG_DEFERRED_BUFFER is not a valid integer"))
+        .unwrap_or(1024);
+    let retain_buffer = env::var("ECOS_LOG_RETAIN_BUFFER")
+        .ok()
+        .map(|v| parse_usize(&v).expect("ECOS_LOG_RETAIN_BUFFER is not a valid integer"))
+        .unwrap_or(1024);
+
+    let config = format!(
+        "pub const LOG_FILTER: Option<&str> = {};\npub const DEFERRED_BUFFER: usize = {};\npub const RETAIN_BUFFER: usize = {};\n",
+        filter, deferred_buffer, retain_buffer
+    );
+
+    let out_dir = env::var("OUT_DIR").expect("OUT_DIR not set");
+    fs::write(PathBuf::from(&out_dir).join("log_config.rs"), config)
+        .expect("write log config failed");
+}
+
+fn parse_usize(value: &str) -> Option<usize> {
+    let value = value.trim().replace('_', "");
+    if let Some(hex) = value
+        .strip_prefix("0x")
+        .or_else(|| value.strip_prefix("0X"))
+    {
+        usize::from_str_radix(hex, 16).ok()
+    } else if let Some(kb) = value.strip_suffix(['K', 'k']) {
+        kb.parse::<usize>().ok().map(|v| v * 1024)
+    } else if let Some(mb) = value.strip_suffix(['M', 'm']) {
+        mb.parse::<usize>().ok().map(|v| v * 1024 * 1024)
+    } else {
+        value.parse().ok()
+    }
+}
@@ -1,661 +1,14 @@
-#![allow(unused)]
+//! 分配器的板上自测与性能对比，启用 `alloc-auto-test` 特性时编译
 
-// ... 测试打印宏 ... 的补丁 ...
-macro_rules! println {
-    ($($arg:tt)*) => {
-        #[cfg(feature = "alloc-debug-trace")]
-        {
-            #[cfg(feature = "log")]
-            {
-                use crate::log;
-                log::trace!($($arg)*);
-            }
-            #[cfg(not(feature = "log"))]
-            {
-                crate::print!("[TRACE] ");
-                crate::println!($($arg)*);
-            }
-        }
-        #[cfg(not(feature = "alloc-debug-trace"))]
-        {
-            // 空实现
-        }
-    };
-}
-
-macro_rules! alloc_dbg {
-    ($($arg:tt)*) => {
-        #[cfg(feature = "alloc-auto-test")]
-        {
-            #[cfg(feature = "log")]
-            {
-                use crate::log;
-                log::info!($($arg)*);
-            }
-            #[cfg(not(feature = "log"))]
-            {
-                crate::print!("[DEBUG] ");
-                crate::println!($($arg)*);
-            }
-        }
-        #[cfg(not(feature = "alloc-auto-test"))]
-        {
-            // 空实现
-        }
-    };
-}
-
-use core::alloc::{GlobalAlloc, Layout};
-use core::cell::UnsafeCell;
-use core::ptr::null_mut;
-
-// 从链接脚本引入堆起始地址
-unsafe extern "C" {
-    static _heap_start: u8;
-}
-
-// 内存对齐要求
-const MIN_ALIGN: usize = 4;
-const MIN_BLOCK_SIZE: usize = core::mem::size_of::<BlockHeader>() + MIN_ALIGN;
-
-// 块头部信息（所有块共用）
-#[repr(C)]
-struct BlockHeader {
-    size: usize,                    // 块的总大小（包括头部）
-    is_free: bool,                  // 是否空闲
-    next: Option<*mut BlockHeader>, // 仅用于空闲链表
-}
-
-impl BlockHeader {
-    // 从地址创建节点
-    unsafe fn from_addr(addr: usize, size: usize, is_free: bool) -> *mut BlockHeader {
-        println!(
-            "BlockHeader::from_addr: addr=0x{:08x}, size={}, is_free={}",
-            addr, size, is_free
-        );
-        let ptr = addr as *mut BlockHeader;
-        // SAFETY: 调用者确保地址有效且对齐
-        unsafe {
-            (*ptr).size = size;
-            (*ptr).is_free = is_free;
-            (*ptr).next = None;
-        }
-        println!(
-            "BlockHeader::from_addr: created at 0x{:08x} with size={}",
-            ptr as usize,
-            unsafe { (*ptr).size }
-        );
-        ptr
-    }
-
-    // 获取数据区域起始地址
-    fn data_addr(&self) -> usize {
-        (self as *const _ as usize) + core::mem::size_of::<Self>()
-    }
-
-    // 获取块结束地址
-    fn end_addr(&self) -> usize {
-        (self as *const _ as usize) + self.size
-    }
-
-    // 获取可用数据大小（不包括头部）
-    fn data_size(&self) -> usize {
-        self.size - core::mem::size_of::<Self>()
-    }
-}
-
-// 全局分配器内部状态
-struct GlobalAllocatorInner {
-    free_list_head: Option<*mut BlockHeader>,
-    initialized: bool,
-}
-
-impl GlobalAllocatorInner {
-    const fn new() -> Self {
-        Self {
-            free_list_head: None,
-            initialized: false,
-        }
-    }
-
-    // 初始化堆内存
-    unsafe fn init(&mut self) {
-        println!("GlobalAllocatorInner::init: starting");
-
-        if self.initialized {
-            println!("GlobalAllocatorInner::init: already initialized");
-            return;
-        }
-
-        // 获取堆区域信息
-        // SAFETY: 调用者确保这是有效的堆起始地址
-        let heap_start = unsafe { &_heap_start as *const u8 as usize };
-        println!(
-            "GlobalAllocatorInner::init: heap_start=0x{:08x}",
-            heap_start
-        );
-
-        let heap_end = 0x04800000; // 8MB RAM 结束地址
-        println!("GlobalAllocatorInner::init: heap_end=0x{:08x}", heap_end);
-
-        if heap_start >= heap_end {
-            println!("GlobalAllocatorInner::init: ERROR - heap_start >= heap_end");
-            panic!("Invalid heap region");
-        }
-
-        let heap_size = heap_end - heap_start;
-        println!("GlobalAllocatorInner::init: heap_size={} bytes", heap_size);
-
-        if heap_size < MIN_BLOCK_SIZE {
-            println!("GlobalAllocatorInner::init: ERROR - heap too small");
-            panic!("Heap too small");
-        }
-
-        // 将整个堆初始化为一个空闲块
-        // SAFETY: heap_start 有效且对齐
-        let free_node = unsafe { BlockHeader::from_addr(heap_start, heap_size, true) };
-        self.free_list_head = Some(free_node);
-        self.initialized = true;
-
-        println!(
-            "GlobalAllocatorInner::init: initialized with free block at 0x{:08x}",
-            free_node as usize
-        );
-        self.print_free_list();
-    }
-
-    // 打印空闲链表状态
-    fn print_free_list(&self) {
-        println!("Free list status:");
-        if !self.initialized {
-            println!("  Not initialized");
-            return;
-        }
-
-        let mut count = 0;
-        let mut current_ptr = self.free_list_head;
-        while let Some(mut current) = current_ptr {
-            // SAFETY: 仅用于调试输出，不会修改
-            unsafe {
-                let node = &*current;
-                println!(
-                    "  Block {}: addr=0x{:08x}, size={}, data_size={}, next={:?}",
-                    count,
-                    current as usize,
-                    node.size,
-                    node.data_size(),
-                    node.next.map(|p| p as usize)
-                );
-                current_ptr = node.next;
-            }
-            count += 1;
-        }
-        println!("  Total free blocks: {}", count);
-    }
-
-    // 向上对齐
-    fn align_up(addr: usize, align: usize) -> usize {
-        let aligned = (addr + align - 1) & !(align - 1);
-        aligned
-    }
-
-    // 合并相邻空闲块
-    unsafe fn coalesce(&mut self) {
-        println!("GlobalAllocatorInner::coalesce: starting");
-        let mut current_ptr = self.free_list_head;
-        let mut prev_ptr: Option<*mut BlockHeader> = None;
-        let mut merged_count = 0;
-
-        while let Some(mut current) = current_ptr {
-            // SAFETY: current 是有效的 BlockHeader 指针
-            let current_node = unsafe { &mut *current };
-            let node_end = current_node.end_addr();
-            println!(
-                "coalesce: checking block at 0x{:08x}, end=0x{:08x}",
-                current as usize, node_end
-            );
-
-            // 检查是否可以与下一个块合并
-            if let Some(next_ptr) = current_node.next {
-                let next_addr = next_ptr as usize;
-                println!("coalesce:   next block at 0x{:08x}", next_addr);
-
-                if node_end == next_addr {
-                    println!(
-                        "coalesce:   MERGING blocks: 0x{:08x} + 0x{:08x}",
-                        current as usize, next_addr
-                    );
-                    // SAFETY: next_ptr 是有效的 BlockHeader 指针
-                    let next_node = unsafe { &mut *next_ptr };
-                    current_node.size += next_node.size;
-                    current_node.next = next_node.next.take();
-                    merged_count += 1;
-                    println!("coalesce:   new size: {}", current_node.size);
-                    // 重新检查这个位置，因为可能还能继续合并
-                    continue;
-                }
-            }
-
-            prev_ptr = Some(current);
-            current_ptr = current_node.next;
-        }
-
-        println!(
-            "GlobalAllocatorInner::coalesce: completed, merged {} blocks",
-            merged_count
-        );
-        if merged_count > 0 {
-            self.print_free_list();
-        }
-    }
-
-    // 内部分配函数
-    unsafe fn alloc_impl(&mut self, layout: Layout) -> *mut u8 {
-        println!("GlobalAllocatorInner::alloc_impl: layout={:?}", layout);
+use super::firstfit::FirstFitHeap;
+use super::selftest::{BenchResult, check_heap_against_model, check_resize_in_place, run_bench};
+use super::tlsf::TlsfHeap;
+use super::{ALLOCATOR, stats};
 
-        if !self.initialized {
-            println!("alloc_impl: ERROR - not initialized");
-            return null_mut();
-        }
-
-        // 计算所需总大小（包括头部和对齐）
-        let required_size = layout.size();
-        let align = layout.align().max(MIN_ALIGN);
-
-        // 总大小 = 头部大小 + 对齐后的数据大小
-        let total_needed =
-            core::mem::size_of::<BlockHeader>() + Self::align_up(required_size, align);
-        println!(
-            "alloc_impl: required_size={}, align={}, total_needed={}",
-            required_size, align, total_needed
-        );
-
-        // 遍历空闲链表寻找合适的块
-        let mut prev_ptr: Option<*mut BlockHeader> = None;
-        let mut current_ptr = self.free_list_head;
-        let mut block_index = 0;
-
-        while let Some(mut current) = current_ptr {
-            println!(
-                "alloc_impl: checking block {} at 0x{:08x}",
-                block_index, current as usize
-            );
-            // SAFETY: current 是有效的 BlockHeader 指针
-            let current_node = unsafe { &mut *current };
-            let block_size = current_node.size;
-            println!(
-                "alloc_impl:   block size={}, data_size={}",
-                block_size,
-                current_node.data_size()
-            );
-
-            if block_size >= total_needed {
-                println!("alloc_impl:   FOUND suitable block!");
-
-                // 计算剩余空间
-                let remaining = block_size - total_needed;
-                println!(
-                    "alloc_impl:   remaining space after allocation: {}",
-                    remaining
-                );
-
-                if remaining >= MIN_BLOCK_SIZE {
-                    println!("alloc_impl:   SPLITTING block (remaining >= MIN_BLOCK_SIZE)");
-                    // 分割块：创建新的空闲块
-                    let new_free_addr = (current as usize) + total_needed;
-                    println!("alloc_impl:   new_free_addr=0x{:08x}", new_free_addr);
-                    // SAFETY: new_free_addr 在当前块内部，有效且对齐
-                    let new_free =
-                        unsafe { BlockHeader::from_addr(new_free_addr, remaining, true) };
-
-                    // 链接新空闲块
-                    // SAFETY: new_free 是有效的 BlockHeader 指针
-                    unsafe {
-                        (*new_free).next = current_node.next.take();
-                    }
-
-                    // 从链表中移除当前块或更新大小
-                    if let Some(mut prev) = prev_ptr {
-                        println!("alloc_impl:   updating previous block's next pointer");
-                        // SAFETY: prev 是有效的 BlockHeader 指针
-                        unsafe {
-                            (*prev).next = Some(new_free);
-                        }
-                    } else {
-                        println!("alloc_impl:   updating free_list_head to new free block");
-                        self.free_list_head = Some(new_free);
-                    }
-                } else {
-                    println!("alloc_impl:   USING entire block (remaining < MIN_BLOCK_SIZE)");
-                    // 整个块都被使用，从链表中移除
-                    if let Some(mut prev) = prev_ptr {
-                        println!("alloc_impl:   removing block from middle of list");
-                        // SAFETY: prev 是有效的 BlockHeader 指针
-                        unsafe {
-                            (*prev).next = current_node.next.take();
-                        }
-                    } else {
-                        println!("alloc_impl:   removing block from head of list");
-                        self.free_list_head = current_node.next.take();
-                    }
-                }
-
-                // 更新当前块为已分配状态
-                current_node.size = total_needed;
-                current_node.is_free = false;
-                current_node.next = None;
-
-                let data_addr = current_node.data_addr();
-                println!(
-                    "alloc_impl:   set block at 0x{:08x} as allocated, size={}",
-                    current as usize, total_needed
-                );
-                println!("alloc_impl:   returning data pointer: 0x{:08x}", data_addr);
-                self.print_free_list();
-                return data_addr as *mut u8;
-            } else {
-                println!("alloc_impl:   block too small");
-            }
-
-            // 移动到下一个节点
-            prev_ptr = Some(current);
-            current_ptr = current_node.next;
-            block_index += 1;
-        }
-
-        println!("GlobalAllocatorInner::alloc_impl: NO suitable block found");
-        println!("alloc_impl: free list state before failure:");
-        self.print_free_list();
-        // 没有找到合适的空闲块
-        null_mut()
-    }
-
-    // 内部释放函数
-    unsafe fn dealloc_impl(&mut self, ptr: *mut u8, layout: Layout) {
-        println!(
-            "GlobalAllocatorInner::dealloc_impl: ptr=0x{:p}, layout={:?}",
-            ptr, layout
-        );
-
-        if ptr.is_null() {
-            println!("dealloc_impl: WARNING - null pointer");
-            return;
-        }
-
-        if !self.initialized {
-            println!("dealloc_impl: ERROR - not initialized");
-            return;
-        }
-
-        let data_addr = ptr as usize;
-        let header_addr = data_addr - core::mem::size_of::<BlockHeader>();
-        println!(
-            "dealloc_impl: data_addr=0x{:08x}, header_addr=0x{:08x}",
-            data_addr, header_addr
-        );
-
-        // 获取块头部
-        let block_ptr = header_addr as *mut BlockHeader;
-        // SAFETY: header_addr 应该是有效的 BlockHeader
-        let block = unsafe { &mut *block_ptr };
-
-        // 标记为空闲
-        block.is_free = true;
-        let block_size = block.size;
-        println!(
-            "dealloc_impl: marking block at 0x{:08x} as free, size={}",
-            header_addr, block_size
-        );
-
-        // 按地址顺序插入到空闲链表
-        let insert_addr = block_ptr as usize;
-
-        // 如果链表为空，直接插入
-        if self.free_list_head.is_none() {
-            println!("dealloc_impl: free list empty, inserting as only block");
-            block.next = None;
-            self.free_list_head = Some(block_ptr);
-            // SAFETY: 刚刚初始化了空闲链表
-            unsafe {
-                self.coalesce();
-            }
-            return;
-        }
-
-        // 如果要插入到链表头部
-        if let Some(head) = self.free_list_head {
-            if insert_addr < head as usize {
-                println!(
-                    "dealloc_impl: inserting before head (0x{:08x})",
-                    head as usize
-                );
-                block.next = self.free_list_head;
-                self.free_list_head = Some(block_ptr);
-                // SAFETY: 刚刚修改了空闲链表
-                unsafe {
-                    self.coalesce();
-                }
-                return;
-            }
-        }
-
-        // 遍历链表找到插入位置
-        let mut current_ptr = self.free_list_head;
-        let mut prev_ptr: Option<*mut BlockHeader> = None;
-        let mut position = 0;
-
-        while let Some(mut current) = current_ptr {
-            let current_addr = current as usize;
-            println!(
-                "dealloc_impl: checking position {}: addr=0x{:08x}",
-                position, current_addr
-            );
-
-            if insert_addr < current_addr {
-                println!(
-                    "dealloc_impl: inserting before block at 0x{:08x}",
-                    current_addr
-                );
-                // 插入到当前节点之前
-                block.next = Some(current);
-
-                if let Some(mut prev) = prev_ptr {
-                    println!("dealloc_impl: updating previous block's next pointer");
-                    // SAFETY: prev 是有效的 BlockHeader 指针
-                    unsafe {
-                        (*prev).next = Some(block_ptr);
-                    }
-                }
-                // SAFETY: 刚刚修改了空闲链表
-                unsafe {
-                    self.coalesce();
-                }
-                return;
-            }
-
-            // 移动到下一个节点
-            prev_ptr = Some(current);
-            // SAFETY: current 是有效的 BlockHeader 指针
-            let current_node = unsafe { &mut *current };
-            current_ptr = current_node.next;
-            position += 1;
-        }
-
-        // 插入到链表末尾
-        println!("dealloc_impl: inserting at end of list");
-        if let Some(mut prev) = prev_ptr {
-            // SAFETY: prev 是有效的 BlockHeader 指针
-            unsafe {
-                (*prev).next = Some(block_ptr);
-            }
-        }
-
-        // SAFETY: 刚刚修改了空闲链表
-        unsafe {
-            self.coalesce();
-        }
-
-        println!("dealloc_impl: completed successfully");
-        self.print_free_list();
-    }
-}
-
-// 全局分配器（线程安全包装）
-pub struct GlobalAllocator {
-    inner: UnsafeCell<GlobalAllocatorInner>,
-}
-
-// SAFETY: 在单线程环境中，使用 UnsafeCell 是安全的
-unsafe impl Sync for GlobalAllocator {}
-
-impl GlobalAllocator {
-    pub const fn new() -> Self {
-        Self {
-            inner: UnsafeCell::new(GlobalAllocatorInner::new()),
-        }
-    }
-
-    // 初始化堆内存
-    pub unsafe fn init(&self) {
-        println!("GlobalAllocator::init: starting");
-        // SAFETY: 获取内部状态的可变引用
-        let inner = unsafe { &mut *self.inner.get() };
-        unsafe {
-            inner.init();
-        }
-        println!("GlobalAllocator::init: completed");
-    }
-
-    #[cfg(feature = "alloc-auto-test")]
-    /// for dev fn test
-    pub fn print_free_list(&self) {
-        // SAFETY: 获取内部状态的可变引用
-        let inner = unsafe { &*self.inner.get() };
-        inner.print_free_list()
-    }
-}
-
-// 实现GlobalAlloc trait
-unsafe impl GlobalAlloc for GlobalAllocator {
-    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
-        println!("GlobalAlloc::alloc: layout={:?}", layout);
-        // SAFETY: 获取内部状态的可变引用
-        let inner = unsafe { &mut *self.inner.get() };
-        // SAFETY: 调用者确保这是有效的分配请求
-        let result = unsafe { inner.alloc_impl(layout) };
-        println!("GlobalAlloc::alloc: returning {:?}", result);
-        result
-    }
-
-    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
-        println!("GlobalAlloc::dealloc: ptr=0x{:p}, layout={:?}", ptr, layout);
-        // SAFETY: 获取内部状态的可变引用
-        let inner = unsafe { &mut *self.inner.get() };
-        // SAFETY: 调用者确保这是有效的释放请求
-        unsafe {
-            inner.dealloc_impl(ptr, layout);
-        }
-        println!("GlobalAlloc::dealloc: completed");
-    }
-
-    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
-        println!("GlobalAlloc::alloc_zeroed: layout={:?}", layout);
-        let ptr = unsafe { self.alloc(layout) };
-        if !ptr.is_null() {
-            println!(
-                "GlobalAlloc::alloc_zeroed: zeroing {} bytes at 0x{:p}",
-                layout.size(),
-                ptr
-            );
-            // SAFETY: ptr 是有效的已分配内存
-            unsafe {
-                ptr.write_bytes(0, layout.size());
-            }
-        }
-        println!("GlobalAlloc::alloc_zeroed: returning {:?}", ptr);
-        ptr
-    }
-
-    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
-        println!(
-            "GlobalAlloc::realloc: ptr=0x{:p}, layout={:?}, new_size={}",
-            ptr, layout, new_size
-        );
-
-        if new_size == 0 {
-            println!("GlobalAlloc::realloc: new_size=0, deallocating");
-            unsafe {
-                self.dealloc(ptr, layout);
-            }
-            return null_mut();
-        }
-
-        if ptr.is_null() {
-            println!("GlobalAlloc::realloc: null pointer, allocating new block");
-            let new_layout = Layout::from_size_align(new_size, layout.align()).unwrap_or(layout);
-            return unsafe { self.alloc(new_layout) };
-        }
-
-        // 获取原分配大小
-        let data_addr = ptr as usize;
-        let header_addr = data_addr - core::mem::size_of::<BlockHeader>();
-        // SAFETY: header_addr 是有效的 BlockHeader
-        let block_ptr = header_addr as *const BlockHeader;
-        let old_total_size = unsafe { (*block_ptr).size };
-        let old_data_size = old_total_size - core::mem::size_of::<BlockHeader>();
-
-        println!(
-            "GlobalAlloc::realloc: old_data_size={}, new_size={}",
-            old_data_size, new_size
-        );
-
-        if new_size <= old_data_size {
-            println!("GlobalAlloc::realloc: new_size <= old_data_size, returning same pointer");
-            return ptr;
-        }
-
-        // 分配新内存并复制数据
-        println!("GlobalAlloc::realloc: allocating new larger block");
-        let new_layout = Layout::from_size_align(new_size, layout.align()).unwrap_or(layout);
-        let new_ptr = unsafe { self.alloc(new_layout) };
-
-        if !new_ptr.is_null() {
-            println!(
-                "GlobalAlloc::realloc: copying {} bytes from 0x{:p} to 0x{:p}",
-                old_data_size, ptr, new_ptr
-            );
-            // SAFETY: ptr 和 new_ptr 都是有效的指针，不重叠
-            unsafe {
-                core::ptr::copy_nonoverlapping(ptr, new_ptr, old_data_size);
-                self.dealloc(ptr, layout);
-            }
-        } else {
-            println!("GlobalAlloc::realloc: FAILED to allocate new block");
-        }
-
-        println!("GlobalAlloc::realloc: returning {:?}", new_ptr);
-        new_ptr
-    }
-}
-
-// 全局分配器实例
-#[global_allocator]
-static ALLOCATOR: GlobalAllocator = GlobalAllocator::new();
-
-pub unsafe fn init() {
-    println!("=== ALLOCATOR INIT START ===");
-    // SAFETY: 在系统启动时调用，确保单线程访问
-    unsafe {
-        ALLOCATOR.init();
-    }
-    println!("=== ALLOCATOR INIT COMPLETE ===");
-
-    #[cfg(feature = "alloc-auto-test")]
-    test();
-}
+use core::alloc::Layout;
+use core::sync::atomic::{AtomicUsize, Ordering};
 
 /// 使用一些基础的动态变量测试分配器
-#[cfg(feature = "alloc-auto-test")]
 pub fn test() {
     extern crate alloc;
     use alloc::boxed::Box;
@@ -1884,11 +1237,501 @@ pub fn test() {
         alloc_dbg!("[DEBUG] Test 12: All memory automatically released");
     }
 
+    {
+        // 测试13：超过 MIN_ALIGN 的对齐要求
+        alloc_dbg!("\n=== Test 13: Over-aligned allocations ===");
+        {
+            use alloc::alloc::{alloc, alloc_zeroed, dealloc, realloc};
+            use core::alloc::Layout;
+
+            let before = stats();
+
+            // 每种对齐（1 ~ 4096）都测试若干大小，同时保留前面的块制造不同的起始地址
+            let mut align = 1;
+            while align <= 4096 {
+                let mut blocks = Vec::new();
+                for &size in &[1usize, 3, 16, 100, align, align + 1, 3 * align] {
+                    let layout = Layout::from_size_align(size, align).unwrap();
+                    let ptr = unsafe { alloc(layout) };
+                    assert!(
+                        !ptr.is_null(),
+                        "alloc(size={}, align={}) failed",
+                        size,
+                        align
+                    );
+                    assert_eq!(
+                        ptr as usize % align,
+                        0,
+                        "ptr 0x{:08x} not aligned to {}",
+                        ptr as usize,
+                        align
+                    );
+                    unsafe { ptr.write_bytes((size ^ align) as u8, size) };
+                    blocks.push((ptr, layout));
+                }
+                // 写入的内容互不覆盖
+                for &(ptr, layout) in &blocks {
+                    let pattern = (layout.size() ^ layout.align()) as u8;
+                    for i in 0..layout.size() {
+                        assert_eq!(unsafe { *ptr.add(i) }, pattern, "data corrupted");
+                    }
+                }
+                for (ptr, layout) in blocks {
+                    unsafe { dealloc(ptr, layout) };
+                }
+                alloc_dbg!("✅ align={} verified", align);
+                align <<= 1;
+            }
+
+            // alloc_zeroed / realloc 也要保持对齐和内容
+            let layout = Layout::from_size_align(40, 256).unwrap();
+            unsafe {
+                let ptr = alloc_zeroed(layout);
+                assert!(!ptr.is_null() && ptr as usize % 256 == 0);
+                assert!((0..40).all(|i| *ptr.add(i) == 0), "alloc_zeroed not zeroed");
+                for i in 0..40 {
+                    *ptr.add(i) = i as u8;
+                }
+                let grown = realloc(ptr, layout, 1000);
+                assert!(!grown.is_null() && grown as usize % 256 == 0);
+                assert!(
+                    (0..40).all(|i| *grown.add(i) == i as u8),
+                    "realloc lost data"
+                );
+                dealloc(grown, Layout::from_size_align(1000, 256).unwrap());
+            }
+
+            // 通过 Box 使用对齐的类型
+            #[repr(align(64))]
+            struct CacheLine([u8; 64]);
+            let lines: Vec<Box<CacheLine>> = (0..8).map(|i| Box::new(CacheLine([i; 64]))).collect();
+            for (i, line) in lines.iter().enumerate() {
+                assert_eq!(&**line as *const CacheLine as usize % 64, 0);
+                assert!(line.0.iter().all(|&b| b == i as u8));
+            }
+            drop(lines);
+
+            let after = stats();
+            assert_eq!(after.used, before.used, "over-aligned blocks leaked");
+            assert_eq!(after.allocations, before.allocations);
+            alloc_dbg!("✅ Test 13 passed: Over-aligned allocations verified");
+        }
+    }
+
+    {
+        // 测试14：realloc 原地扩大与收缩
+        alloc_dbg!("\n=== Test 14: In-place realloc ===");
+        {
+            // SAFETY: SCRATCH_HEAP 只在测试中使用，且测试在单线程中运行
+            let region = unsafe { &mut *core::ptr::addr_of_mut!(SCRATCH_HEAP) };
+            check_resize_in_place("first-fit", &mut FirstFitHeap::new(), region);
+            check_resize_in_place("tlsf", &mut TlsfHeap::new(), region);
+
+            // 全局分配器上模拟 UART 接收缓冲区逐步增长，内容必须保持
+            let before = stats();
+            let mut buffer: Vec<u8> = Vec::new();
+            for i in 0..16 * 1024 {
+                buffer.push(i as u8);
+            }
+            assert!(buffer.iter().enumerate().all(|(i, &b)| b == i as u8));
+            buffer.truncate(100);
+            buffer.shrink_to_fit();
+            assert!(buffer.iter().enumerate().all(|(i, &b)| b == i as u8));
+            drop(buffer);
+            assert_eq!(stats().used, before.used, "realloc leaked memory");
+            alloc_dbg!("✅ Test 14 passed: In-place realloc verified");
+        }
+    }
+
+    #[cfg(feature = "alloc-checked")]
+    {
+        // 测试15：堆损坏检测
+        alloc_dbg!("\n=== Test 15: Heap corruption detection ===");
+        {
+            check_corruption_detection();
+
+            let live = super::verify();
+            assert_eq!(live, stats().allocations, "verify() live count mismatch");
+            alloc_dbg!(
+                "✅ Test 15 passed: Heap verified, {} live allocations",
+                live
+            );
+        }
+    }
+
+    {
+        // 测试16：分配失败处理函数
+        alloc_dbg!("\n=== Test 16: OOM handler ===");
+        {
+            let before = stats();
+
+            // 先用缓存占住最大空闲块的大部分，让接下来的大分配必然失败
+            let request = before.largest_free / 2;
+            let cache: Vec<u8> = Vec::with_capacity(before.largest_free / 4 * 3);
+            if stats().largest_free >= request {
+                alloc_dbg!("⚠️ Test 16 skipped: heap too fragmented");
+            } else {
+                unsafe {
+                    *core::ptr::addr_of_mut!(OOM_CACHE) = Some(cache);
+                }
+                super::set_oom_handler(release_cache_on_oom);
+
+                // 处理函数释放缓存后重试成功
+                let big: Vec<u8> = Vec::with_capacity(request);
+                assert_eq!(OOM_CALLS.load(Ordering::Relaxed), 1, "handler not called");
+                assert!(unsafe { (*core::ptr::addr_of!(OOM_CACHE)).is_none() });
+                drop(big);
+
+                // 处理函数无法释放内存时照常失败，只调用一次
+                let layout = Layout::from_size_align(before.total, 4).unwrap();
+                let ptr = unsafe { alloc::alloc::alloc(layout) };
+                assert!(ptr.is_null(), "allocation larger than heap succeeded");
+                assert_eq!(OOM_CALLS.load(Ordering::Relaxed), 2);
+
+                super::clear_oom_handler();
+                let ptr = unsafe { alloc::alloc::alloc(layout) };
+                assert!(ptr.is_null());
+                assert_eq!(
+                    OOM_CALLS.load(Ordering::Relaxed),
+                    2,
+                    "cleared handler called"
+                );
+            }
+
+            assert_eq!(stats().used, before.used, "OOM test leaked memory");
+            alloc_dbg!("✅ Test 16 passed: OOM handler verified");
+        }
+    }
+
+    {
+        // 测试17：独立的 Heap，随机操作与模型对比
+        alloc_dbg!("\n=== Test 17: Standalone Heap vs model ===");
+        {
+            // SAFETY: SCRATCH_HEAP 只在测试中使用，且测试在单线程中运行
+            let region = unsafe { &mut *core::ptr::addr_of_mut!(SCRATCH_HEAP) };
+            check_heap_against_model(region, 0x5EED_0001, 3000);
+            alloc_dbg!("✅ Test 17 passed: Standalone Heap matches model");
+        }
+    }
+
+    #[cfg(feature = "alloc-trace")]
+    {
+        // 测试18：分配跟踪
+        alloc_dbg!("\n=== Test 18: Allocation trace ===");
+        {
+            check_allocation_trace();
+            alloc_dbg!("✅ Test 18 passed: Trace events recorded");
+        }
+    }
+
+    #[cfg(not(feature = "alloc-unlocked"))]
+    {
+        // 测试19：嵌套分配
+        alloc_dbg!("\n=== Test 19: Nested allocation ===");
+        {
+            check_nested_allocation();
+            alloc_dbg!("✅ Test 19 passed: Nested allocation is safe");
+        }
+    }
+
+    {
+        // 测试20：bump 分配器
+        alloc_dbg!("\n=== Test 20: Bump allocator ===");
+        {
+            check_bump_heap();
+            alloc_dbg!("✅ Test 20 passed: Bump allocator verified");
+        }
+    }
+
     alloc_dbg!("[DEBUG] Read/Write correctness test complete");
 
     alloc_dbg!("\n🎉 All tests PASSED! Allocator is working correctly!");
 
     ALLOCATOR.print_free_list();
+    alloc_dbg!("[DEBUG] {}", stats());
 
     alloc_dbg!("[DEBUG] === ALLOCATOR TEST COMPLETE ===");
 }
+
+// 基准测试和原地 realloc 测试使用的独立堆，不影响全局分配器
+const SCRATCH_HEAP_SIZE: usize = 64 * 1024;
+const BENCH_ROUNDS: usize = 4000;
+
+static mut SCRATCH_HEAP: [u8; SCRATCH_HEAP_SIZE] = [0; SCRATCH_HEAP_SIZE];
+
+fn cycles() -> u64 {
+    riscv::register::mcycle::read64()
+}
+
+// 经全局分配器分配、扩大、释放，确认每次操作都在跟踪缓冲区留下一条对应的事件，
+// 且分配出现在存活列表中
+#[cfg(feature = "alloc-trace")]
+fn check_allocation_trace() {
+    use super::{ALLOCATOR, TraceEvent, TraceKind, for_each_event};
+    use core::alloc::GlobalAlloc;
+
+    // 最近一条事件
+    fn last_event() -> Option<TraceEvent> {
+        let mut last = None;
+        for_each_event(|event| last = Some(*event));
+        last
+    }
+
+    // 直接调用全局分配器，不经过 alloc::alloc，确保记录的是这里的调用
+    super::clear_trace();
+    let layout = Layout::from_size_align(40, 8).unwrap();
+    let ptr = unsafe { ALLOCATOR.alloc(layout) };
+    assert!(!ptr.is_null());
+    let event = last_event().expect("alloc not traced");
+    assert_eq!(event.kind, TraceKind::Alloc);
+    assert_eq!(event.addr, ptr as u32);
+    assert_eq!(event.size, 40);
+
+    // 分配出现在存活列表中
+    let mut found = false;
+    ALLOCATOR.for_each_live(|addr, size, _, _| {
+        found |= addr == ptr as usize && size == 40;
+    });
+    assert!(found, "allocation missing from live list");
+    super::dump_live_allocations();
+
+    let grown = unsafe { ALLOCATOR.realloc(ptr, layout, 4000) };
+    assert!(!grown.is_null());
+    let event = last_event().expect("realloc not traced");
+    assert_eq!(event.kind, TraceKind::Realloc);
+    assert_eq!(event.addr, grown as u32);
+    assert_eq!(event.old_addr, ptr as u32);
+    assert_eq!(event.size, 4000);
+
+    let layout = Layout::from_size_align(4000, 8).unwrap();
+    unsafe { ALLOCATOR.dealloc(grown, layout) };
+    let event = last_event().expect("dealloc not traced");
+    assert_eq!(event.kind, TraceKind::Free);
+    assert_eq!(event.addr, grown as u32);
+
+    let mut count = 0;
+    for_each_event(|_| count += 1);
+    assert_eq!(count, 3, "realloc recorded more than one event");
+    super::dump_trace();
+}
+
+#[cfg(not(feature = "alloc-unlocked"))]
+fn check_nested_allocation() {
+    use core::alloc::GlobalAlloc;
+    use riscv::register::mstatus;
+
+    let before = stats();
+    let mie = mstatus::read().mie();
+    let layout = Layout::from_size_align(64, 4).unwrap();
+
+    // 在外层临界区中分配：分配器内部的临界区退出时不能提前打开中断
+    riscv::interrupt::free(|| {
+        let ptr = unsafe { ALLOCATOR.alloc(layout) };
+        assert!(!ptr.is_null());
+        assert!(
+            !mstatus::read().mie(),
+            "interrupts re-enabled by nested critical section"
+        );
+        unsafe { ALLOCATOR.dealloc(ptr, layout) };
+    });
+    assert_eq!(mstatus::read().mie(), mie, "interrupt state not restored");
+
+    // OOM 处理函数在外层分配的临界区中运行，在其中反复分配/释放，
+    // 相当于分配进行到一半时被中断，中断处理函数又去分配
+    NESTED_ALLOCS.store(0, Ordering::Relaxed);
+    super::set_oom_handler(allocate_in_oom_handler);
+    let huge = Layout::from_size_align(before.total, 4).unwrap();
+    let ptr = unsafe { ALLOCATOR.alloc(huge) };
+    super::clear_oom_handler();
+
+    assert!(ptr.is_null(), "allocation larger than heap succeeded");
+    assert_eq!(
+        NESTED_ALLOCS.load(Ordering::Relaxed),
+        NESTED_ROUNDS,
+        "nested allocations failed"
+    );
+    assert_eq!(mstatus::read().mie(), mie, "interrupt state not restored");
+    assert_eq!(stats().used, before.used, "nested allocation leaked memory");
+    #[cfg(feature = "alloc-checked")]
+    super::verify();
+}
+
+#[cfg(not(feature = "alloc-unlocked"))]
+const NESTED_ROUNDS: usize = 16;
+#[cfg(not(feature = "alloc-unlocked"))]
+static NESTED_ALLOCS: AtomicUsize = AtomicUsize::new(0);
+
+#[cfg(not(feature = "alloc-unlocked"))]
+fn allocate_in_oom_handler(_layout: Layout, _stats: &super::HeapStats) {
+    use core::alloc::GlobalAlloc;
+
+    assert!(
+        !riscv::register::mstatus::read().mie(),
+        "OOM handler ran with interrupts enabled"
+    );
+
+    let mut ptrs = [core::ptr::null_mut::<u8>(); NESTED_ROUNDS];
+    for (i, slot) in ptrs.iter_mut().enumerate() {
+        let layout = Layout::from_size_align(16 + i * 8, 4).unwrap();
+        let ptr = unsafe { ALLOCATOR.alloc(layout) };
+        if ptr.is_null() {
+            break;
+        }
+        unsafe { ptr.write_bytes(i as u8, layout.size()) };
+        *slot = ptr;
+    }
+    for (i, &ptr) in ptrs.iter().enumerate() {
+        if ptr.is_null() {
+            continue;
+        }
+        let layout = Layout::from_size_align(16 + i * 8, 4).unwrap();
+        let data = unsafe { core::slice::from_raw_parts(ptr, layout.size()) };
+        if data.iter().all(|&b| b == i as u8) {
+            NESTED_ALLOCS.fetch_add(1, Ordering::Relaxed);
+        }
+        unsafe { ALLOCATOR.dealloc(ptr, layout) };
+    }
+}
+
+fn check_bump_heap() {
+    use super::bump::BumpHeap;
+
+    let mut heap = BumpHeap::new();
+    let layout = Layout::from_size_align(100, 4).unwrap();
+    // SAFETY: SCRATCH_HEAP 只在测试中使用，且测试在单线程中运行
+    unsafe {
+        let start = core::ptr::addr_of_mut!(SCRATCH_HEAP) as usize;
+        heap.init(start, start + SCRATCH_HEAP_SIZE);
+        let initial = heap.stats();
+
+        let a = heap.alloc_impl(layout);
+        let b = heap.alloc_impl(layout);
+        let c = heap.alloc_impl(layout);
+        assert!(!a.is_null() && !b.is_null() && !c.is_null());
+        assert!(a < b && b < c, "bump allocations not increasing");
+        assert_eq!(heap.stats().allocations, 3);
+
+        // 释放中间的块不回收内存
+        let before = heap.stats();
+        heap.dealloc_impl(b, layout);
+        assert_eq!(heap.stats().free, before.free, "middle block reclaimed");
+        assert_eq!(heap.stats().allocations, 2);
+
+        // 只有最近分配的块可以原地扩大
+        assert!(!heap.resize_in_place(a, 200), "non-last block grew");
+        assert!(heap.resize_in_place(c, 1000), "last block failed to grow");
+        assert!(heap.resize_in_place(c, 50), "last block failed to shrink");
+
+        // 释放最近分配的块会退回分配指针
+        heap.dealloc_impl(c, Layout::from_size_align(50, 4).unwrap());
+        let d = heap.alloc_impl(layout);
+        assert_eq!(d, c, "last block not reclaimed");
+        heap.dealloc_impl(d, layout);
+        heap.dealloc_impl(a, layout);
+
+        let stats = heap.stats();
+        assert_eq!(stats.allocations, 0);
+        assert_eq!(stats.used, 0, "used not reset");
+        assert!(
+            stats.free < initial.free,
+            "freed out-of-order block reclaimed"
+        );
+
+        // 重新初始化后恢复整个区域
+        heap.init(start, start + SCRATCH_HEAP_SIZE);
+        assert_eq!(heap.stats().free, initial.free);
+    }
+}
+
+// 在独立的堆上故意制造越界写、重复释放和非法指针，确认都能被检测到
+// （全局分配器检测到后会 panic，所以这里直接调用不会 panic 的检查函数）
+#[cfg(feature = "alloc-checked")]
+fn check_corruption_detection() {
+    use super::checked::CheckedHeap;
+    use core::alloc::Layout;
+
+    let mut heap = CheckedHeap::new();
+    let layout = Layout::from_size_align(13, 1).unwrap();
+    // SAFETY: SCRATCH_HEAP 只在测试中使用，且测试在单线程中运行
+    unsafe {
+        let start = core::ptr::addr_of_mut!(SCRATCH_HEAP) as usize;
+        heap.init(start, start + SCRATCH_HEAP_SIZE);
+
+        let a = heap.alloc_impl(layout);
+        let b = heap.alloc_impl(layout);
+        assert_eq!(heap.try_verify(), Ok(2));
+
+        // 越界写一个字节，破坏尾部金丝雀
+        let saved = *a.add(13);
+        *a.add(13) ^= 0xFF;
+        let err = heap.try_verify().unwrap_err();
+        assert_eq!(err, (a as usize + 13, "tail canary overwritten"));
+        alloc_dbg!("✅ overflow detected: {} at 0x{:08x}", err.1, err.0);
+        *a.add(13) = saved;
+
+        // 释放之后再次释放
+        heap.dealloc_impl(a, layout);
+        let err = heap.check(a).unwrap_err();
+        assert_eq!(err, (a as usize, "double free"));
+        alloc_dbg!("✅ double free detected: {} at 0x{:08x}", err.1, err.0);
+
+        // 堆外的指针和堆内未对齐/非分配起点的指针
+        let local = 0u32;
+        let err = heap.check(&local as *const u32 as *mut u8).unwrap_err();
+        assert_eq!(err.1, "pointer not allocated from heap");
+        assert!(heap.check(b.add(1)).is_err(), "unaligned pointer accepted");
+        assert!(heap.check(b.add(4)).is_err(), "interior pointer accepted");
+        alloc_dbg!("✅ invalid free detected");
+
+        heap.dealloc_impl(b, layout);
+        assert_eq!(heap.try_verify(), Ok(0));
+    }
+}
+
+// 测试16 使用的缓存：分配失败时由处理函数释放
+static mut OOM_CACHE: Option<alloc::vec::Vec<u8>> = None;
+static OOM_CALLS: AtomicUsize = AtomicUsize::new(0);
+
+fn release_cache_on_oom(layout: Layout, stats: &super::HeapStats) {
+    alloc_dbg!(
+        "[OOM] {:?} failed, largest free block {}",
+        layout,
+        stats.largest_free
+    );
+    OOM_CALLS.fetch_add(1, Ordering::Relaxed);
+    // SAFETY: 只在测试中单线程访问
+    unsafe {
+        *core::ptr::addr_of_mut!(OOM_CACHE) = None;
+    }
+}
+
+fn print_bench(name: &str, r: &BenchResult) {
+    alloc_dbg!(
+        "[BENCH] {}: {} allocs (avg {} / worst {} cycles), {} frees (avg {} / worst {} cycles), {} failures",
+        name,
+        r.allocs,
+        r.alloc_avg(),
+        r.alloc_worst,
+        r.frees,
+        r.free_avg(),
+        r.free_worst,
+        r.failures
+    );
+}
+
+/// 在同一组随机操作序列下对比首次适应和 TLSF 的分配/释放耗时（以 CPU 周期计）
+///
+/// 使用独立的 64KB 静态缓冲区，不影响全局分配器
+pub fn bench() {
+    alloc_dbg!("[BENCH] === ALLOCATOR BENCHMARK ===");
+
+    // SAFETY: SCRATCH_HEAP 只在测试中使用，且测试在单线程中运行
+    let region = unsafe { &mut *core::ptr::addr_of_mut!(SCRATCH_HEAP) };
+    let r = run_bench(&mut FirstFitHeap::new(), region, BENCH_ROUNDS, cycles);
+    print_bench("first-fit", &r);
+
+    let r = run_bench(&mut TlsfHeap::new(), region, BENCH_ROUNDS, cycles);
+    print_bench("tlsf", &r);
+
+    alloc_dbg!("[BENCH] === ALLOCATOR BENCHMARK COMPLETE ===");
+}
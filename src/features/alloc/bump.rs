@@ -0,0 +1,256 @@
+//! 指针碰撞（bump）分配器，启用 `alloc-bump` 特性时作为全局分配器
+//!
+//! 每个区域只有一个向前移动的分配指针，分配是 O(1) 的一次比较加一次加法。
+//! 释放不回收内存，只有释放（或收缩）最近分配的那个块时才把指针退回去，
+//! 所以按栈的顺序释放的临时分配不会泄漏。适合只在启动阶段分配、之后不再释放的场景
+
+use core::alloc::Layout;
+use core::ptr::null_mut;
+
+use super::{Counters, HeapStats, MAX_REGIONS, RegionStats, RegionTable, align_up};
+
+// 块头部：只记录块的总大小，用于释放时回退指针和 verify 遍历
+#[repr(C)]
+struct BlockHeader {
+    size: usize, // 块的总大小（包括头部）
+}
+
+const HEADER_SIZE: usize = core::mem::size_of::<BlockHeader>();
+
+// 内存对齐要求
+pub(super) const MIN_ALIGN: usize = core::mem::align_of::<BlockHeader>();
+
+/// 指针碰撞分配器：每个区域从头到尾依次分配，不复用已释放的块
+pub(super) struct BumpHeap {
+    regions: RegionTable,
+    next: [usize; MAX_REGIONS], // 每个区域的下一个可分配地址
+    counters: Counters,
+    initialized: bool,
+}
+
+impl BumpHeap {
+    pub(super) const fn new() -> Self {
+        Self {
+            regions: RegionTable::new(),
+            next: [0; MAX_REGIONS],
+            counters: Counters::new(),
+            initialized: false,
+        }
+    }
+
+    // 初始化堆内存（丢弃已有区域，只保留 [heap_start, heap_end)）
+    pub(super) unsafe fn init(&mut self, heap_start: usize, heap_end: usize) {
+        println!("BumpHeap::init: starting");
+
+        if self.initialized {
+            let allocated = self.regions.allocated();
+            if allocated > 0 {
+                println!(
+                    "BumpHeap::init: ERROR - {} blocks still allocated",
+                    allocated
+                );
+                panic!("Heap already in use");
+            }
+            println!("BumpHeap::init: re-initializing unused heap");
+        }
+
+        self.regions.clear();
+        self.counters.reset();
+        self.initialized = false;
+
+        if heap_end <= heap_start {
+            println!("BumpHeap::init: ERROR - heap_start >= heap_end");
+            panic!("Invalid heap region");
+        }
+
+        // SAFETY: 调用者确保区域有效
+        unsafe {
+            self.add_region(heap_start, heap_end - heap_start);
+        }
+    }
+
+    // 注册一块新的堆区域 [start, start + len)
+    pub(super) unsafe fn add_region(&mut self, start: usize, len: usize) {
+        println!("BumpHeap::add_region: start=0x{:08x}, len={}", start, len);
+
+        let (heap_start, _) = self
+            .regions
+            .push(start, len, MIN_ALIGN, HEADER_SIZE + MIN_ALIGN);
+        self.next[self.regions.len() - 1] = heap_start;
+        self.initialized = true;
+    }
+
+    // 地址所在区域的下标
+    fn region_index(&self, addr: usize) -> Option<usize> {
+        self.regions.iter().position(|r| r.contains(addr))
+    }
+
+    pub(super) unsafe fn alloc_impl(&mut self, layout: Layout) -> *mut u8 {
+        println!("BumpHeap::alloc_impl: layout={:?}", layout);
+
+        let Some(size) = align_up(layout.size(), MIN_ALIGN).checked_add(HEADER_SIZE) else {
+            self.counters.record_failure();
+            return null_mut();
+        };
+
+        let next = &self.next;
+        let found = self
+            .regions
+            .iter()
+            .enumerate()
+            .find(|&(index, region)| region.end - next[index] >= size);
+
+        if let Some((index, _)) = found {
+            let addr = self.next[index];
+
+            // SAFETY: [addr, addr + size) 位于区域内尚未分配的部分
+            unsafe {
+                (*(addr as *mut BlockHeader)).size = size;
+            }
+            self.next[index] = addr + size;
+            self.regions.record_alloc(addr);
+            self.counters.record_alloc(size);
+
+            println!(
+                "BumpHeap::alloc_impl: returning 0x{:08x}",
+                addr + HEADER_SIZE
+            );
+            return (addr + HEADER_SIZE) as *mut u8;
+        }
+
+        println!("BumpHeap::alloc_impl: out of memory");
+        self.counters.record_failure();
+        null_mut()
+    }
+
+    // 释放：只有最近分配的块才会真正归还
+    pub(super) unsafe fn dealloc_impl(&mut self, ptr: *mut u8, _layout: Layout) {
+        if ptr.is_null() {
+            return;
+        }
+
+        let header_addr = ptr as usize - HEADER_SIZE;
+        // SAFETY: ptr 由本分配器分配，头部有效
+        let size = unsafe { (*(header_addr as *const BlockHeader)).size };
+        self.regions.record_free(header_addr);
+        self.counters.record_free(size);
+
+        if let Some(index) = self.region_index(header_addr)
+            && header_addr + size == self.next[index]
+        {
+            println!("BumpHeap::dealloc_impl: rolling back {} bytes", size);
+            self.next[index] = header_addr;
+        }
+    }
+
+    // 原地调整块大小：收缩总是成功（最近分配的块会退回多余部分），
+    // 扩大只有最近分配的块在区域剩余空间足够时才成功
+    pub(super) unsafe fn resize_in_place(&mut self, ptr: *mut u8, new_size: usize) -> bool {
+        let header_addr = ptr as usize - HEADER_SIZE;
+        let header = header_addr as *mut BlockHeader;
+        // SAFETY: ptr 由本分配器分配，头部有效
+        let old_size = unsafe { (*header).size };
+        let total_needed = HEADER_SIZE + align_up(new_size, MIN_ALIGN);
+
+        let Some(index) = self.region_index(header_addr) else {
+            return false;
+        };
+        let is_last = header_addr + old_size == self.next[index];
+
+        if total_needed > old_size {
+            let region_end = self.regions.get(index).map_or(header_addr, |r| r.end);
+            if !is_last || region_end - header_addr < total_needed {
+                return false;
+            }
+        } else if !is_last {
+            // 不是最近分配的块，收缩出来的空间无法复用，保持原大小
+            return true;
+        }
+
+        // SAFETY: 同上
+        unsafe {
+            (*header).size = total_needed;
+        }
+        self.next[index] = header_addr + total_needed;
+        self.counters.record_resize(old_size, total_needed);
+        true
+    }
+
+    // 获取已分配块的可用数据大小（不包括头部）
+    pub(super) unsafe fn usable_size(&self, ptr: *mut u8) -> usize {
+        // SAFETY: 调用者确保 ptr 是本分配器返回的有效指针
+        unsafe { (*((ptr as usize - HEADER_SIZE) as *const BlockHeader)).size - HEADER_SIZE }
+    }
+
+    // 每个区域尾部尚未分配的部分视为一个空闲块
+    pub(super) fn stats(&self) -> HeapStats {
+        let mut stats = self.counters.stats(&self.regions);
+        for (index, region) in self.regions.iter().enumerate() {
+            if region.end > self.next[index] {
+                stats.add_free_block(region.end - self.next[index]);
+            }
+        }
+        stats
+    }
+
+    pub(super) fn region_stats(&self, index: usize) -> Option<RegionStats> {
+        let region = self.regions.get(index)?;
+        let mut stats = region.stats();
+        if region.end > self.next[index] {
+            stats.add_free_block(region.end - self.next[index]);
+        }
+        Some(stats)
+    }
+
+    pub(super) fn region_count(&self) -> usize {
+        self.regions.len()
+    }
+
+    // 地址是否位于堆内
+    pub(super) fn contains(&self, addr: usize) -> bool {
+        self.regions.contains(addr)
+    }
+
+    // 完整遍历堆：每个区域内已分配的部分由块首尾相接铺满，正好结束于分配指针
+    #[cfg(feature = "alloc-checked")]
+    pub(super) fn verify(&self) -> Result<(), (usize, &'static str)> {
+        for (index, region) in self.regions.iter().enumerate() {
+            let next = self.next[index];
+            if next < region.start || next > region.end || !next.is_multiple_of(MIN_ALIGN) {
+                return Err((next, "bump pointer outside region"));
+            }
+
+            let mut addr = region.start;
+            while addr < next {
+                // SAFETY: addr 位于区域已分配的部分，由上一个块的大小推得
+                let size = unsafe { (*(addr as *const BlockHeader)).size };
+                if size < HEADER_SIZE || size % MIN_ALIGN != 0 || size > next - addr {
+                    return Err((addr, "invalid block size"));
+                }
+                addr += size;
+            }
+        }
+
+        Ok(())
+    }
+
+    // 打印每个区域的剩余空间
+    pub(super) fn print_free_list(&self) {
+        println!("Bump regions:");
+        if !self.initialized {
+            println!("  Not initialized");
+            return;
+        }
+
+        for (index, region) in self.regions.iter().enumerate() {
+            println!(
+                "  Region {}: start=0x{:08x}, next=0x{:08x}, end=0x{:08x}, free={}",
+                index,
+                region.start,
+                self.next[index],
+                region.end,
+                region.end - self.next[index]
+            );
+        }
+    }
+}
@@ -0,0 +1,298 @@
+//! 堆损坏检测，启用 `alloc-checked` 特性时包在所选分配器实现外面
+//!
+//! 每个分配在数据之前加一个守护头，在数据之后加 4 字节的尾部金丝雀：
+//!
+//! ```text
+//! [ 底层块头 | Guard { size, prev, next, magic } | data ... | canary ]
+//! ```
+//!
+//! 释放时检查魔数和金丝雀，可以发现重复释放、非法指针释放和越界写；
+//! 所有存活的分配串成双向链表，[`verify`](super::verify) 据此逐个检查，
+//! 再让底层分配器遍历整个堆。发现问题时带着出错地址 panic，经由 crate 的 panic 处理输出
+
+use core::alloc::Layout;
+use core::ptr::null_mut;
+
+use super::{HeapStats, MIN_ALIGN, RawBackend, RegionStats};
+
+// 魔数放在守护头末尾、紧贴数据：底层分配器释放块时会把空闲链表指针写在块的开头，
+// 放在末尾可以让 MAGIC_FREED 在块被重新分配之前一直保留，用于识别重复释放
+#[repr(C)]
+pub(super) struct Guard {
+    size: usize,      // 请求的数据大小
+    prev: *mut Guard, // 存活链表
+    next: *mut Guard,
+    #[cfg(feature = "alloc-trace")]
+    caller: usize, // 分配者的返回地址
+    #[cfg(feature = "alloc-trace")]
+    tick: u32, // 分配时的系统 tick
+    magic: u32,
+}
+
+const MAGIC_LIVE: u32 = 0xA110_C8ED;
+const MAGIC_FREED: u32 = 0xF4EE_D8ED;
+const CANARY: u32 = 0xC0FF_EE11;
+
+const GUARD_SIZE: usize = core::mem::size_of::<Guard>();
+const CANARY_SIZE: usize = core::mem::size_of::<u32>();
+
+// 检测到堆损坏：经由 panic 输出出错地址
+#[cold]
+fn report(addr: usize, what: &str) -> ! {
+    panic!("heap corruption: {} at 0x{:08x}", what, addr);
+}
+
+/// 带损坏检测的分配器
+pub(super) struct CheckedHeap {
+    inner: RawBackend,
+    live: *mut Guard, // 存活分配链表头
+    live_count: usize,
+}
+
+impl CheckedHeap {
+    pub(super) const fn new() -> Self {
+        Self {
+            inner: RawBackend::new(),
+            live: null_mut(),
+            live_count: 0,
+        }
+    }
+
+    pub(super) unsafe fn init(&mut self, heap_start: usize, heap_end: usize) {
+        // SAFETY: 调用者确保区域有效；还有分配时底层分配器会 panic
+        unsafe {
+            self.inner.init(heap_start, heap_end);
+        }
+        self.live = null_mut();
+        self.live_count = 0;
+    }
+
+    pub(super) unsafe fn add_region(&mut self, start: usize, len: usize) {
+        // SAFETY: 调用者确保区域有效
+        unsafe {
+            self.inner.add_region(start, len);
+        }
+    }
+
+    pub(super) unsafe fn alloc_impl(&mut self, layout: Layout) -> *mut u8 {
+        let Some(total) = layout
+            .size()
+            .checked_add(GUARD_SIZE + CANARY_SIZE)
+            .and_then(|size| Layout::from_size_align(size, layout.align()).ok())
+        else {
+            return null_mut();
+        };
+
+        // SAFETY: total 是有效的布局
+        let raw = unsafe { self.inner.alloc_impl(total) };
+        if raw.is_null() {
+            return raw;
+        }
+
+        let guard = raw as *mut Guard;
+        // SAFETY: raw 指向刚分配的、至少 total 字节的块
+        unsafe {
+            (*guard).size = layout.size();
+            (*guard).prev = null_mut();
+            (*guard).next = self.live;
+            (*guard).magic = MAGIC_LIVE;
+            #[cfg(feature = "alloc-trace")]
+            {
+                (*guard).caller = super::trace::current_caller();
+                (*guard).tick = super::trace::tick();
+            }
+            if !self.live.is_null() {
+                (*self.live).prev = guard;
+            }
+            self.live = guard;
+            self.live_count += 1;
+
+            let data = raw.add(GUARD_SIZE);
+            write_canary(data, layout.size());
+            data
+        }
+    }
+
+    pub(super) unsafe fn dealloc_impl(&mut self, ptr: *mut u8, layout: Layout) {
+        if ptr.is_null() {
+            return;
+        }
+
+        let guard = match self.check(ptr) {
+            Ok(guard) => guard,
+            Err((addr, what)) => report(addr, what),
+        };
+
+        // SAFETY: guard 已通过检查
+        unsafe {
+            if (*guard).size != layout.size() {
+                report(ptr as usize, "dealloc layout size mismatch");
+            }
+
+            self.unlink(guard);
+            (*guard).magic = MAGIC_FREED;
+
+            let total = Layout::from_size_align_unchecked(
+                layout.size() + GUARD_SIZE + CANARY_SIZE,
+                layout.align(),
+            );
+            self.inner.dealloc_impl(guard as *mut u8, total);
+        }
+    }
+
+    pub(super) unsafe fn resize_in_place(&mut self, ptr: *mut u8, new_size: usize) -> bool {
+        let guard = match self.check(ptr) {
+            Ok(guard) => guard,
+            Err((addr, what)) => report(addr, what),
+        };
+
+        // SAFETY: guard 已通过检查
+        unsafe {
+            if !self
+                .inner
+                .resize_in_place(guard as *mut u8, new_size + GUARD_SIZE + CANARY_SIZE)
+            {
+                return false;
+            }
+            (*guard).size = new_size;
+            write_canary(ptr, new_size);
+        }
+        true
+    }
+
+    pub(super) unsafe fn usable_size(&self, ptr: *mut u8) -> usize {
+        // SAFETY: 调用者确保 ptr 是本分配器返回的有效指针
+        unsafe { self.inner.usable_size(ptr.sub(GUARD_SIZE)) - GUARD_SIZE - CANARY_SIZE }
+    }
+
+    // 检查一个由用户传入的数据指针：在堆内、对齐、魔数正确、金丝雀完好
+    pub(super) fn check(&self, ptr: *mut u8) -> Result<*mut Guard, (usize, &'static str)> {
+        let data = ptr as usize;
+        let guard_addr = data.wrapping_sub(GUARD_SIZE);
+        if !data.is_multiple_of(MIN_ALIGN) || !self.inner.contains(guard_addr) {
+            return Err((data, "pointer not allocated from heap"));
+        }
+
+        let guard = guard_addr as *mut Guard;
+        // SAFETY: guard 位于堆内且对齐
+        unsafe {
+            match (*guard).magic {
+                MAGIC_LIVE => {}
+                MAGIC_FREED => return Err((data, "double free")),
+                _ => return Err((data, "invalid pointer or corrupted header")),
+            }
+            let canary_end = data
+                .saturating_add((*guard).size)
+                .saturating_add(CANARY_SIZE - 1);
+            if !self.inner.contains(canary_end) {
+                return Err((data, "corrupted header size"));
+            }
+            if read_canary(ptr, (*guard).size) != CANARY {
+                return Err((data + (*guard).size, "tail canary overwritten"));
+            }
+        }
+        Ok(guard)
+    }
+
+    // 从存活链表中摘除
+    unsafe fn unlink(&mut self, guard: *mut Guard) {
+        // SAFETY: 调用者确保 guard 在存活链表中
+        unsafe {
+            let prev = (*guard).prev;
+            let next = (*guard).next;
+            if !next.is_null() {
+                (*next).prev = prev;
+            }
+            if prev.is_null() {
+                self.live = next;
+            } else {
+                (*prev).next = next;
+            }
+        }
+        self.live_count -= 1;
+    }
+
+    // 检查全部存活分配以及底层堆结构，返回第一个发现的问题
+    pub(super) fn try_verify(&self) -> Result<usize, (usize, &'static str)> {
+        let mut count = 0;
+        let mut prev: *mut Guard = null_mut();
+        let mut guard = self.live;
+        while !guard.is_null() {
+            if count >= self.live_count {
+                return Err((guard as usize, "live allocation list corrupted"));
+            }
+            if !self.inner.contains(guard as usize) {
+                return Err((guard as usize, "live allocation list corrupted"));
+            }
+            let data = guard as usize + GUARD_SIZE;
+            self.check(data as *mut u8)?;
+            // SAFETY: guard 已通过检查
+            unsafe {
+                if (*guard).prev != prev {
+                    return Err((data, "live allocation list corrupted"));
+                }
+                prev = guard;
+                guard = (*guard).next;
+            }
+            count += 1;
+        }
+        if count != self.live_count {
+            return Err((prev as usize, "live allocation list truncated"));
+        }
+
+        self.inner.verify()?;
+        Ok(count)
+    }
+
+    pub(super) fn verify(&self) -> usize {
+        match self.try_verify() {
+            Ok(count) => count,
+            Err((addr, what)) => report(addr, what),
+        }
+    }
+
+    // 遍历存活分配：(数据地址, 大小, 调用者, tick)，最新的在前
+    #[cfg(feature = "alloc-trace")]
+    pub(super) fn for_each_live(&self, mut f: impl FnMut(usize, usize, usize, u32)) {
+        let mut guard = self.live;
+        while !guard.is_null() {
+            // SAFETY: 存活链表中的守护头都有效
+            unsafe {
+                f(
+                    guard as usize + GUARD_SIZE,
+                    (*guard).size,
+                    (*guard).caller,
+                    (*guard).tick,
+                );
+                guard = (*guard).next;
+            }
+        }
+    }
+
+    pub(super) fn stats(&self) -> HeapStats {
+        self.inner.stats()
+    }
+
+    pub(super) fn region_stats(&self, index: usize) -> Option<RegionStats> {
+        self.inner.region_stats(index)
+    }
+
+    pub(super) fn region_count(&self) -> usize {
+        self.inner.region_count()
+    }
+
+    pub(super) fn print_free_list(&self) {
+        self.inner.print_free_list()
+    }
+}
+
+// 金丝雀紧跟在数据之后，不一定对齐
+unsafe fn write_canary(data: *mut u8, size: usize) {
+    // SAFETY: 调用者确保 data + size 之后还有 CANARY_SIZE 字节
+    unsafe { (data.add(size) as *mut u32).write_unaligned(CANARY) }
+}
+
+unsafe fn read_canary(data: *mut u8, size: usize) -> u32 {
+    // SAFETY: 同上
+    unsafe { (data.add(size) as *const u32).read_unaligned() }
+}
@@ -0,0 +1,636 @@
+//! 首次适应（first-fit）分配器，默认的全局分配器实现（`alloc-firstfit`）
+
+use core::alloc::Layout;
+use core::ptr::null_mut;
+
+use super::{Counters, HeapStats, RegionStats, RegionTable, align_up};
+
+// 内存对齐要求
+pub(super) const MIN_ALIGN: usize = core::mem::align_of::<BlockHeader>();
+const MIN_BLOCK_SIZE: usize = core::mem::size_of::<BlockHeader>() + MIN_ALIGN;
+
+// 块头部信息（所有块共用）
+#[repr(C)]
+struct BlockHeader {
+    size: usize,                    // 块的总大小（包括头部）
+    is_free: bool,                  // 是否空闲
+    next: Option<*mut BlockHeader>, // 仅用于空闲链表
+}
+
+impl BlockHeader {
+    // 从地址创建节点
+    unsafe fn from_addr(addr: usize, size: usize, is_free: bool) -> *mut BlockHeader {
+        println!(
+            "BlockHeader::from_addr: addr=0x{:08x}, size={}, is_free={}",
+            addr, size, is_free
+        );
+        let ptr = addr as *mut BlockHeader;
+        // SAFETY: 调用者确保地址有效且对齐
+        unsafe {
+            (*ptr).size = size;
+            (*ptr).is_free = is_free;
+            (*ptr).next = None;
+        }
+        println!(
+            "BlockHeader::from_addr: created at 0x{:08x} with size={}",
+            ptr as usize,
+            unsafe { (*ptr).size }
+        );
+        ptr
+    }
+
+    // 获取数据区域起始地址
+    fn data_addr(&self) -> usize {
+        (self as *const _ as usize) + core::mem::size_of::<Self>()
+    }
+
+    // 获取块结束地址
+    fn end_addr(&self) -> usize {
+        (self as *const _ as usize) + self.size
+    }
+
+    // 获取可用数据大小（不包括头部）
+    fn data_size(&self) -> usize {
+        self.size - core::mem::size_of::<Self>()
+    }
+}
+
+/// 首次适应（first-fit）分配器：按地址排序的单向空闲链表，释放时合并相邻块
+///
+/// 行为与 `linked_list_allocator` 一致，分配和释放都需要遍历空闲链表
+pub(super) struct FirstFitHeap {
+    free_list_head: Option<*mut BlockHeader>,
+    regions: RegionTable,
+    counters: Counters,
+    initialized: bool,
+}
+
+impl FirstFitHeap {
+    pub(super) const fn new() -> Self {
+        Self {
+            free_list_head: None,
+            regions: RegionTable::new(),
+            counters: Counters::new(),
+            initialized: false,
+        }
+    }
+
+    // 初始化堆内存（丢弃已有区域，只保留 [heap_start, heap_end)）
+    pub(super) unsafe fn init(&mut self, heap_start: usize, heap_end: usize) {
+        println!("FirstFitHeap::init: starting");
+
+        if self.initialized {
+            let allocated = self.regions.allocated();
+            if allocated > 0 {
+                println!(
+                    "FirstFitHeap::init: ERROR - {} blocks still allocated",
+                    allocated
+                );
+                panic!("Heap already in use");
+            }
+            println!("FirstFitHeap::init: re-initializing unused heap");
+        }
+
+        self.free_list_head = None;
+        self.regions.clear();
+        self.counters.reset();
+        self.initialized = false;
+
+        if heap_end <= heap_start {
+            println!("FirstFitHeap::init: ERROR - heap_start >= heap_end");
+            panic!("Invalid heap region");
+        }
+
+        // SAFETY: 调用者确保区域有效
+        unsafe {
+            self.add_region(heap_start, heap_end - heap_start);
+        }
+    }
+
+    // 注册一块新的堆区域 [start, start + len)
+    pub(super) unsafe fn add_region(&mut self, start: usize, len: usize) {
+        println!(
+            "FirstFitHeap::add_region: start=0x{:08x}, len={}",
+            start, len
+        );
+
+        // 起始地址向上、结束地址向下对齐，保证块头部对齐
+        let (heap_start, heap_end) = self.regions.push(start, len, MIN_ALIGN, MIN_BLOCK_SIZE);
+        let heap_size = heap_end - heap_start;
+        println!(
+            "FirstFitHeap::add_region: heap_start=0x{:08x}, heap_end=0x{:08x}, heap_size={}",
+            heap_start, heap_end, heap_size
+        );
+        self.initialized = true;
+
+        // 将整个区域作为一个空闲块按地址插入空闲链表
+        // SAFETY: heap_start 有效且对齐
+        let free_node = unsafe { BlockHeader::from_addr(heap_start, heap_size, true) };
+        unsafe {
+            self.insert_free(free_node);
+        }
+
+        println!(
+            "FirstFitHeap::add_region: region {} added with free block at 0x{:08x}",
+            self.regions.len() - 1,
+            free_node as usize
+        );
+        self.print_free_list();
+    }
+
+    // 遍历所有空闲块：(地址, 大小)
+    fn for_each_free(&self, mut f: impl FnMut(usize, usize)) {
+        let mut current_ptr = self.free_list_head;
+        while let Some(current) = current_ptr {
+            // SAFETY: 只读遍历空闲链表
+            let node = unsafe { &*current };
+            f(current as usize, node.size);
+            current_ptr = node.next;
+        }
+    }
+
+    // 统计整个堆：计数器直接读取，只有最大空闲块和空闲块数需要遍历空闲链表
+    pub(super) fn stats(&self) -> HeapStats {
+        let mut stats = self.counters.stats(&self.regions);
+        self.for_each_free(|_, size| stats.add_free_block(size));
+        stats
+    }
+
+    // 统计指定区域
+    pub(super) fn region_stats(&self, index: usize) -> Option<RegionStats> {
+        let region = self.regions.get(index)?;
+        let mut stats = region.stats();
+        self.for_each_free(|addr, size| {
+            if region.contains(addr) {
+                stats.add_free_block(size);
+            }
+        });
+        Some(stats)
+    }
+
+    // 区域数量
+    pub(super) fn region_count(&self) -> usize {
+        self.regions.len()
+    }
+
+    // 获取已分配块的可用数据大小（不包括头部）
+    pub(super) unsafe fn usable_size(&self, ptr: *mut u8) -> usize {
+        let header_addr = ptr as usize - core::mem::size_of::<BlockHeader>();
+        // SAFETY: 调用者确保 ptr 是本分配器返回的有效指针
+        unsafe { (*(header_addr as *const BlockHeader)).data_size() }
+    }
+
+    // 地址是否位于堆内
+    pub(super) fn contains(&self, addr: usize) -> bool {
+        self.regions.contains(addr)
+    }
+
+    // 完整遍历堆：空闲链表按地址递增、都在堆内且标记为空闲；
+    // 每个区域内的块首尾相接，正好铺满整个区域。出错时返回 (地址, 原因)
+    #[cfg(feature = "alloc-checked")]
+    pub(super) fn verify(&self) -> Result<(), (usize, &'static str)> {
+        let mut last_end = 0;
+        let mut current_ptr = self.free_list_head;
+        while let Some(current) = current_ptr {
+            let addr = current as usize;
+            if !self.regions.contains(addr) || !addr.is_multiple_of(MIN_ALIGN) {
+                return Err((addr, "free list entry outside heap"));
+            }
+            if addr < last_end {
+                return Err((addr, "free list out of order"));
+            }
+            // SAFETY: 地址已确认位于堆内且对齐
+            let node = unsafe { &*current };
+            if !node.is_free {
+                return Err((addr, "free list entry not marked free"));
+            }
+            last_end = addr + node.size;
+            current_ptr = node.next;
+        }
+
+        for region in self.regions.iter() {
+            let mut addr = region.start;
+            while addr < region.end {
+                // SAFETY: addr 位于区域内，由上一个块的大小推得
+                let node = unsafe { &*(addr as *const BlockHeader) };
+                if node.size < core::mem::size_of::<BlockHeader>()
+                    || node.size % MIN_ALIGN != 0
+                    || node.size > region.end - addr
+                {
+                    return Err((addr, "invalid block size"));
+                }
+                addr += node.size;
+            }
+        }
+
+        Ok(())
+    }
+
+    // 打印空闲链表状态
+    pub(super) fn print_free_list(&self) {
+        println!("Free list status:");
+        if !self.initialized {
+            println!("  Not initialized");
+            return;
+        }
+
+        let mut count = 0;
+        let mut current_ptr = self.free_list_head;
+        while let Some(mut current) = current_ptr {
+            // SAFETY: 仅用于调试输出，不会修改
+            unsafe {
+                let node = &*current;
+                println!(
+                    "  Block {}: addr=0x{:08x}, size={}, data_size={}, next={:?}",
+                    count,
+                    current as usize,
+                    node.size,
+                    node.data_size(),
+                    node.next.map(|p| p as usize)
+                );
+                current_ptr = node.next;
+            }
+            count += 1;
+        }
+        println!("  Total free blocks: {}", count);
+    }
+
+    // 合并相邻空闲块
+    unsafe fn coalesce(&mut self) {
+        println!("FirstFitHeap::coalesce: starting");
+        let mut current_ptr = self.free_list_head;
+        let mut prev_ptr: Option<*mut BlockHeader> = None;
+        let mut merged_count = 0;
+
+        while let Some(mut current) = current_ptr {
+            // SAFETY: current 是有效的 BlockHeader 指针
+            let current_node = unsafe { &mut *current };
+            let node_end = current_node.end_addr();
+            println!(
+                "coalesce: checking block at 0x{:08x}, end=0x{:08x}",
+                current as usize, node_end
+            );
+
+            // 检查是否可以与下一个块合并
+            if let Some(next_ptr) = current_node.next {
+                let next_addr = next_ptr as usize;
+                println!("coalesce:   next block at 0x{:08x}", next_addr);
+
+                // 区域边界处即使地址相邻也不能合并
+                let at_region_end = self.regions.is_region_end(node_end);
+
+                if node_end == next_addr && !at_region_end {
+                    println!(
+                        "coalesce:   MERGING blocks: 0x{:08x} + 0x{:08x}",
+                        current as usize, next_addr
+                    );
+                    // SAFETY: next_ptr 是有效的 BlockHeader 指针
+                    let next_node = unsafe { &mut *next_ptr };
+                    current_node.size += next_node.size;
+                    current_node.next = next_node.next.take();
+                    merged_count += 1;
+                    println!("coalesce:   new size: {}", current_node.size);
+                    // 重新检查这个位置，因为可能还能继续合并
+                    continue;
+                }
+            }
+
+            prev_ptr = Some(current);
+            current_ptr = current_node.next;
+        }
+
+        println!(
+            "FirstFitHeap::coalesce: completed, merged {} blocks",
+            merged_count
+        );
+        if merged_count > 0 {
+            self.print_free_list();
+        }
+    }
+
+    // 内部分配函数
+    pub(super) unsafe fn alloc_impl(&mut self, layout: Layout) -> *mut u8 {
+        println!("FirstFitHeap::alloc_impl: layout={:?}", layout);
+
+        if !self.initialized {
+            println!("alloc_impl: ERROR - not initialized");
+            self.counters.record_failure();
+            return null_mut();
+        }
+
+        // 计算所需总大小（包括头部和对齐）
+        let required_size = layout.size();
+        let align = layout.align().max(MIN_ALIGN);
+
+        // 总大小 = 头部大小 + 对齐后的数据大小
+        let total_needed = core::mem::size_of::<BlockHeader>() + align_up(required_size, align);
+        println!(
+            "alloc_impl: required_size={}, align={}, total_needed={}",
+            required_size, align, total_needed
+        );
+
+        // 遍历空闲链表寻找合适的块
+        let mut prev_ptr: Option<*mut BlockHeader> = None;
+        let mut current_ptr = self.free_list_head;
+        let mut block_index = 0;
+
+        while let Some(mut current) = current_ptr {
+            println!(
+                "alloc_impl: checking block {} at 0x{:08x}",
+                block_index, current as usize
+            );
+            // SAFETY: current 是有效的 BlockHeader 指针
+            let current_node = unsafe { &mut *current };
+            let block_size = current_node.size;
+            println!(
+                "alloc_impl:   block size={}, data_size={}",
+                block_size,
+                current_node.data_size()
+            );
+
+            if block_size >= total_needed {
+                println!("alloc_impl:   FOUND suitable block!");
+
+                // 计算剩余空间
+                let remaining = block_size - total_needed;
+                println!(
+                    "alloc_impl:   remaining space after allocation: {}",
+                    remaining
+                );
+
+                if remaining >= MIN_BLOCK_SIZE {
+                    println!("alloc_impl:   SPLITTING block (remaining >= MIN_BLOCK_SIZE)");
+                    // 分割块：创建新的空闲块
+                    let new_free_addr = (current as usize) + total_needed;
+                    println!("alloc_impl:   new_free_addr=0x{:08x}", new_free_addr);
+                    // SAFETY: new_free_addr 在当前块内部，有效且对齐
+                    let new_free =
+                        unsafe { BlockHeader::from_addr(new_free_addr, remaining, true) };
+
+                    // 链接新空闲块
+                    // SAFETY: new_free 是有效的 BlockHeader 指针
+                    unsafe {
+                        (*new_free).next = current_node.next.take();
+                    }
+
+                    // 从链表中移除当前块或更新大小
+                    if let Some(mut prev) = prev_ptr {
+                        println!("alloc_impl:   updating previous block's next pointer");
+                        // SAFETY: prev 是有效的 BlockHeader 指针
+                        unsafe {
+                            (*prev).next = Some(new_free);
+                        }
+                    } else {
+                        println!("alloc_impl:   updating free_list_head to new free block");
+                        self.free_list_head = Some(new_free);
+                    }
+
+                    current_node.size = total_needed;
+                } else {
+                    println!("alloc_impl:   USING entire block (remaining < MIN_BLOCK_SIZE)");
+                    // 整个块都被使用，从链表中移除
+                    if let Some(mut prev) = prev_ptr {
+                        println!("alloc_impl:   removing block from middle of list");
+                        // SAFETY: prev 是有效的 BlockHeader 指针
+                        unsafe {
+                            (*prev).next = current_node.next.take();
+                        }
+                    } else {
+                        println!("alloc_impl:   removing block from head of list");
+                        self.free_list_head = current_node.next.take();
+                    }
+                }
+
+                // 更新当前块为已分配状态（不分割时保留整个块的大小，避免尾部泄漏）
+                current_node.is_free = false;
+                current_node.next = None;
+
+                self.regions.record_alloc(current as usize);
+                self.counters.record_alloc(current_node.size);
+
+                let data_addr = current_node.data_addr();
+                println!(
+                    "alloc_impl:   set block at 0x{:08x} as allocated, size={}",
+                    current as usize, current_node.size
+                );
+                println!("alloc_impl:   returning data pointer: 0x{:08x}", data_addr);
+                self.print_free_list();
+                return data_addr as *mut u8;
+            } else {
+                println!("alloc_impl:   block too small");
+            }
+
+            // 移动到下一个节点
+            prev_ptr = Some(current);
+            current_ptr = current_node.next;
+            block_index += 1;
+        }
+
+        self.counters.record_failure();
+        println!("FirstFitHeap::alloc_impl: NO suitable block found");
+        println!("alloc_impl: free list state before failure:");
+        self.print_free_list();
+        // 没有找到合适的空闲块
+        null_mut()
+    }
+
+    // 内部释放函数
+    pub(super) unsafe fn dealloc_impl(&mut self, ptr: *mut u8, layout: Layout) {
+        println!(
+            "FirstFitHeap::dealloc_impl: ptr=0x{:p}, layout={:?}",
+            ptr, layout
+        );
+
+        if ptr.is_null() {
+            println!("dealloc_impl: WARNING - null pointer");
+            return;
+        }
+
+        if !self.initialized {
+            println!("dealloc_impl: ERROR - not initialized");
+            return;
+        }
+
+        let data_addr = ptr as usize;
+        let header_addr = data_addr - core::mem::size_of::<BlockHeader>();
+        println!(
+            "dealloc_impl: data_addr=0x{:08x}, header_addr=0x{:08x}",
+            data_addr, header_addr
+        );
+
+        // 获取块头部
+        let block_ptr = header_addr as *mut BlockHeader;
+        // SAFETY: header_addr 应该是有效的 BlockHeader
+        let block = unsafe { &mut *block_ptr };
+
+        // 标记为空闲
+        block.is_free = true;
+        self.regions.record_free(header_addr);
+        let block_size = block.size;
+        self.counters.record_free(block_size);
+        println!(
+            "dealloc_impl: marking block at 0x{:08x} as free, size={}",
+            header_addr, block_size
+        );
+
+        // SAFETY: block_ptr 是刚释放的有效块
+        unsafe {
+            self.insert_free(block_ptr);
+            self.coalesce();
+        }
+
+        println!("dealloc_impl: completed successfully");
+        self.print_free_list();
+    }
+
+    // 原地调整已分配块的大小，使数据区至少有 new_size 字节
+    //
+    // 收缩时把多余的尾部切成空闲块还给链表；扩大时吞并地址上紧随其后的空闲块，
+    // 吞并后多出来的部分同样切回链表。无法原地扩大时返回 false，块保持不变
+    pub(super) unsafe fn resize_in_place(&mut self, ptr: *mut u8, new_size: usize) -> bool {
+        println!(
+            "FirstFitHeap::resize_in_place: ptr=0x{:p}, new_size={}",
+            ptr, new_size
+        );
+
+        let header_addr = ptr as usize - core::mem::size_of::<BlockHeader>();
+        let block_ptr = header_addr as *mut BlockHeader;
+        // SAFETY: ptr 由本分配器分配，头部有效
+        let block = unsafe { &mut *block_ptr };
+        let old_size = block.size;
+        let total_needed = core::mem::size_of::<BlockHeader>() + align_up(new_size, MIN_ALIGN);
+
+        if total_needed > block.size {
+            let block_end = block.end_addr();
+            if self.regions.is_region_end(block_end) {
+                println!("resize_in_place: block is at region end, cannot grow");
+                return false;
+            }
+
+            // 在空闲链表中查找紧随其后的空闲块
+            let mut prev_ptr: Option<*mut BlockHeader> = None;
+            let mut current_ptr = self.free_list_head;
+            while let Some(current) = current_ptr {
+                if current as usize >= block_end {
+                    break;
+                }
+                prev_ptr = Some(current);
+                // SAFETY: current 是有效的 BlockHeader 指针
+                current_ptr = unsafe { (*current).next };
+            }
+
+            let Some(next_ptr) = current_ptr.filter(|&n| n as usize == block_end) else {
+                println!("resize_in_place: next block is not free");
+                return false;
+            };
+            // SAFETY: next_ptr 是有效的空闲块
+            let next = unsafe { &mut *next_ptr };
+            if block.size + next.size < total_needed {
+                println!("resize_in_place: next free block too small");
+                return false;
+            }
+
+            // 从空闲链表中摘除并吞并
+            match prev_ptr {
+                // SAFETY: prev 是有效的 BlockHeader 指针
+                Some(prev) => unsafe { (*prev).next = next.next.take() },
+                None => self.free_list_head = next.next.take(),
+            }
+            block.size += next.size;
+            println!("resize_in_place: absorbed next block, size={}", block.size);
+        }
+
+        // 多余的尾部切回空闲链表
+        let remaining = block.size - total_needed;
+        if remaining >= MIN_BLOCK_SIZE {
+            let tail_addr = header_addr + total_needed;
+            // SAFETY: tail_addr 位于当前块内部，有效且对齐
+            unsafe {
+                let tail = BlockHeader::from_addr(tail_addr, remaining, true);
+                block.size = total_needed;
+                self.insert_free(tail);
+                self.coalesce();
+            }
+            println!("resize_in_place: released {} bytes", remaining);
+        }
+
+        self.counters.record_resize(old_size, block.size);
+        true
+    }
+
+    // 按地址顺序将空闲块插入空闲链表（不合并）
+    unsafe fn insert_free(&mut self, block_ptr: *mut BlockHeader) {
+        let insert_addr = block_ptr as usize;
+        // SAFETY: 调用者确保 block_ptr 是有效的 BlockHeader 指针
+        let block = unsafe { &mut *block_ptr };
+
+        // 如果链表为空，直接插入
+        if self.free_list_head.is_none() {
+            println!("insert_free: free list empty, inserting as only block");
+            block.next = None;
+            self.free_list_head = Some(block_ptr);
+            return;
+        }
+
+        // 如果要插入到链表头部
+        if let Some(head) = self.free_list_head
+            && insert_addr < head as usize
+        {
+            println!(
+                "insert_free: inserting before head (0x{:08x})",
+                head as usize
+            );
+            block.next = self.free_list_head;
+            self.free_list_head = Some(block_ptr);
+            return;
+        }
+
+        // 遍历链表找到插入位置
+        let mut current_ptr = self.free_list_head;
+        let mut prev_ptr: Option<*mut BlockHeader> = None;
+        let mut position = 0;
+
+        while let Some(mut current) = current_ptr {
+            let current_addr = current as usize;
+            println!(
+                "insert_free: checking position {}: addr=0x{:08x}",
+                position, current_addr
+            );
+
+            if insert_addr < current_addr {
+                println!(
+                    "insert_free: inserting before block at 0x{:08x}",
+                    current_addr
+                );
+                // 插入到当前节点之前
+                block.next = Some(current);
+
+                if let Some(mut prev) = prev_ptr {
+                    println!("insert_free: updating previous block's next pointer");
+                    // SAFETY: prev 是有效的 BlockHeader 指针
+                    unsafe {
+                        (*prev).next = Some(block_ptr);
+                    }
+                }
+                return;
+            }
+
+            // 移动到下一个节点
+            prev_ptr = Some(current);
+            // SAFETY: current 是有效的 BlockHeader 指针
+            let current_node = unsafe { &mut *current };
+            current_ptr = current_node.next;
+            position += 1;
+        }
+
+        // 插入到链表末尾
+        println!("insert_free: inserting at end of list");
+        block.next = None;
+        if let Some(mut prev) = prev_ptr {
+            // SAFETY: prev 是有效的 BlockHeader 指针
+            unsafe {
+                (*prev).next = Some(block_ptr);
+            }
+        }
+    }
+}
@@ -0,0 +1,232 @@
+//! 与链接脚本和 `#[global_allocator]` 无关的堆，可以管理任意几块内存
+//!
+//! 全局分配器本身也是一个 [`Heap`]，只是区域来自 `_heap_start` / `HEAP_END`。
+//! 应用可以另外在静态数组上建独立的堆（例如给某个任务专用的内存池），
+//! 分配算法由 `alloc-tlsf` / `alloc-bump` / `alloc-checked` 等特性决定，与全局分配器一致
+
+use core::alloc::Layout;
+use core::marker::PhantomData;
+use core::ptr::NonNull;
+
+use super::{Backend, HeapStats, MIN_ALIGN, RegionStats, align_up};
+
+// 超过 MIN_ALIGN 的对齐要求在这里处理，底层分配器只保证 MIN_ALIGN：
+//
+//   raw                      aligned
+//   |<------ padding ------->|
+//   [ ...        | back ptr  | data ...              ]
+//
+// 多申请 align 字节，把数据地址向上对齐，并在数据之前的一个 usize 中保存 raw，
+// 释放时凭 layout.align() 得知需要通过回指针找到底层分配的块
+const BACK_PTR_SIZE: usize = core::mem::size_of::<usize>();
+
+fn is_over_aligned(layout: &Layout) -> bool {
+    layout.align() > MIN_ALIGN
+}
+
+// 向底层分配器申请的布局：padding 最多为 align - MIN_ALIGN + BACK_PTR_SIZE <= align
+fn padded_layout(layout: &Layout) -> Option<Layout> {
+    let size = layout.size().checked_add(layout.align())?;
+    Layout::from_size_align(size, MIN_ALIGN).ok()
+}
+
+// 在底层块 raw 中放置对齐后的数据，写入回指针
+unsafe fn place_aligned(raw: *mut u8, align: usize) -> *mut u8 {
+    let aligned = align_up(raw as usize + BACK_PTR_SIZE, align);
+    // SAFETY: aligned - BACK_PTR_SIZE >= raw，且位于 padded_layout 申请的范围内
+    unsafe {
+        ((aligned - BACK_PTR_SIZE) as *mut usize).write(raw as usize);
+    }
+    aligned as *mut u8
+}
+
+// 通过回指针找到对齐分配对应的底层块
+unsafe fn raw_block(ptr: *mut u8) -> *mut u8 {
+    // SAFETY: ptr 由 place_aligned 返回
+    unsafe { ((ptr as usize - BACK_PTR_SIZE) as *const usize).read() as *mut u8 }
+}
+
+/// 管理一块或几块（最多 [`MAX_REGIONS`](super::MAX_REGIONS)）内存的堆
+///
+/// ```
+/// use core::alloc::Layout;
+/// use ecos_ssc1::allocator::Heap;
+///
+/// static mut POOL: [u8; 4096] = [0; 4096];
+///
+/// let mut heap = Heap::new(unsafe { &mut *core::ptr::addr_of_mut!(POOL) });
+/// let layout = Layout::from_size_align(64, 8).unwrap();
+/// let ptr = heap.allocate(layout).unwrap();
+/// unsafe { heap.deallocate(ptr, layout) };
+/// ```
+pub struct Heap<'a> {
+    inner: Backend,
+    _regions: PhantomData<&'a mut [u8]>,
+}
+
+impl<'a> Heap<'a> {
+    /// 创建一个没有任何区域的空堆，所有分配都会失败
+    pub const fn empty() -> Self {
+        Self {
+            inner: Backend::new(),
+            _regions: PhantomData,
+        }
+    }
+
+    /// 在 `region` 上创建堆
+    ///
+    /// 区域太小（放不下一个块）时 panic
+    pub fn new(region: &'a mut [u8]) -> Self {
+        let mut heap = Self::empty();
+        heap.add_region(region);
+        heap
+    }
+
+    /// 再加入一块内存，不同区域之间的空闲块不会合并
+    ///
+    /// 区域太小、超过 `MAX_REGIONS` 或与已有区域重叠时 panic
+    pub fn add_region(&mut self, region: &'a mut [u8]) {
+        // SAFETY: 独占借用保证区域在 'a 内有效且不被其他人使用
+        unsafe {
+            self.add_region_raw(region.as_mut_ptr() as usize, region.len());
+        }
+    }
+
+    // 丢弃所有区域，以 [start, end) 重新初始化（仍有分配时 panic）
+    pub(super) unsafe fn init_raw(&mut self, start: usize, end: usize) {
+        // SAFETY: 调用者确保区域有效
+        unsafe {
+            self.inner.init(start, end);
+        }
+    }
+
+    pub(super) unsafe fn add_region_raw(&mut self, start: usize, len: usize) {
+        // SAFETY: 调用者确保区域有效
+        unsafe {
+            self.inner.add_region(start, len);
+        }
+    }
+
+    /// 按 `layout` 分配，没有足够大的空闲块时返回 `None`
+    ///
+    /// 支持任意 2 的幂对齐
+    pub fn allocate(&mut self, layout: Layout) -> Option<NonNull<u8>> {
+        // SAFETY: 底层分配器只会返回本堆区域内的块
+        let ptr = unsafe {
+            if is_over_aligned(&layout) {
+                let raw = self.inner.alloc_impl(padded_layout(&layout)?);
+                if raw.is_null() {
+                    raw
+                } else {
+                    place_aligned(raw, layout.align())
+                }
+            } else {
+                self.inner.alloc_impl(layout)
+            }
+        };
+        NonNull::new(ptr)
+    }
+
+    /// 释放 `ptr`
+    ///
+    /// # Safety
+    /// `ptr` 必须是本堆以同一个 `layout`（大小可以是最近一次 resize 之后的大小）分配的，且只释放一次
+    pub unsafe fn deallocate(&mut self, ptr: NonNull<u8>, layout: Layout) {
+        let ptr = ptr.as_ptr();
+        // SAFETY: 调用者确保这是有效的释放请求
+        unsafe {
+            if is_over_aligned(&layout) {
+                // padded_layout 在分配时已经成功过，这里不会失败
+                let padded = padded_layout(&layout).unwrap_or(layout);
+                self.inner.dealloc_impl(raw_block(ptr), padded);
+            } else {
+                self.inner.dealloc_impl(ptr, layout);
+            }
+        }
+    }
+
+    /// 原地把块调整为 `new_size` 字节，成功返回 `true`，此后应以新大小释放
+    ///
+    /// 收缩总是成功，多余的尾部还给堆；扩大只有在紧随其后的块空闲且足够大时才成功
+    ///
+    /// # Safety
+    /// 同 [`deallocate`](Self::deallocate)
+    pub unsafe fn resize_in_place(
+        &mut self,
+        ptr: NonNull<u8>,
+        layout: Layout,
+        new_size: usize,
+    ) -> bool {
+        let ptr = ptr.as_ptr();
+        // SAFETY: 调用者确保 ptr 由本堆以 layout 分配；
+        // 对齐分配按 padded_layout 的大小调整底层块，与 deallocate 保持一致
+        unsafe {
+            if is_over_aligned(&layout) {
+                let raw = raw_block(ptr);
+                self.inner
+                    .resize_in_place(raw, new_size.saturating_add(layout.align()))
+            } else {
+                self.inner.resize_in_place(ptr, new_size)
+            }
+        }
+    }
+
+    /// 调整块大小：先尝试原地调整，不行再分配新块、复制数据并释放旧块
+    ///
+    /// 失败时返回 `None`，原来的块保持不变
+    ///
+    /// # Safety
+    /// 同 [`deallocate`](Self::deallocate)
+    pub unsafe fn reallocate(
+        &mut self,
+        ptr: NonNull<u8>,
+        layout: Layout,
+        new_size: usize,
+    ) -> Option<NonNull<u8>> {
+        // SAFETY: 调用者确保这是有效的请求
+        unsafe {
+            if self.resize_in_place(ptr, layout, new_size) {
+                return Some(ptr);
+            }
+            let new_layout = Layout::from_size_align(new_size, layout.align()).ok()?;
+            let new_ptr = self.allocate(new_layout)?;
+            core::ptr::copy_nonoverlapping(
+                ptr.as_ptr(),
+                new_ptr.as_ptr(),
+                layout.size().min(new_size),
+            );
+            self.deallocate(ptr, layout);
+            Some(new_ptr)
+        }
+    }
+
+    /// 整个堆的统计信息
+    pub fn stats(&self) -> HeapStats {
+        self.inner.stats()
+    }
+
+    /// 区域数量
+    pub fn region_count(&self) -> usize {
+        self.inner.region_count()
+    }
+
+    /// 第 `index` 个区域的统计信息
+    pub fn region_stats(&self, index: usize) -> Option<RegionStats> {
+        self.inner.region_stats(index)
+    }
+
+    /// 完整检查堆，发现损坏时 panic，返回存活分配的数量（需要 `alloc-checked` 特性）
+    #[cfg(feature = "alloc-checked")]
+    pub fn verify(&self) -> usize {
+        self.inner.verify()
+    }
+
+    #[cfg(feature = "alloc-trace")]
+    pub(super) fn for_each_live(&self, f: impl FnMut(usize, usize, usize, u32)) {
+        self.inner.for_each_live(f)
+    }
+
+    pub(super) fn print_free_list(&self) {
+        self.inner.print_free_list()
+    }
+}
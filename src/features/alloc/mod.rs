@@ -0,0 +1,1002 @@
+#![allow(unused)]
+
+// ... 测试打印宏 ... 的补丁 ...
+macro_rules! println {
+    ($($arg:tt)*) => {
+        #[cfg(feature = "alloc-debug-trace")]
+        {
+            #[cfg(feature = "log")]
+            {
+                use crate::log;
+                log::trace!($($arg)*);
+            }
+            #[cfg(not(feature = "log"))]
+            {
+                crate::print!("[TRACE] ");
+                crate::println!($($arg)*);
+            }
+        }
+        #[cfg(not(feature = "alloc-debug-trace"))]
+        {
+            // 空实现
+        }
+    };
+}
+
+macro_rules! alloc_dbg {
+    ($($arg:tt)*) => {
+        #[cfg(feature = "alloc-auto-test")]
+        {
+            #[cfg(feature = "log")]
+            {
+                use crate::log;
+                log::info!($($arg)*);
+            }
+            #[cfg(not(feature = "log"))]
+            {
+                crate::print!("[DEBUG] ");
+                crate::println!($($arg)*);
+            }
+        }
+        #[cfg(not(feature = "alloc-auto-test"))]
+        {
+            // 空实现
+        }
+    };
+}
+
+use core::alloc::{GlobalAlloc, Layout};
+use core::cell::{Cell, UnsafeCell};
+use core::ptr::{NonNull, null_mut};
+
+mod bump;
+mod firstfit;
+mod tlsf;
+
+#[cfg(feature = "alloc-checked")]
+mod checked;
+
+#[cfg(feature = "alloc-trace")]
+mod trace;
+#[cfg(feature = "alloc-trace")]
+pub use trace::{
+    EVENT_SIZE, TraceEvent, TraceKind, clear_trace, dropped_events, dump_trace, for_each_event,
+};
+
+mod heap;
+pub use heap::Heap;
+
+// 板上自测要读 mcycle / mstatus，宿主机上的测试见 tests.rs
+#[cfg(all(feature = "alloc-auto-test", target_arch = "riscv32"))]
+mod auto_test;
+
+#[cfg(all(feature = "alloc-auto-test", target_arch = "riscv32"))]
+pub use auto_test::{bench, test};
+
+// 板上自测与宿主机测试共用的检查
+#[cfg(any(test, feature = "alloc-auto-test"))]
+mod selftest;
+
+#[cfg(all(test, not(feature = "alloc-custom")))]
+mod tests;
+
+// 由 cargo feature 选择全局分配器的实现，都不选时为 alloc-firstfit
+#[cfg(any(
+    all(feature = "alloc-firstfit", feature = "alloc-tlsf"),
+    all(feature = "alloc-firstfit", feature = "alloc-bump"),
+    all(feature = "alloc-tlsf", feature = "alloc-bump"),
+))]
+compile_error!("features `alloc-firstfit`, `alloc-tlsf` and `alloc-bump` are mutually exclusive");
+
+#[cfg(all(feature = "alloc-custom", feature = "alloc-auto-test"))]
+compile_error!(
+    "`alloc-auto-test` tests the crate-provided global allocator, disable `alloc-custom`"
+);
+
+#[cfg(not(any(feature = "alloc-tlsf", feature = "alloc-bump")))]
+type RawBackend = firstfit::FirstFitHeap;
+#[cfg(not(any(feature = "alloc-tlsf", feature = "alloc-bump")))]
+use firstfit::MIN_ALIGN;
+#[cfg(feature = "alloc-tlsf")]
+type RawBackend = tlsf::TlsfHeap;
+#[cfg(feature = "alloc-tlsf")]
+use tlsf::MIN_ALIGN;
+#[cfg(feature = "alloc-bump")]
+type RawBackend = bump::BumpHeap;
+#[cfg(feature = "alloc-bump")]
+use bump::MIN_ALIGN;
+
+// alloc-checked 在所选实现外面再包一层损坏检测
+#[cfg(not(feature = "alloc-checked"))]
+type Backend = RawBackend;
+#[cfg(feature = "alloc-checked")]
+type Backend = checked::CheckedHeap;
+
+// 从链接脚本引入堆起始地址
+#[cfg(target_arch = "riscv32")]
+unsafe extern "C" {
+    static _heap_start: u8;
+    static _heap_end: u8;
+}
+
+// 由 build.rs 根据 ECOS_HEAP_END / ECOS_HEAP_RESERVED 生成
+mod config {
+    include!(concat!(env!("OUT_DIR"), "/heap_config.rs"));
+}
+
+/// RAM（PSRAM）起始地址
+pub const RAM_BASE: usize = 0x04000000;
+/// 单片 PSRAM 大小：8MB
+pub const PSRAM_SIZE: usize = 8 * 1024 * 1024;
+
+/// 默认堆结束地址（不含）
+///
+/// 优先使用编译期 `ECOS_HEAP_END`，否则按 autoconf 的 `CONFIG_PSRAM_NUM` 计算 RAM 顶部；
+/// 最后再减去 `ECOS_HEAP_RESERVED` 预留给应用的部分
+#[cfg(target_arch = "riscv32")]
+pub const HEAP_END: usize = match config::HEAP_END {
+    Some(end) => end,
+    None => RAM_BASE + crate::bindings::CONFIG_PSRAM_NUM as usize * PSRAM_SIZE,
+} - config::HEAP_RESERVED;
+
+// 向上对齐
+fn align_up(addr: usize, align: usize) -> usize {
+    (addr + align - 1) & !(align - 1)
+}
+
+/// 堆区域统计信息
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RegionStats {
+    /// 区域起始地址
+    pub start: usize,
+    /// 区域总大小（字节）
+    pub size: usize,
+    /// 已使用字节数（包括块头部）
+    pub used: usize,
+    /// 空闲字节数（包括块头部）
+    pub free: usize,
+    /// 最大空闲块大小
+    pub largest_free: usize,
+    /// 空闲块数量
+    pub free_blocks: usize,
+    /// 当前未释放的分配数
+    pub allocations: usize,
+}
+
+impl RegionStats {
+    fn add_free_block(&mut self, size: usize) {
+        self.free += size;
+        self.used -= size;
+        self.free_blocks += 1;
+        self.largest_free = self.largest_free.max(size);
+    }
+}
+
+/// 整个堆的统计信息
+#[derive(Debug, Clone, Copy, Default)]
+pub struct HeapStats {
+    /// 堆总大小（所有区域之和）
+    pub total: usize,
+    /// 已使用字节数（包括块头部）
+    pub used: usize,
+    /// 空闲字节数（包括块头部）
+    pub free: usize,
+    /// 最大空闲块大小
+    pub largest_free: usize,
+    /// 空闲块数量
+    pub free_blocks: usize,
+    /// 当前未释放的分配数
+    pub allocations: usize,
+    /// 累计分配次数
+    pub total_allocations: usize,
+    /// 使用量峰值
+    pub peak_used: usize,
+    /// 分配失败次数
+    pub failed_allocations: usize,
+}
+
+impl HeapStats {
+    fn add_free_block(&mut self, size: usize) {
+        self.free += size;
+        self.free_blocks += 1;
+        self.largest_free = self.largest_free.max(size);
+    }
+}
+
+impl core::fmt::Display for HeapStats {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(
+            f,
+            "heap: total={} used={} free={} largest={} blocks={} allocs={}/{} peak={} failed={}",
+            self.total,
+            self.used,
+            self.free,
+            self.largest_free,
+            self.free_blocks,
+            self.allocations,
+            self.total_allocations,
+            self.peak_used,
+            self.failed_allocations
+        )
+    }
+}
+
+// 堆区域
+#[derive(Clone, Copy)]
+struct HeapRegion {
+    start: usize,
+    end: usize,
+    allocated: usize, // 当前未释放的块数
+}
+
+impl HeapRegion {
+    const EMPTY: Self = Self {
+        start: 0,
+        end: 0,
+        allocated: 0,
+    };
+
+    fn contains(&self, addr: usize) -> bool {
+        addr >= self.start && addr < self.end
+    }
+
+    // 空闲块尚未计入的区域统计（全部视为已使用）
+    fn stats(&self) -> RegionStats {
+        RegionStats {
+            start: self.start,
+            size: self.end - self.start,
+            used: self.end - self.start,
+            allocations: self.allocated,
+            ..RegionStats::default()
+        }
+    }
+}
+
+/// 最多可注册的堆区域数（如内部 SRAM + 外部 PSRAM）
+pub const MAX_REGIONS: usize = 4;
+
+// 已注册的堆区域表（各分配器实现共用）
+struct RegionTable {
+    regions: [HeapRegion; MAX_REGIONS],
+    count: usize,
+}
+
+impl RegionTable {
+    const fn new() -> Self {
+        Self {
+            regions: [HeapRegion::EMPTY; MAX_REGIONS],
+            count: 0,
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.count
+    }
+
+    fn get(&self, index: usize) -> Option<&HeapRegion> {
+        self.regions[..self.count].get(index)
+    }
+
+    fn clear(&mut self) {
+        self.count = 0;
+    }
+
+    // 当前未释放的块数（所有区域）
+    fn allocated(&self) -> usize {
+        self.regions[..self.count].iter().map(|r| r.allocated).sum()
+    }
+
+    // 所有区域的总大小
+    fn total(&self) -> usize {
+        self.regions[..self.count]
+            .iter()
+            .map(|r| r.end - r.start)
+            .sum()
+    }
+
+    fn iter(&self) -> impl Iterator<Item = &HeapRegion> {
+        self.regions[..self.count].iter()
+    }
+
+    // 地址是否位于某个区域内
+    fn contains(&self, addr: usize) -> bool {
+        self.iter().any(|r| r.contains(addr))
+    }
+
+    // 地址是否恰好是某个区域的结束地址
+    fn is_region_end(&self, addr: usize) -> bool {
+        self.regions[..self.count].iter().any(|r| r.end == addr)
+    }
+
+    fn record_alloc(&mut self, addr: usize) {
+        if let Some(region) = self.regions[..self.count]
+            .iter_mut()
+            .find(|r| r.contains(addr))
+        {
+            region.allocated += 1;
+        }
+    }
+
+    fn record_free(&mut self, addr: usize) {
+        if let Some(region) = self.regions[..self.count]
+            .iter_mut()
+            .find(|r| r.contains(addr))
+        {
+            region.allocated = region.allocated.saturating_sub(1);
+        }
+    }
+
+    // 对齐并登记新区域，返回对齐后的 (start, end)
+    fn push(&mut self, start: usize, len: usize, align: usize, min_size: usize) -> (usize, usize) {
+        let end = start.saturating_add(len) & !(align - 1);
+        let start = align_up(start, align);
+
+        if start >= end {
+            panic!("Invalid heap region");
+        }
+
+        if end - start < min_size {
+            panic!("Heap too small");
+        }
+
+        if self.count >= MAX_REGIONS {
+            panic!("Too many heap regions");
+        }
+
+        if self.regions[..self.count]
+            .iter()
+            .any(|r| start < r.end && r.start < end)
+        {
+            panic!("Overlapping heap region");
+        }
+
+        self.regions[self.count] = HeapRegion {
+            start,
+            end,
+            allocated: 0,
+        };
+        self.count += 1;
+
+        (start, end)
+    }
+}
+
+// 分配计数器（各分配器实现共用）
+struct Counters {
+    used: usize,               // 已分配块的总大小
+    peak_used: usize,          // used 的峰值
+    total_allocations: usize,  // 累计分配次数
+    failed_allocations: usize, // 分配失败次数
+}
+
+impl Counters {
+    const fn new() -> Self {
+        Self {
+            used: 0,
+            peak_used: 0,
+            total_allocations: 0,
+            failed_allocations: 0,
+        }
+    }
+
+    fn reset(&mut self) {
+        self.used = 0;
+        self.peak_used = 0;
+    }
+
+    fn record_alloc(&mut self, size: usize) {
+        self.used += size;
+        self.peak_used = self.peak_used.max(self.used);
+        self.total_allocations += 1;
+    }
+
+    fn record_free(&mut self, size: usize) {
+        self.used = self.used.saturating_sub(size);
+    }
+
+    // 原地调整块大小，不计入分配次数
+    fn record_resize(&mut self, old_size: usize, new_size: usize) {
+        self.used = self.used.saturating_sub(old_size) + new_size;
+        self.peak_used = self.peak_used.max(self.used);
+    }
+
+    fn record_failure(&mut self) {
+        self.failed_allocations += 1;
+    }
+
+    // 空闲块尚未计入的堆统计
+    fn stats(&self, regions: &RegionTable) -> HeapStats {
+        HeapStats {
+            total: regions.total(),
+            used: self.used,
+            allocations: regions.allocated(),
+            total_allocations: self.total_allocations,
+            peak_used: self.peak_used,
+            failed_allocations: self.failed_allocations,
+            ..HeapStats::default()
+        }
+    }
+}
+
+// 全局分配器（线程安全包装）
+pub struct GlobalAllocator {
+    inner: UnsafeCell<Heap<'static>>,
+    oom_handler: Cell<Option<OomHandler>>,
+    in_oom_handler: Cell<bool>,
+}
+
+/// 分配失败时调用的处理函数，参数为失败的布局和失败时的堆统计
+///
+/// 处理函数返回后分配器会再尝试一次分配，因此可以在其中释放缓存；
+/// 也可以只记录日志，或者直接复位系统而不返回。
+/// 处理函数在分配器的临界区中运行，期间中断被屏蔽
+pub type OomHandler = fn(Layout, &HeapStats);
+
+// SAFETY: 单核环境，所有访问内部状态的入口都在 critical 临界区中进行
+// （启用 alloc-unlocked 时由用户保证不会在中断中分配）
+unsafe impl Sync for GlobalAllocator {}
+
+/// 在临界区中执行 `f`：屏蔽机器模式中断，防止中断处理函数重入分配器破坏空闲链表
+///
+/// 可以嵌套，退出最外层时才恢复原来的中断使能状态。启用 `alloc-unlocked` 特性时不屏蔽中断，
+/// 适合从不在中断上下文中分配、又不希望分配时增加中断延迟的应用。
+/// 宿主机上没有中断，由调用者（测试）自己保证不并发访问
+#[inline(always)]
+pub(super) fn critical<R>(f: impl FnOnce() -> R) -> R {
+    #[cfg(all(target_arch = "riscv32", not(feature = "alloc-unlocked")))]
+    {
+        riscv::interrupt::free(f)
+    }
+    #[cfg(any(not(target_arch = "riscv32"), feature = "alloc-unlocked"))]
+    {
+        f()
+    }
+}
+
+impl Default for GlobalAllocator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl GlobalAllocator {
+    pub const fn new() -> Self {
+        Self {
+            inner: UnsafeCell::new(Heap::empty()),
+            oom_handler: Cell::new(None),
+            in_oom_handler: Cell::new(false),
+        }
+    }
+
+    // 设置/清除分配失败处理函数
+    pub fn set_oom_handler(&self, handler: Option<OomHandler>) {
+        self.oom_handler.set(handler);
+    }
+
+    // 分配一次，不调用分配失败处理函数
+    unsafe fn try_alloc(&self, layout: Layout) -> *mut u8 {
+        // SAFETY: 获取内部状态的可变引用
+        let inner = unsafe { &mut *self.inner.get() };
+        inner.allocate(layout).map_or(null_mut(), NonNull::as_ptr)
+    }
+
+    // 分配失败：调用处理函数后重试一次
+    //
+    // 调用处理函数时不持有内部状态的引用，处理函数里可以正常释放/分配内存；
+    // 处理函数内部再次分配失败时不会递归调用自己
+    #[cold]
+    unsafe fn handle_oom(&self, layout: Layout) -> *mut u8 {
+        let Some(handler) = self.oom_handler.get() else {
+            return null_mut();
+        };
+        if self.in_oom_handler.get() {
+            return null_mut();
+        }
+
+        println!("GlobalAlloc::handle_oom: calling handler for {:?}", layout);
+        self.in_oom_handler.set(true);
+        handler(layout, &self.stats());
+        self.in_oom_handler.set(false);
+
+        // SAFETY: 同 alloc
+        unsafe { self.try_alloc(layout) }
+    }
+
+    // 分配，失败时交给 OOM 处理函数后重试一次；不记录跟踪事件
+    unsafe fn alloc_untraced(&self, layout: Layout) -> *mut u8 {
+        // SAFETY: 调用者确保这是有效的分配请求
+        let mut result = unsafe { self.try_alloc(layout) };
+        if result.is_null() {
+            result = unsafe { self.handle_oom(layout) };
+        }
+        result
+    }
+
+    // 释放；不记录跟踪事件
+    unsafe fn dealloc_untraced(&self, ptr: *mut u8, layout: Layout) {
+        let Some(ptr) = NonNull::new(ptr) else {
+            return;
+        };
+        // SAFETY: 获取内部状态的可变引用
+        let inner = unsafe { &mut *self.inner.get() };
+        // SAFETY: 调用者确保这是有效的释放请求
+        unsafe {
+            inner.deallocate(ptr, layout);
+        }
+    }
+
+    /// 以 [heap_start, heap_end) 初始化堆内存
+    ///
+    /// # Safety
+    /// 同 [`init_with_region`]
+    pub unsafe fn init(&self, heap_start: usize, heap_end: usize) {
+        println!("GlobalAllocator::init: starting");
+        critical(|| {
+            // SAFETY: 获取内部状态的可变引用
+            let inner = unsafe { &mut *self.inner.get() };
+            unsafe {
+                inner.init_raw(heap_start, heap_end);
+            }
+        });
+        println!("GlobalAllocator::init: completed");
+    }
+
+    /// 注册额外的堆区域
+    ///
+    /// # Safety
+    /// 同 [`add_region`]
+    pub unsafe fn add_region(&self, start: usize, len: usize) {
+        critical(|| {
+            // SAFETY: 获取内部状态的可变引用
+            let inner = unsafe { &mut *self.inner.get() };
+            unsafe {
+                inner.add_region_raw(start, len);
+            }
+        })
+    }
+
+    // 整个堆的统计信息
+    pub fn stats(&self) -> HeapStats {
+        critical(|| {
+            // SAFETY: 只读访问
+            let inner = unsafe { &*self.inner.get() };
+            inner.stats()
+        })
+    }
+
+    // 区域数量
+    pub fn region_count(&self) -> usize {
+        critical(|| {
+            // SAFETY: 只读访问
+            let inner = unsafe { &*self.inner.get() };
+            inner.region_count()
+        })
+    }
+
+    // 指定区域的统计信息
+    pub fn region_stats(&self, index: usize) -> Option<RegionStats> {
+        critical(|| {
+            // SAFETY: 只读访问
+            let inner = unsafe { &*self.inner.get() };
+            inner.region_stats(index)
+        })
+    }
+
+    // 检查所有存活分配和整个堆的结构，发现损坏时 panic
+    #[cfg(feature = "alloc-checked")]
+    pub fn verify(&self) -> usize {
+        critical(|| {
+            // SAFETY: 只读访问
+            let inner = unsafe { &*self.inner.get() };
+            inner.verify()
+        })
+    }
+
+    // 遍历存活的分配：(数据地址, 大小, 调用者, tick)
+    #[cfg(feature = "alloc-trace")]
+    pub(super) fn for_each_live(&self, f: impl FnMut(usize, usize, usize, u32)) {
+        critical(|| {
+            // SAFETY: 只读访问
+            let inner = unsafe { &*self.inner.get() };
+            inner.for_each_live(f)
+        })
+    }
+
+    // 在一个临界区内复制地址大于 after 的前 DUMP_BATCH 个存活分配（按地址从小到大），
+    // 同时返回存活分配的总数
+    #[cfg(feature = "alloc-trace")]
+    fn live_batch(
+        &self,
+        after: Option<u32>,
+        batch: &mut [trace::TraceEvent; trace::DUMP_BATCH],
+    ) -> (usize, usize) {
+        let mut n = 0;
+        let mut total = 0;
+        self.for_each_live(|addr, size, caller, tick| {
+            total += 1;
+            let addr = addr as u32;
+            if after.is_some_and(|after| addr <= after) {
+                return;
+            }
+            // 插入排序，只保留地址最小的 DUMP_BATCH 个
+            let pos = batch[..n].partition_point(|event| event.addr < addr);
+            if pos == trace::DUMP_BATCH {
+                return;
+            }
+            if n < trace::DUMP_BATCH {
+                n += 1;
+            }
+            batch.copy_within(pos..n - 1, pos + 1);
+            batch[pos] = trace::TraceEvent {
+                kind: trace::TraceKind::Live,
+                addr,
+                size: size as u32,
+                old_addr: 0,
+                caller: caller as u32,
+                tick,
+            };
+        });
+        (n, total)
+    }
+
+    // 输出所有存活的分配，按地址分批复制，打印在临界区外进行
+    #[cfg(feature = "alloc-trace")]
+    pub fn dump_live_allocations(&self) {
+        let mut batch = [trace::TraceEvent::EMPTY; trace::DUMP_BATCH];
+        let (mut n, count) = self.live_batch(None, &mut batch);
+        crate::println!("ECOS-ALLOC-LIVE v1 count={}", count);
+        while n > 0 {
+            for event in &batch[..n] {
+                trace::print_event('L', event);
+            }
+            if n < trace::DUMP_BATCH {
+                break;
+            }
+            n = self.live_batch(Some(batch[n - 1].addr), &mut batch).0;
+        }
+        crate::println!("ECOS-ALLOC-LIVE END");
+    }
+
+    #[cfg(feature = "alloc-auto-test")]
+    /// for dev fn test
+    pub fn print_free_list(&self) {
+        critical(|| {
+            // SAFETY: 只读访问
+            let inner = unsafe { &*self.inner.get() };
+            inner.print_free_list()
+        })
+    }
+}
+
+// 实现GlobalAlloc trait
+//
+// 启用 alloc-trace 时每个入口在最开始读取调用者地址，结束时只记录一条事件；
+// realloc 内部走 alloc_untraced / dealloc_untraced，避免同一次调用被记录成多条
+unsafe impl GlobalAlloc for GlobalAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        #[cfg(feature = "alloc-trace")]
+        let caller = trace::return_address();
+        critical(|| {
+            #[cfg(feature = "alloc-trace")]
+            trace::set_current_caller(caller);
+            println!("GlobalAlloc::alloc: layout={:?}", layout);
+            // SAFETY: 调用者确保这是有效的分配请求
+            let result = unsafe { self.alloc_untraced(layout) };
+            #[cfg(feature = "alloc-trace")]
+            trace::record(
+                if result.is_null() {
+                    trace::TraceKind::Failed
+                } else {
+                    trace::TraceKind::Alloc
+                },
+                result,
+                layout,
+                null_mut(),
+                caller,
+            );
+            println!("GlobalAlloc::alloc: returning {:?}", result);
+            result
+        })
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        #[cfg(feature = "alloc-trace")]
+        let caller = trace::return_address();
+        critical(|| {
+            #[cfg(feature = "alloc-trace")]
+            trace::set_current_caller(caller);
+            println!("GlobalAlloc::dealloc: ptr=0x{:p}, layout={:?}", ptr, layout);
+            // SAFETY: 调用者确保这是有效的释放请求
+            unsafe {
+                self.dealloc_untraced(ptr, layout);
+            }
+            #[cfg(feature = "alloc-trace")]
+            if !ptr.is_null() {
+                trace::record(trace::TraceKind::Free, ptr, layout, null_mut(), caller);
+            }
+            println!("GlobalAlloc::dealloc: completed");
+        })
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        println!("GlobalAlloc::alloc_zeroed: layout={:?}", layout);
+        let ptr = unsafe { self.alloc(layout) };
+        if !ptr.is_null() {
+            println!(
+                "GlobalAlloc::alloc_zeroed: zeroing {} bytes at 0x{:p}",
+                layout.size(),
+                ptr
+            );
+            // SAFETY: ptr 是有效的已分配内存
+            unsafe {
+                ptr.write_bytes(0, layout.size());
+            }
+        }
+        println!("GlobalAlloc::alloc_zeroed: returning {:?}", ptr);
+        ptr
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        #[cfg(feature = "alloc-trace")]
+        let caller = trace::return_address();
+        critical(|| {
+            #[cfg(feature = "alloc-trace")]
+            trace::set_current_caller(caller);
+            println!(
+                "GlobalAlloc::realloc: ptr=0x{:p}, layout={:?}, new_size={}",
+                ptr, layout, new_size
+            );
+
+            if new_size == 0 {
+                println!("GlobalAlloc::realloc: new_size=0, deallocating");
+                unsafe {
+                    self.dealloc_untraced(ptr, layout);
+                }
+                #[cfg(feature = "alloc-trace")]
+                if !ptr.is_null() {
+                    trace::record(trace::TraceKind::Free, ptr, layout, null_mut(), caller);
+                }
+                return null_mut();
+            }
+
+            let new_layout = Layout::from_size_align(new_size, layout.align()).unwrap_or(layout);
+
+            let new_ptr = if ptr.is_null() {
+                println!("GlobalAlloc::realloc: null pointer, allocating new block");
+                unsafe { self.alloc_untraced(new_layout) }
+            } else if unsafe {
+                // 先尝试原地收缩/扩大，避免复制
+                // SAFETY: ptr 由本分配器以 layout 分配
+                let inner = &mut *self.inner.get();
+                inner.resize_in_place(NonNull::new_unchecked(ptr), layout, new_size)
+            } {
+                println!("GlobalAlloc::realloc: resized in place, returning same pointer");
+                ptr
+            } else {
+                // 分配新内存并复制数据
+                println!("GlobalAlloc::realloc: allocating new larger block");
+                let new_ptr = unsafe { self.alloc_untraced(new_layout) };
+
+                if !new_ptr.is_null() {
+                    // 只有扩大才会走到这里，原数据全部复制
+                    println!(
+                        "GlobalAlloc::realloc: copying {} bytes from 0x{:p} to 0x{:p}",
+                        layout.size(),
+                        ptr,
+                        new_ptr
+                    );
+                    // SAFETY: ptr 和 new_ptr 都是有效的指针，不重叠
+                    unsafe {
+                        core::ptr::copy_nonoverlapping(ptr, new_ptr, layout.size());
+                        self.dealloc_untraced(ptr, layout);
+                    }
+                } else {
+                    println!("GlobalAlloc::realloc: FAILED to allocate new block");
+                }
+                new_ptr
+            };
+
+            #[cfg(feature = "alloc-trace")]
+            trace::record(
+                if new_ptr.is_null() {
+                    trace::TraceKind::Failed
+                } else {
+                    trace::TraceKind::Realloc
+                },
+                new_ptr,
+                new_layout,
+                ptr,
+                caller,
+            );
+            println!("GlobalAlloc::realloc: returning {:?}", new_ptr);
+            new_ptr
+        })
+    }
+}
+
+// 全局分配器实例，启用 alloc-custom 时由应用自己提供；
+// 宿主机上只作为普通实例供测试使用，不替换 std 的分配器
+#[cfg(not(feature = "alloc-custom"))]
+#[cfg_attr(target_arch = "riscv32", global_allocator)]
+static ALLOCATOR: GlobalAllocator = GlobalAllocator::new();
+
+#[cfg(feature = "alloc-custom")]
+unsafe extern "Rust" {
+    // 由应用通过 alloc_init_hook! 提供
+    fn __ecos_alloc_init(start: usize, end: usize);
+}
+
+/// 启用 `alloc-custom` 时注册应用自己的堆初始化函数
+///
+/// `alloc-custom` 不注册本 crate 的 `#[global_allocator]`，由应用提供；
+/// [`init`]（`ecos_main` / `rust_main` 会自动调用）、[`init_with_region`] 和 [`init_from_linker`]
+/// 则改为以堆区域 `(start, end)` 调用这里注册的函数。没有注册时链接会报 `__ecos_alloc_init` 未定义：
+///
+/// ```no_run
+/// use ecos_ssc1::allocator::GlobalAllocator;
+///
+/// #[global_allocator]
+/// static HEAP: GlobalAllocator = GlobalAllocator::new();
+///
+/// fn init_heap(start: usize, end: usize) {
+///     // SAFETY: 区域由 init / init_with_region / init_from_linker 传入
+///     unsafe { HEAP.init(start, end) };
+/// }
+///
+/// ecos_ssc1::alloc_init_hook!(init_heap);
+/// ```
+///
+/// 换成其他分配器（如 `linked_list_allocator`）时，在初始化函数里以同样的 `(start, end)` 初始化即可
+#[cfg(feature = "alloc-custom")]
+#[macro_export]
+macro_rules! alloc_init_hook {
+    ($init:path) => {
+        #[unsafe(no_mangle)]
+        fn __ecos_alloc_init(start: usize, end: usize) {
+            let init: fn(usize, usize) = $init;
+            init(start, end)
+        }
+    };
+}
+
+/// 使用默认堆区域初始化：`[_heap_start, HEAP_END)`
+///
+/// `ecos_main` / `rust_main` 会自动调用
+#[cfg(target_arch = "riscv32")]
+pub unsafe fn init() {
+    // SAFETY: _heap_start 由链接脚本提供
+    let heap_start = unsafe { &_heap_start as *const u8 as usize };
+    unsafe {
+        init_with_region(heap_start, HEAP_END);
+    }
+}
+
+/// 使用指定的堆区域 `[start, end)` 初始化
+///
+/// 可以在 `ecos_main` 自动初始化之后再次调用以缩小/移动堆，
+/// 但前提是此前没有任何未释放的分配，否则会 panic
+///
+/// # Safety
+/// 调用者确保该区域是有效、未被其他用途占用的 RAM
+pub unsafe fn init_with_region(start: usize, end: usize) {
+    println!("=== ALLOCATOR INIT START ===");
+    // SAFETY: 在系统启动时调用，确保单线程访问
+    #[cfg(not(feature = "alloc-custom"))]
+    unsafe {
+        ALLOCATOR.init(start, end);
+    }
+    // SAFETY: 由应用通过 alloc_init_hook! 提供
+    #[cfg(feature = "alloc-custom")]
+    unsafe {
+        __ecos_alloc_init(start, end);
+    }
+    println!("=== ALLOCATOR INIT COMPLETE ===");
+
+    #[cfg(all(feature = "alloc-auto-test", target_arch = "riscv32"))]
+    test();
+}
+
+/// 额外注册一块不相连的堆区域，如内部 SRAM 或第二片 PSRAM
+///
+/// 不同区域之间的空闲块即使地址相邻也不会合并
+///
+/// # Safety
+/// 调用者确保 `[start, start + len)` 是有效、未被其他用途占用的 RAM，且不与已有区域重叠
+#[cfg(not(feature = "alloc-custom"))]
+pub unsafe fn add_region(start: usize, len: usize) {
+    // SAFETY: 调用者确保区域有效
+    unsafe {
+        ALLOCATOR.add_region(start, len);
+    }
+}
+
+/// 获取整个堆的统计信息
+///
+/// 计数器在分配/释放时顺带维护，`largest_free` 和 `free_blocks` 在调用时遍历空闲链表得到，
+/// release 构建下同样可用，适合定期通过 UART 上报内存状况：
+///
+/// ```
+/// println!("{}", ecos_ssc1::allocator::stats());
+/// ```
+#[cfg(not(feature = "alloc-custom"))]
+pub fn stats() -> HeapStats {
+    ALLOCATOR.stats()
+}
+
+/// 已注册的堆区域数量
+#[cfg(not(feature = "alloc-custom"))]
+pub fn region_count() -> usize {
+    ALLOCATOR.region_count()
+}
+
+/// 获取第 `index` 个堆区域的统计信息
+#[cfg(not(feature = "alloc-custom"))]
+pub fn region_stats(index: usize) -> Option<RegionStats> {
+    ALLOCATOR.region_stats(index)
+}
+
+/// 设置分配失败处理函数
+///
+/// 找不到足够大的空闲块时，在返回 null（进而触发 alloc error handler）之前调用一次，
+/// 处理函数返回后会重试这次分配：
+///
+/// ```
+/// fn on_oom(layout: core::alloc::Layout, stats: &ecos_ssc1::allocator::HeapStats) {
+///     println!("OOM: {:?}\n{}", layout, stats);
+///     // 释放缓存后返回即可重试；也可以在这里复位系统
+/// }
+///
+/// ecos_ssc1::allocator::set_oom_handler(on_oom);
+/// ```
+#[cfg(not(feature = "alloc-custom"))]
+pub fn set_oom_handler(handler: OomHandler) {
+    ALLOCATOR.set_oom_handler(Some(handler));
+}
+
+/// 清除分配失败处理函数，恢复为直接返回 null
+#[cfg(not(feature = "alloc-custom"))]
+pub fn clear_oom_handler() {
+    ALLOCATOR.set_oom_handler(None);
+}
+
+/// 完整检查堆（需要 `alloc-checked` 特性）
+///
+/// 逐个检查存活分配的头部魔数和尾部金丝雀，并遍历每个区域确认块结构和空闲链表完好。
+/// 发现损坏时带着出问题的地址 panic，正常时返回存活分配的数量。
+/// 开销与堆中块的数量成正比，适合在调试时放在主循环里定期调用
+#[cfg(all(feature = "alloc-checked", not(feature = "alloc-custom")))]
+pub fn verify() -> usize {
+    ALLOCATOR.verify()
+}
+
+/// 把所有尚未释放的分配输出到 UART（需要 `alloc-trace` 特性）
+///
+/// 每个分配一行，带大小、分配者的返回地址和分配时的 tick，格式与 [`dump_trace`] 相同，
+/// 可以用 `tools/alloc_trace_decode.py` 按调用者汇总，查找泄漏。
+/// 地址是底层块中数据的起始地址，对齐要求超过 `MIN_ALIGN` 的分配会与用户拿到的指针有一小段偏移。
+/// 按地址从小到大分批输出，打印时不屏蔽中断，期间发生的分配和释放不一定反映在结果中
+#[cfg(all(feature = "alloc-trace", not(feature = "alloc-custom")))]
+pub fn dump_live_allocations() {
+    ALLOCATOR.dump_live_allocations()
+}
+
+/// 使用链接脚本中的 `_heap_start` / `_heap_end` 符号初始化
+///
+/// 仅在调用时才要求链接脚本提供 `_heap_end`
+///
+/// # Safety
+/// 同 [`init_with_region`]
+#[cfg(target_arch = "riscv32")]
+#[inline]
+pub unsafe fn init_from_linker() {
+    // SAFETY: 符号由链接脚本提供
+    let (start, end) = unsafe {
+        (
+            &_heap_start as *const u8 as usize,
+            &_heap_end as *const u8 as usize,
+        )
+    };
+    unsafe {
+        init_with_region(start, end);
+    }
+}
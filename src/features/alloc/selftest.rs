@@ -0,0 +1,340 @@
+//! 板上自测（auto_test.rs）与宿主机测试（tests.rs）共用的检查，
+//! 只依赖 [`Heap`] 和调用方给出的内存区域
+
+use alloc::vec::Vec;
+use core::alloc::Layout;
+use core::ptr::{NonNull, null_mut};
+
+use super::firstfit::FirstFitHeap;
+use super::tlsf::TlsfHeap;
+use super::{Heap, HeapStats};
+
+/// 固定种子的 xorshift，不依赖 rand 特性
+pub struct XorShift(pub u64);
+
+impl XorShift {
+    pub fn next(&mut self) -> u64 {
+        let mut x = self.0;
+        x ^= x << 13;
+        x ^= x >> 7;
+        x ^= x << 17;
+        self.0 = x;
+        x
+    }
+
+    /// [low, high) 内的随机数
+    pub fn range(&mut self, low: i64, high: i64) -> i64 {
+        low + (self.next() % (high - low) as u64) as i64
+    }
+
+    /// [0, n) 内的随机数
+    pub fn below(&mut self, n: usize) -> usize {
+        self.next() as usize % n
+    }
+}
+
+/// 在 `region` 上建独立的 Heap，随机分配/释放/调整大小，每一步都与记录存活分配的模型对比：
+/// 地址在区域内且对齐、互不重叠、内容未被破坏、统计一致
+pub fn check_heap_against_model(region: &mut [u8], seed: u64, rounds: usize) {
+    struct Live {
+        ptr: NonNull<u8>,
+        layout: Layout,
+        tag: u8,
+    }
+
+    fn check_contents(live: &Live) {
+        for i in 0..live.layout.size() {
+            let byte = unsafe { *live.ptr.as_ptr().add(i) };
+            assert_eq!(byte, live.tag, "data at {:p}+{} corrupted", live.ptr, i);
+        }
+    }
+
+    let start = region.as_ptr() as usize;
+    let end = start + region.len();
+    let mut heap = Heap::new(region);
+    let initial = heap.stats();
+
+    let mut rng = XorShift(seed);
+    let mut model: Vec<Live> = Vec::new();
+    let mut failures = 0;
+
+    for round in 0..rounds {
+        let tag = round as u8;
+        match rng.below(10) {
+            // 分配
+            0..=4 => {
+                let size = if rng.below(10) == 0 {
+                    256 + rng.below(4096 - 256)
+                } else {
+                    rng.below(128)
+                };
+                let align = 1 << rng.below(8);
+                let layout = Layout::from_size_align(size, align).unwrap();
+                let Some(ptr) = heap.allocate(layout) else {
+                    failures += 1;
+                    continue;
+                };
+                let addr = ptr.as_ptr() as usize;
+                assert!(addr >= start && addr + size <= end, "outside region");
+                assert_eq!(addr % align, 0, "misaligned");
+                for other in &model {
+                    let other_addr = other.ptr.as_ptr() as usize;
+                    assert!(
+                        addr + size <= other_addr || other_addr + other.layout.size() <= addr,
+                        "overlapping allocations"
+                    );
+                }
+                unsafe { ptr.as_ptr().write_bytes(tag, size) };
+                model.push(Live { ptr, layout, tag });
+            }
+            // 释放
+            5..=7 if !model.is_empty() => {
+                let live = model.swap_remove(rng.below(model.len()));
+                check_contents(&live);
+                unsafe { heap.deallocate(live.ptr, live.layout) };
+            }
+            // 调整大小
+            _ if !model.is_empty() => {
+                let index = rng.below(model.len());
+                let live = &mut model[index];
+                check_contents(live);
+                let new_size = rng.below(1024);
+                let Some(ptr) = (unsafe { heap.reallocate(live.ptr, live.layout, new_size) })
+                else {
+                    failures += 1;
+                    continue;
+                };
+                assert_eq!(ptr.as_ptr() as usize % live.layout.align(), 0);
+                // 只有原大小与新大小中较小的部分保留原内容
+                let kept = live.layout.size().min(new_size);
+                live.ptr = ptr;
+                live.layout = Layout::from_size_align(kept, live.layout.align()).unwrap();
+                check_contents(live);
+                live.layout = Layout::from_size_align(new_size, live.layout.align()).unwrap();
+                live.tag = tag;
+                unsafe { ptr.as_ptr().write_bytes(tag, new_size) };
+            }
+            _ => {}
+        }
+
+        let stats = heap.stats();
+        assert_eq!(stats.allocations, model.len(), "allocation count mismatch");
+        assert!(stats.used + stats.free <= stats.total);
+        assert!(model.iter().map(|l| l.layout.size()).sum::<usize>() <= stats.used);
+    }
+
+    for live in model.drain(..) {
+        check_contents(&live);
+        unsafe { heap.deallocate(live.ptr, live.layout) };
+    }
+
+    let stats = heap.stats();
+    assert_eq!(stats.used, 0, "memory leaked");
+    // bump 分配器不回收按非栈顺序释放的块
+    #[cfg(not(feature = "alloc-bump"))]
+    {
+        assert_eq!(stats.free_blocks, 1, "free blocks not coalesced");
+        assert_eq!(stats.free, initial.free);
+    }
+    alloc_dbg!(
+        "✅ {} rounds, {} allocations, {} failures, peak used {}",
+        rounds,
+        stats.total_allocations,
+        failures,
+        stats.peak_used
+    );
+}
+
+/// 首次适应和 TLSF 共用的接口，供基准测试和原地 realloc 测试直接驱动具体实现
+pub trait ScratchHeap {
+    unsafe fn scratch_init(&mut self, start: usize, end: usize);
+    unsafe fn scratch_alloc(&mut self, layout: Layout) -> *mut u8;
+    unsafe fn scratch_dealloc(&mut self, ptr: *mut u8, layout: Layout);
+    unsafe fn scratch_resize(&mut self, ptr: *mut u8, new_size: usize) -> bool;
+    fn scratch_stats(&self) -> HeapStats;
+}
+
+macro_rules! impl_scratch_heap {
+    ($ty:ty) => {
+        impl ScratchHeap for $ty {
+            unsafe fn scratch_init(&mut self, start: usize, end: usize) {
+                unsafe { self.init(start, end) }
+            }
+            unsafe fn scratch_alloc(&mut self, layout: Layout) -> *mut u8 {
+                unsafe { self.alloc_impl(layout) }
+            }
+            unsafe fn scratch_dealloc(&mut self, ptr: *mut u8, layout: Layout) {
+                unsafe { self.dealloc_impl(ptr, layout) }
+            }
+            unsafe fn scratch_resize(&mut self, ptr: *mut u8, new_size: usize) -> bool {
+                unsafe { self.resize_in_place(ptr, new_size) }
+            }
+            fn scratch_stats(&self) -> HeapStats {
+                self.stats()
+            }
+        }
+    };
+}
+
+impl_scratch_heap!(FirstFitHeap);
+impl_scratch_heap!(TlsfHeap);
+
+const BENCH_SLOTS: usize = 128;
+
+/// 基准测试结果，耗时的单位由传给 [`run_bench`] 的时钟决定（板上为周期，宿主机上为纳秒）
+#[derive(Default)]
+pub struct BenchResult {
+    pub allocs: u32,
+    pub frees: u32,
+    pub failures: u32,
+    pub alloc_ticks: u64,
+    pub alloc_worst: u64,
+    pub free_ticks: u64,
+    pub free_worst: u64,
+}
+
+impl BenchResult {
+    pub fn alloc_avg(&self) -> u64 {
+        self.alloc_ticks / self.allocs.max(1) as u64
+    }
+
+    pub fn free_avg(&self) -> u64 {
+        self.free_ticks / self.frees.max(1) as u64
+    }
+}
+
+// 固定种子的 xorshift32，保证两种实现跑的是完全相同的操作序列
+fn next_rand(state: &mut u32) -> u32 {
+    let mut x = *state;
+    x ^= x << 13;
+    x ^= x >> 17;
+    x ^= x << 5;
+    *state = x;
+    x
+}
+
+/// 在 `region` 上随机混合分配/释放 `rounds` 次，制造碎片并用 `clock` 记录每次操作的耗时
+///
+/// 结束时释放所有块，并检查没有泄漏、空闲块已全部合并
+pub fn run_bench(
+    heap: &mut impl ScratchHeap,
+    region: &mut [u8],
+    rounds: usize,
+    clock: fn() -> u64,
+) -> BenchResult {
+    let mut result = BenchResult::default();
+    let mut slots: [(*mut u8, usize); BENCH_SLOTS] = [(null_mut(), 0); BENCH_SLOTS];
+    let mut seed = 0x1234_5678;
+
+    let start = region.as_mut_ptr() as usize;
+    // SAFETY: region 在本函数内一直有效，且只交给 heap 使用
+    unsafe { heap.scratch_init(start, start + region.len()) };
+
+    for _ in 0..rounds {
+        let r = next_rand(&mut seed);
+        let slot = &mut slots[r as usize % BENCH_SLOTS];
+        if slot.0.is_null() {
+            // 大部分是小块，偶尔夹杂较大的块
+            let size = if r & 0x700 == 0 {
+                256 + (r >> 16) as usize % 1024
+            } else {
+                8 + (r >> 16) as usize % 120
+            };
+            let layout = Layout::from_size_align(size, 4).unwrap();
+            let t0 = clock();
+            // SAFETY: layout 有效
+            let ptr = unsafe { heap.scratch_alloc(layout) };
+            let dt = clock() - t0;
+            if ptr.is_null() {
+                result.failures += 1;
+                continue;
+            }
+            // 写满整块，确保返回的内存确实可用
+            unsafe { ptr.write_bytes(r as u8, size) };
+            result.allocs += 1;
+            result.alloc_ticks += dt;
+            result.alloc_worst = result.alloc_worst.max(dt);
+            *slot = (ptr, size);
+        } else {
+            let layout = Layout::from_size_align(slot.1, 4).unwrap();
+            let t0 = clock();
+            // SAFETY: ptr 由同一个堆分配且只释放一次
+            unsafe { heap.scratch_dealloc(slot.0, layout) };
+            let dt = clock() - t0;
+            result.frees += 1;
+            result.free_ticks += dt;
+            result.free_worst = result.free_worst.max(dt);
+            *slot = (null_mut(), 0);
+        }
+    }
+
+    // 释放剩余的块
+    for slot in slots.iter().filter(|s| !s.0.is_null()) {
+        let layout = Layout::from_size_align(slot.1, 4).unwrap();
+        // SAFETY: 同上
+        unsafe { heap.scratch_dealloc(slot.0, layout) };
+    }
+    let stats = heap.scratch_stats();
+    assert_eq!(stats.used, 0, "memory leaked");
+    assert_eq!(stats.free_blocks, 1, "free blocks not coalesced");
+
+    result
+}
+
+/// 在 `region` 上验证具体实现的原地扩大/收缩（`resize_in_place`），
+/// 复制回退由 [`Heap::reallocate`] 负责
+pub fn check_resize_in_place(name: &str, heap: &mut impl ScratchHeap, region: &mut [u8]) {
+    let layout = Layout::from_size_align(64, 4).unwrap();
+    let start = region.as_mut_ptr() as usize;
+    // SAFETY: region 在本函数内一直有效，且只交给 heap 使用
+    unsafe {
+        heap.scratch_init(start, start + region.len());
+
+        // a 后面紧跟 b，释放 b 之后 a 可以原地扩大
+        let a = heap.scratch_alloc(layout);
+        let b = heap.scratch_alloc(layout);
+        let c = heap.scratch_alloc(layout);
+        assert!(!a.is_null() && !b.is_null() && !c.is_null());
+        a.write_bytes(0xA5, 64);
+        heap.scratch_dealloc(b, layout);
+        assert!(
+            heap.scratch_resize(a, 100),
+            "{}: grow into freed neighbour",
+            name
+        );
+        assert!((0..64).all(|i| *a.add(i) == 0xA5), "{}: data lost", name);
+
+        // c 仍然挡在后面，无法扩大到超过 a + b 的大小
+        assert!(
+            !heap.scratch_resize(a, 1024),
+            "{}: must not grow over c",
+            name
+        );
+
+        // 收缩后尾部还给空闲链表，新的分配可以落在 a 的尾部
+        let before = heap.scratch_stats();
+        assert!(heap.scratch_resize(a, 8), "{}: shrink", name);
+        let after = heap.scratch_stats();
+        assert!(
+            after.used < before.used && after.free > before.free,
+            "{}: tail not released",
+            name
+        );
+        assert!((0..8).all(|i| *a.add(i) == 0xA5), "{}: data lost", name);
+
+        // 释放 c 之后 a 可以一直扩大到堆末尾附近
+        heap.scratch_dealloc(c, layout);
+        assert!(
+            heap.scratch_resize(a, region.len() / 2),
+            "{}: grow large",
+            name
+        );
+        assert!((0..8).all(|i| *a.add(i) == 0xA5), "{}: data lost", name);
+        heap.scratch_dealloc(a, layout);
+
+        let stats = heap.scratch_stats();
+        assert_eq!(stats.used, 0, "{}: leaked", name);
+        assert_eq!(stats.free_blocks, 1, "{}: not coalesced", name);
+    }
+    alloc_dbg!("✅ {} resize in place verified", name);
+}
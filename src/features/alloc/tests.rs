@@ -0,0 +1,801 @@
+//! 宿主机上的分配器测试，`cargo test --lib` 运行
+//!
+//! 板上自测（auto_test.rs）的场景 1–12 和随机模型检查移植到这里。测试二进制以 [`Routed`]
+//! 作为 `#[global_allocator]`：测试线程在 [`on_heap`] 中时 Vec / String / Box 都从本 crate 的
+//! 全局分配器（建在静态缓冲区上）分配，其余分配（测试框架自己的线程等）仍交给 std。
+//! 所选的分配实现由 cargo feature 决定，`--features alloc-tlsf` 等分别测试各个实现
+
+use std::alloc::System;
+use std::cell::Cell;
+use std::collections::LinkedList;
+use std::sync::{Mutex, Once, OnceLock, PoisonError};
+use std::time::Instant;
+
+use super::firstfit::FirstFitHeap;
+use super::selftest::{XorShift, check_heap_against_model, check_resize_in_place, run_bench};
+use super::tlsf::TlsfHeap;
+use super::{ALLOCATOR, GlobalAlloc, Heap, Layout, NonNull, init_with_region, stats};
+
+// 全局分配器使用的堆
+const ARENA_SIZE: usize = 1024 * 1024;
+
+#[repr(align(4096))]
+struct Arena([u8; ARENA_SIZE]);
+
+static mut ARENA: Arena = Arena([0; ARENA_SIZE]);
+
+fn arena_range() -> (usize, usize) {
+    let start = core::ptr::addr_of!(ARENA) as usize;
+    (start, start + ARENA_SIZE)
+}
+
+thread_local! {
+    // 当前线程的分配是否交给 ALLOCATOR
+    static ON_HEAP: Cell<bool> = const { Cell::new(false) };
+}
+
+// 宿主机上 critical 不屏蔽任何东西，测试框架的线程释放测试线程留下的块（如 panic 信息）时
+// 可能与测试并发，所以对 ALLOCATOR 的访问在这里串行化
+static HEAP_LOCK: Mutex<()> = Mutex::new(());
+
+// 按线程选择分配器，释放和 realloc 按地址是否落在 ARENA 内选择
+struct Routed;
+
+impl Routed {
+    fn owns(ptr: *mut u8) -> bool {
+        let (start, end) = arena_range();
+        (start..end).contains(&(ptr as usize))
+    }
+
+    // 调用 ALLOCATOR 期间的嵌套分配（如 alloc-debug-trace 的输出）交给 std，避免重入
+    fn with_heap<R>(f: impl FnOnce() -> R) -> R {
+        let _lock = HEAP_LOCK.lock().unwrap_or_else(PoisonError::into_inner);
+        let routed = ON_HEAP.replace(false);
+        let result = f();
+        ON_HEAP.set(routed);
+        result
+    }
+}
+
+unsafe impl GlobalAlloc for Routed {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        // 测试失败时 panic 信息和回溯的分配交给 std，不占用（也不受限于）ARENA
+        if ON_HEAP.get() && !std::thread::panicking() {
+            Self::with_heap(|| unsafe { ALLOCATOR.alloc(layout) })
+        } else {
+            unsafe { System.alloc(layout) }
+        }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        if Self::owns(ptr) {
+            Self::with_heap(|| unsafe { ALLOCATOR.dealloc(ptr, layout) })
+        } else {
+            unsafe { System.dealloc(ptr, layout) }
+        }
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        if Self::owns(ptr) {
+            Self::with_heap(|| unsafe { ALLOCATOR.realloc(ptr, layout, new_size) })
+        } else {
+            unsafe { System.realloc(ptr, layout, new_size) }
+        }
+    }
+}
+
+#[global_allocator]
+static ROUTED: Routed = Routed;
+
+// 同一时间只有一个测试使用全局分配器，统计数据才有意义
+static SERIAL: Mutex<()> = Mutex::new(());
+
+// 在全局分配器上运行 f，结束后检查没有泄漏
+fn on_heap(f: impl FnOnce()) {
+    static INIT: Once = Once::new();
+
+    struct Leave;
+    impl Drop for Leave {
+        fn drop(&mut self) {
+            ON_HEAP.set(false);
+        }
+    }
+
+    let _serial = SERIAL.lock().unwrap_or_else(PoisonError::into_inner);
+    INIT.call_once(|| {
+        let (start, end) = arena_range();
+        // SAFETY: ARENA 只作为全局分配器的堆使用
+        unsafe { init_with_region(start, end) };
+    });
+
+    let before = stats().allocations;
+    {
+        let _leave = Leave;
+        ON_HEAP.set(true);
+        f();
+    }
+    assert_eq!(stats().allocations, before, "allocations leaked");
+}
+
+// 测试1：基本分配
+#[test]
+fn basic_allocations() {
+    on_heap(|| {
+        let mut xxx = vec![233];
+        xxx.push(666);
+        let mut v = Vec::new();
+        for i in 0..10 {
+            v.push(i * 10);
+        }
+
+        assert_eq!(v.len(), 10);
+        for (i, &x) in v.iter().enumerate() {
+            assert_eq!(x, i * 10);
+        }
+        assert!(v.capacity() >= 10);
+        assert_eq!(xxx, [233, 666]);
+        assert!(
+            Routed::owns(v.as_ptr() as *mut u8),
+            "not allocated on ARENA"
+        );
+    });
+}
+
+// 测试2：字符串操作
+#[test]
+fn string_operations() {
+    on_heap(|| {
+        let s = String::from("Hello allocator!");
+        let s2 = String::from("This is a test!");
+        assert_eq!(s, "Hello allocator!");
+        assert_eq!(s.len(), 16);
+        assert_eq!(s2, "This is a test!");
+        assert_eq!(s2.len(), 15);
+        assert_ne!(s, s2);
+    });
+}
+
+// 测试3：多级嵌套结构
+#[test]
+fn nested_structures() {
+    on_heap(|| {
+        let b1 = Box::new([0u8; 1024]);
+        assert_eq!(b1.len(), 1024);
+        assert!(b1.iter().all(|&x| x == 0));
+
+        let mut boxes = Vec::new();
+        for i in 0..5 {
+            boxes.push(Box::new(i * 100));
+        }
+        assert_eq!(boxes.len(), 5);
+        for (i, b) in boxes.iter().enumerate() {
+            assert_eq!(**b, i * 100);
+        }
+    });
+}
+
+// 测试4：链表
+#[test]
+fn linked_list() {
+    on_heap(|| {
+        let mut list = LinkedList::new();
+        for i in 0..10 {
+            list.push_back(i);
+            list.push_front(i + 10);
+        }
+        assert_eq!(list.len(), 20);
+        assert_eq!(list.iter().filter(|&&x| x < 10).count(), 10);
+        assert_eq!(list.iter().filter(|&&x| x >= 10).count(), 10);
+    });
+}
+
+// 测试5：大规模分配和释放
+#[test]
+fn large_scale_allocations() {
+    on_heap(|| {
+        let mut chunks: Vec<Box<[u8]>> = Vec::new();
+        for i in 0..100 {
+            chunks.push(Box::new([i as u8; 16]));
+        }
+        for (i, chunk) in chunks.iter().enumerate() {
+            assert_eq!(chunk.len(), 16);
+            assert!(chunk.iter().all(|&x| x == i as u8));
+        }
+
+        // 释放一半
+        chunks.drain(0..25);
+        assert_eq!(chunks.len(), 75);
+        let mut chunks = chunks.split_off(25);
+        assert_eq!(chunks.len(), 50);
+        for (i, chunk) in chunks.iter().enumerate() {
+            assert_eq!(chunk.len(), 16);
+            assert_eq!(chunk[0], (i + 50) as u8);
+        }
+
+        // 再分配不同大小的块
+        for i in 0..25 {
+            chunks.push(Box::new([(i + 100) as u8; 16]));
+        }
+        for i in 0..25 {
+            chunks.push(Box::new([(i + 125) as u8; 32]));
+        }
+        assert_eq!(chunks.len(), 100);
+        for i in 0..25 {
+            assert_eq!(chunks[50 + i].len(), 16);
+            assert!(chunks[50 + i].iter().all(|&x| x == (i + 100) as u8));
+            assert_eq!(chunks[75 + i].len(), 32);
+            assert!(chunks[75 + i].iter().all(|&x| x == (i + 125) as u8));
+        }
+    });
+}
+
+// 测试6：内存重用
+#[test]
+fn memory_reuse() {
+    on_heap(|| {
+        let temp = Box::new([1, 2, 3, 4, 5]);
+        assert_eq!(*temp, [1, 2, 3, 4, 5]);
+        let new_temp = Box::new([6, 7, 8, 9, 10]);
+        assert_eq!(*new_temp, [6, 7, 8, 9, 10]);
+        // 确保没有被覆盖
+        assert_eq!(*temp, [1, 2, 3, 4, 5]);
+    });
+}
+
+// 测试7：混合类型
+#[test]
+fn mixed_types() {
+    on_heap(|| {
+        let vec1 = vec![1, 2, 3];
+        let vec2 = vec![4.0, 5.0, 6.0];
+        let string1 = String::from("First string");
+        let string2 = String::from("Second string");
+        let box1 = Box::new(42);
+        let box2 = Box::new(1.5);
+
+        assert_eq!(vec1, [1, 2, 3]);
+        assert_eq!(vec2, [4.0, 5.0, 6.0]);
+        assert_eq!(string1, "First string");
+        assert_eq!(string2, "Second string");
+        assert_eq!(*box1, 42);
+
+        let sum = vec1.iter().sum::<i32>() as f64 + vec2.iter().sum::<f64>() + *box1 as f64 + *box2;
+        assert!((sum - (6.0 + 15.0 + 42.0 + 1.5)).abs() < 0.00001);
+    });
+}
+
+// 测试8：内存压力
+#[test]
+fn memory_stress() {
+    on_heap(|| {
+        let mut allocations = Vec::new();
+        for size_power in 0..6 {
+            let size = 1 << size_power;
+            for _ in 0..10 {
+                allocations.push(vec![size as u8; size]);
+            }
+        }
+        for (idx, data) in allocations.iter().enumerate() {
+            let size = 1 << (idx / 10);
+            assert_eq!(data.len(), size);
+            assert!(data.iter().all(|&x| x == size as u8));
+        }
+
+        // 间隔释放一部分
+        let mut remaining = allocations.len();
+        let mut i = 0;
+        while i < allocations.len() {
+            allocations.remove(i);
+            remaining -= 1;
+            i += 2;
+        }
+        assert_eq!(allocations.len(), remaining);
+    });
+}
+
+// 测试9：分配器状态
+#[test]
+fn allocator_state() {
+    on_heap(|| {
+        let a = Box::new(1);
+        let b = Box::new(2);
+        let c = Box::new(3);
+        assert_eq!((*a, *b, *c), (1, 2, 3));
+
+        let live = stats().allocations;
+        drop(b);
+        assert_eq!(stats().allocations, live - 1);
+        assert_eq!((*a, *c), (1, 3));
+
+        let d = Box::new(4);
+        assert_eq!(*a + *c + *d, 8);
+    });
+}
+
+// 测试10：读写正确性
+#[test]
+fn read_write_correctness() {
+    on_heap(|| {
+        let vec1 = vec![1, 2, 3, 4, 5, 233, 666, 114514, 0x114514];
+        assert_eq!(vec1[5], 233);
+        assert_eq!(vec1[7], 114514);
+        assert_eq!(vec1[8], 0x114514);
+
+        let vec2 = vec![233; 233];
+        assert_eq!(vec2.len(), 233);
+        assert!(vec2.iter().all(|&x| x == 233));
+
+        let s1 = String::from("你好！");
+        let s2 = String::from("hk128");
+        assert_eq!(format!("{} {}", s1, s2), "你好！ hk128");
+
+        let mut mutable_string = String::from("Hello");
+        mutable_string.push_str(" World!");
+        assert_eq!(mutable_string, "Hello World!");
+
+        let mut mutable_vec = vec![1, 2, 3];
+        mutable_vec.push(4);
+        mutable_vec.push(5);
+        assert_eq!(mutable_vec, [1, 2, 3, 4, 5]);
+
+        let mut boxed_value = Box::new(42);
+        *boxed_value = 100;
+        assert_eq!(*boxed_value, 100);
+
+        let mut boxed_array = Box::new([0u8; 10]);
+        for (i, x) in boxed_array.iter_mut().enumerate() {
+            *x = i as u8;
+        }
+        for (i, &x) in boxed_array.iter().enumerate() {
+            assert_eq!(x, i as u8);
+        }
+
+        struct Point {
+            x: i32,
+            y: i32,
+        }
+        let boxed_point = Box::new(Point { x: 10, y: 20 });
+        assert_eq!((boxed_point.x, boxed_point.y), (10, 20));
+
+        // 新的分配不会覆盖已有的内容
+        let persistent = String::from("This should persist");
+        let another = String::from("Another allocation");
+        assert_eq!(persistent, "This should persist");
+        assert_eq!(another, "Another allocation");
+    });
+}
+
+// 测试11：随机动态验证
+#[test]
+fn random_dynamic_verification() {
+    on_heap(|| {
+        let mut rng = XorShift(0x1234567890ABCDEF);
+        let mut vec1 = Vec::new();
+        let mut vec2 = Vec::new();
+        let mut vec3 = Vec::new();
+
+        let check = |vec1: &Vec<i64>, vec2: &Vec<i64>, vec3: &Vec<i64>| {
+            for i in 0..vec1.len() {
+                assert_eq!(vec2[i], vec1[i] + 1, "vec2[{}] mismatch", i);
+                assert_eq!(vec3[i], vec1[i] * 2, "vec3[{}] mismatch", i);
+            }
+        };
+
+        for i in 0..50 {
+            let value = rng.range(-1000, 1000);
+            vec1.push(value);
+            vec2.push(value + 1);
+            vec3.push(value * 2);
+            if i % 10 == 9 {
+                check(&vec1, &vec2, &vec3);
+            }
+        }
+
+        // 随机修改一些元素
+        for _ in 0..10 {
+            let idx = rng.below(vec1.len());
+            let value = rng.range(-500, 500);
+            vec1[idx] = value;
+            vec2[idx] = value + 1;
+            vec3[idx] = value * 2;
+        }
+        check(&vec1, &vec2, &vec3);
+
+        // 继续增长，触发 realloc
+        for _ in 0..20 {
+            let value = rng.range(-2000, 2000);
+            vec1.push(value);
+            vec2.push(value + 1);
+            vec3.push(value * 2);
+        }
+        assert_eq!((vec1.len(), vec2.len(), vec3.len()), (70, 70, 70));
+        check(&vec1, &vec2, &vec3);
+        assert_eq!(vec2, vec1.iter().map(|&x| x + 1).collect::<Vec<_>>());
+        assert_eq!(vec3, vec1.iter().map(|&x| x * 2).collect::<Vec<_>>());
+    });
+}
+
+// 分配 size 字节并写满，写入的内容读回不一致时返回 None
+fn try_allocate_and_write(size: usize, fill: u8) -> Option<Vec<u8>> {
+    let mut vec = Vec::new();
+    vec.try_reserve_exact(size).ok()?;
+    vec.resize(size, fill);
+    if vec.iter().any(|&x| x != fill) {
+        return None;
+    }
+    if size >= 4 {
+        vec[0] = 0xAA;
+        vec[size / 2] = 0xBB;
+        vec[size - 1] = 0xCC;
+        if vec[0] != 0xAA || vec[size / 2] != 0xBB || vec[size - 1] != 0xCC {
+            return None;
+        }
+    }
+    Some(vec)
+}
+
+// 扫盘：指数增长直到失败，再二分查找最大可分配大小，最后用小块填充剩余空间。
+// 返回 (最大成功分配, 总分配字节数)
+fn memory_sweep(phase: u8) -> (usize, usize) {
+    let mut allocations = Vec::new();
+    let mut total = 0;
+    let mut max_successful = 0;
+
+    // 阶段1：指数增长
+    let mut size = 1;
+    while size <= 4 * 1024 * 1024 {
+        let Some(vec) = try_allocate_and_write(size, phase) else {
+            break;
+        };
+        allocations.push(vec);
+        total += size;
+        max_successful = size;
+        size *= 2;
+    }
+
+    // 阶段2：二分查找
+    let (mut low, mut high) = (max_successful, size);
+    while high - low > 1 {
+        let mid = low + (high - low) / 2;
+        if let Some(vec) = try_allocate_and_write(mid, phase) {
+            allocations.push(vec);
+            total += mid;
+            max_successful = max_successful.max(mid);
+            low = mid;
+        } else {
+            high = mid;
+        }
+    }
+
+    // 阶段3：用小块填充剩余的碎片
+    for shift in 0..17 {
+        let size = 1 << shift;
+        if let Some(vec) = try_allocate_and_write(size, phase) {
+            allocations.push(vec);
+            total += size;
+        }
+    }
+
+    (max_successful, total)
+}
+
+// 测试12：两次扫盘，结果应当相近，且堆在之后仍然可用
+#[test]
+#[cfg_attr(feature = "alloc-bump", ignore = "bump 分配器不回收按非栈顺序释放的块")]
+fn memory_sweep_twice() {
+    on_heap(|| {
+        let (max1, total1) = memory_sweep(1);
+        for _ in 0..100 {
+            let _temp = Box::new([0u8; 64]);
+        }
+        let (max2, total2) = memory_sweep(2);
+
+        assert!(max1 > ARENA_SIZE / 4, "first sweep only got {} bytes", max1);
+        assert!(
+            max2 > ARENA_SIZE / 4,
+            "second sweep only got {} bytes",
+            max2
+        );
+        // 允许 10% 的差异（碎片和分配器开销）
+        assert!(max1.abs_diff(max2) <= max1 / 10, "{} vs {}", max1, max2);
+        assert!(
+            total1.abs_diff(total2) <= max1 / 10,
+            "{} vs {}",
+            total1,
+            total2
+        );
+
+        // 扫盘之后分配器仍然可用
+        let mut test2 = Vec::with_capacity(4096);
+        test2.resize(4096, 0xDDu8);
+        test2[2048] = 0x22;
+        assert_eq!((test2[0], test2[2048], test2[4095]), (0xDD, 0x22, 0xDD));
+    });
+}
+
+#[test]
+fn heap_matches_model() {
+    for seed in [0x1234_5678_9ABC_DEF0, 1, 0xDEAD_BEEF, 42] {
+        let mut region = [0u8; 64 * 1024];
+        check_heap_against_model(&mut region, seed, 5000);
+    }
+}
+
+// 首次适应和 TLSF 各自的 resize_in_place，与板上测试14 相同
+#[test]
+fn resize_in_place_per_backend() {
+    let mut region = vec![0u8; 64 * 1024].into_boxed_slice();
+    check_resize_in_place("first-fit", &mut FirstFitHeap::new(), &mut region);
+    check_resize_in_place("tlsf", &mut TlsfHeap::new(), &mut region);
+}
+
+// 所选实现上的 Heap::reallocate：能原地扩大/收缩时返回原指针，否则复制到新块
+#[test]
+fn reallocate_in_place_or_copy() {
+    let mut region = vec![0u8; 64 * 1024].into_boxed_slice();
+    let mut heap = Heap::new(&mut region);
+    let layout = Layout::from_size_align(64, 8).unwrap();
+
+    unsafe {
+        // 释放紧跟在 a 后面的 b，a 可以原地扩大（bump 下 a 重新成为最后一块）
+        let a = heap.allocate(layout).unwrap();
+        let b = heap.allocate(layout).unwrap();
+        a.as_ptr().write_bytes(0xA5, 64);
+        heap.deallocate(b, layout);
+        let grown = heap.reallocate(a, layout, 120).unwrap();
+        assert_eq!(grown, a, "grow into free neighbour moved the block");
+        assert!((0..64).all(|i| *a.as_ptr().add(i) == 0xA5));
+
+        // 收缩把尾部还给堆
+        let before = heap.stats();
+        let layout = Layout::from_size_align(120, 8).unwrap();
+        let shrunk = heap.reallocate(a, layout, 16).unwrap();
+        assert_eq!(shrunk, a, "shrink moved the block");
+        assert!(heap.stats().free > before.free, "tail not released");
+        assert!((0..16).all(|i| *a.as_ptr().add(i) == 0xA5));
+
+        // c 挡在后面，无法原地扩大，复制到新块并保留内容
+        let layout = Layout::from_size_align(16, 8).unwrap();
+        let c = heap.allocate(layout).unwrap();
+        let moved = heap.reallocate(a, layout, 4096).unwrap();
+        assert_ne!(moved, a, "grew over a live block");
+        assert_eq!(moved.as_ptr() as usize % 8, 0);
+        assert!(
+            (0..16).all(|i| *moved.as_ptr().add(i) == 0xA5),
+            "data lost in copy"
+        );
+        assert_eq!(heap.stats().allocations, 2);
+
+        heap.deallocate(moved, Layout::from_size_align(4096, 8).unwrap());
+        heap.deallocate(c, layout);
+    }
+    assert_eq!(heap.stats().allocations, 0);
+    assert_eq!(heap.stats().used, 0, "memory leaked");
+}
+
+// 损坏检测：越界写、重复释放和非法指针都返回确切的错误（同板上的 check_corruption_detection）
+#[cfg(feature = "alloc-checked")]
+#[test]
+fn checked_heap_reports_corruption() {
+    use super::MIN_ALIGN;
+    use super::checked::CheckedHeap;
+
+    let mut region = vec![0u8; 64 * 1024].into_boxed_slice();
+    let start = region.as_mut_ptr() as usize;
+    let mut heap = CheckedHeap::new();
+    let layout = Layout::from_size_align(13, 1).unwrap();
+
+    unsafe {
+        heap.init(start, start + region.len());
+        let a = heap.alloc_impl(layout);
+        let b = heap.alloc_impl(layout);
+        assert!(!a.is_null() && !b.is_null());
+        b.write_bytes(0x5A, 13);
+        assert_eq!(heap.try_verify(), Ok(2));
+        assert!(heap.check(a).is_ok());
+
+        // 越界写一个字节，破坏尾部金丝雀
+        let saved = *a.add(13);
+        *a.add(13) ^= 0xFF;
+        let tail = (a as usize + 13, "tail canary overwritten");
+        assert_eq!(heap.try_verify(), Err(tail));
+        assert_eq!(heap.check(a).map(|_| ()), Err(tail));
+        *a.add(13) = saved;
+        assert_eq!(heap.try_verify(), Ok(2));
+
+        // 释放之后再次释放
+        heap.dealloc_impl(a, layout);
+        assert_eq!(heap.check(a).map(|_| ()), Err((a as usize, "double free")));
+
+        // 堆外的指针、未对齐的指针和分配内部的指针
+        let local = 0u32;
+        let outside = &local as *const u32 as *mut u8;
+        assert_eq!(
+            heap.check(outside).map(|_| ()),
+            Err((outside as usize, "pointer not allocated from heap"))
+        );
+        assert_eq!(
+            heap.check(b.add(1)).map(|_| ()),
+            Err((b as usize + 1, "pointer not allocated from heap"))
+        );
+        assert_eq!(
+            heap.check(b.add(MIN_ALIGN)).map(|_| ()),
+            Err((
+                b as usize + MIN_ALIGN,
+                "invalid pointer or corrupted header"
+            ))
+        );
+
+        heap.dealloc_impl(b, layout);
+        assert_eq!(heap.try_verify(), Ok(0));
+    }
+}
+
+// check_alignments 使用的分配接口，独立的 Heap 和全局分配器各实现一次
+trait AlignedAlloc {
+    unsafe fn alloc(&mut self, layout: Layout) -> *mut u8;
+    unsafe fn realloc(&mut self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8;
+    unsafe fn dealloc(&mut self, ptr: *mut u8, layout: Layout);
+}
+
+impl AlignedAlloc for Heap<'_> {
+    unsafe fn alloc(&mut self, layout: Layout) -> *mut u8 {
+        self.allocate(layout)
+            .map_or(core::ptr::null_mut(), NonNull::as_ptr)
+    }
+
+    unsafe fn realloc(&mut self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let ptr = NonNull::new(ptr).unwrap();
+        unsafe { self.reallocate(ptr, layout, new_size) }
+            .map_or(core::ptr::null_mut(), NonNull::as_ptr)
+    }
+
+    unsafe fn dealloc(&mut self, ptr: *mut u8, layout: Layout) {
+        unsafe { self.deallocate(NonNull::new(ptr).unwrap(), layout) }
+    }
+}
+
+// 经由 Routed 走全局分配器，需要在 on_heap 中使用
+struct Global;
+
+impl AlignedAlloc for Global {
+    unsafe fn alloc(&mut self, layout: Layout) -> *mut u8 {
+        unsafe { std::alloc::alloc(layout) }
+    }
+
+    unsafe fn realloc(&mut self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        unsafe { std::alloc::realloc(ptr, layout, new_size) }
+    }
+
+    unsafe fn dealloc(&mut self, ptr: *mut u8, layout: Layout) {
+        unsafe { std::alloc::dealloc(ptr, layout) }
+    }
+}
+
+fn fill(ptr: *mut u8, size: usize, tag: u8) {
+    for i in 0..size {
+        unsafe { ptr.add(i).write(tag.wrapping_add(i as u8)) };
+    }
+}
+
+fn check_fill(ptr: *mut u8, size: usize, tag: u8) {
+    for i in 0..size {
+        let byte = unsafe { ptr.add(i).read() };
+        assert_eq!(
+            byte,
+            tag.wrapping_add(i as u8),
+            "data at {:p}+{} corrupted",
+            ptr,
+            i
+        );
+    }
+}
+
+// 1..=4096 的每种对齐：地址满足对齐、整块可读写；扩大和收缩后仍然对齐且内容保留，
+// 最后释放所有块
+fn check_alignments(heap: &mut impl AlignedAlloc) {
+    for shift in 0..=12 {
+        let align = 1usize << shift;
+        let sizes = [1, align / 2 + 1, align, 2 * align + 3];
+        let mut blocks = Vec::new();
+
+        for (i, &size) in sizes.iter().enumerate() {
+            let layout = Layout::from_size_align(size, align).unwrap();
+            let ptr = unsafe { heap.alloc(layout) };
+            assert!(!ptr.is_null(), "alloc {:?} failed", layout);
+            assert_eq!(ptr as usize % align, 0, "alloc {:?} misaligned", layout);
+            let tag = (shift * 16 + i) as u8;
+            fill(ptr, size, tag);
+            blocks.push((ptr, layout, tag));
+        }
+
+        for (ptr, layout, tag) in blocks.iter_mut() {
+            check_fill(*ptr, layout.size(), *tag);
+
+            // 扩大：原内容保留，新的部分可写
+            let grown = layout.size() + align + 5;
+            let new_ptr = unsafe { heap.realloc(*ptr, *layout, grown) };
+            assert!(!new_ptr.is_null(), "grow {:?} to {} failed", layout, grown);
+            assert_eq!(new_ptr as usize % align, 0, "grown block misaligned");
+            check_fill(new_ptr, layout.size(), *tag);
+            fill(new_ptr, grown, *tag);
+            *ptr = new_ptr;
+            *layout = Layout::from_size_align(grown, align).unwrap();
+
+            // 收缩：前面的内容保留
+            let shrunk = layout.size() / 3 + 1;
+            let new_ptr = unsafe { heap.realloc(*ptr, *layout, shrunk) };
+            assert!(
+                !new_ptr.is_null(),
+                "shrink {:?} to {} failed",
+                layout,
+                shrunk
+            );
+            assert_eq!(new_ptr as usize % align, 0, "shrunk block misaligned");
+            check_fill(new_ptr, shrunk, *tag);
+            *ptr = new_ptr;
+            *layout = Layout::from_size_align(shrunk, align).unwrap();
+        }
+
+        for (ptr, layout, tag) in blocks {
+            check_fill(ptr, layout.size(), tag);
+            unsafe { heap.dealloc(ptr, layout) };
+        }
+    }
+}
+
+// 各个实现分别用 --features alloc-tlsf / alloc-bump / alloc-checked 运行
+#[test]
+fn heap_over_aligned() {
+    let mut region = vec![0u8; 1024 * 1024].into_boxed_slice();
+    let mut heap = Heap::new(&mut region);
+    let initial = heap.stats();
+
+    check_alignments(&mut heap);
+
+    let stats = heap.stats();
+    assert_eq!(stats.allocations, 0);
+    assert_eq!(stats.used, 0, "memory leaked");
+    #[cfg(not(feature = "alloc-bump"))]
+    assert_eq!(stats.free, initial.free);
+}
+
+#[test]
+fn global_over_aligned() {
+    on_heap(|| check_alignments(&mut Global));
+}
+
+// 基准测试：首次适应与 TLSF 跑完全相同的随机分配/释放序列（同板上的 bench()），
+// 板上以周期计，这里以纳秒计。`cargo test --release --lib bench -- --nocapture` 查看结果
+const BENCH_HEAP_SIZE: usize = 64 * 1024;
+const BENCH_ROUNDS: usize = 20_000;
+
+// 从第一次调用起经过的纳秒数
+fn nanos() -> u64 {
+    static START: OnceLock<Instant> = OnceLock::new();
+    START.get_or_init(Instant::now).elapsed().as_nanos() as u64
+}
+
+#[test]
+fn bench_firstfit_vs_tlsf() {
+    let mut region = vec![0u8; BENCH_HEAP_SIZE].into_boxed_slice();
+    let firstfit = run_bench(&mut FirstFitHeap::new(), &mut region, BENCH_ROUNDS, nanos);
+    let tlsf = run_bench(&mut TlsfHeap::new(), &mut region, BENCH_ROUNDS, nanos);
+
+    for (name, r) in [("first-fit", &firstfit), ("tlsf", &tlsf)] {
+        std::println!(
+            "[BENCH] {}: {} allocs (avg {} / worst {} ns), {} frees (avg {} / worst {} ns), {} failures",
+            name,
+            r.allocs,
+            r.alloc_avg(),
+            r.alloc_worst,
+            r.frees,
+            r.free_avg(),
+            r.free_worst,
+            r.failures
+        );
+        // 两者跑的是同一序列，堆足够大时都不应该失败
+        assert!(r.allocs as usize > BENCH_ROUNDS / 4);
+        assert_eq!(r.failures, 0);
+    }
+}
@@ -0,0 +1,526 @@
+//! TLSF（Two-Level Segregated Fit）分配器，启用 `alloc-tlsf` 特性时作为全局分配器
+//!
+//! 空闲块按大小分为两级：一级按 2 的幂划分，二级再把每个区间等分为 `SL_COUNT` 份，
+//! 每个 (一级, 二级) 对应一个空闲链表，并用位图记录哪些链表非空。
+//! 分配时通过位图直接定位到足够大的链表，释放时只与物理相邻的块合并，
+//! 所以分配和释放都是 O(1)，不会像首次适应那样随着碎片增多而变慢
+
+use core::alloc::Layout;
+use core::ptr::null_mut;
+
+use super::{Counters, HeapStats, RegionStats, RegionTable, align_up};
+
+// 块头部：所有块（空闲/已分配）共用
+#[repr(C)]
+struct BlockHeader {
+    prev_phys: *mut BlockHeader, // 物理上相邻的前一个块，区域首块为 null
+    size: usize,                 // 块的总大小（包括头部），最低位为空闲标志
+}
+
+// 空闲块：头部之后保存空闲链表指针
+#[repr(C)]
+struct FreeBlock {
+    header: BlockHeader,
+    next_free: *mut FreeBlock,
+    prev_free: *mut FreeBlock,
+}
+
+const FLAG_FREE: usize = 1;
+const FLAG_MASK: usize = 3;
+
+const HEADER_SIZE: usize = core::mem::size_of::<BlockHeader>();
+// 内存对齐要求，同时保证 size 的低两位可以用作标志位
+pub(super) const MIN_ALIGN: usize = core::mem::align_of::<FreeBlock>();
+// 空闲块需要放得下链表指针
+const MIN_BLOCK_SIZE: usize = core::mem::size_of::<FreeBlock>();
+
+// 二级索引：每个一级区间再分为 16 份
+const SL_INDEX_LOG2: u32 = 4;
+const SL_COUNT: usize = 1 << SL_INDEX_LOG2;
+// 小于 SMALL_BLOCK_SIZE 的块全部放在一级索引 0 中，按 MIN_ALIGN 线性划分
+const FL_INDEX_SHIFT: u32 = SL_INDEX_LOG2 + MIN_ALIGN.trailing_zeros();
+const SMALL_BLOCK_SIZE: usize = 1 << FL_INDEX_SHIFT;
+// 块大小必须小于 2^FL_INDEX_MAX（2 GiB），足够覆盖全部 RAM。
+// 最高位为 FL_INDEX_SHIFT..FL_INDEX_MAX 的块依次放在一级索引 1..FL_COUNT 中
+const FL_INDEX_MAX: u32 = 31;
+const FL_COUNT: usize = (FL_INDEX_MAX - FL_INDEX_SHIFT + 1) as usize;
+// 单个块的上限，更大的区域只使用前面这一部分
+const MAX_BLOCK_SIZE: usize = (1 << FL_INDEX_MAX) - MIN_ALIGN;
+
+impl BlockHeader {
+    fn size(&self) -> usize {
+        self.size & !FLAG_MASK
+    }
+
+    fn is_free(&self) -> bool {
+        self.size & FLAG_FREE != 0
+    }
+
+    fn set_size(&mut self, size: usize) {
+        self.size = size | (self.size & FLAG_MASK);
+    }
+
+    fn set_free(&mut self, free: bool) {
+        if free {
+            self.size |= FLAG_FREE;
+        } else {
+            self.size &= !FLAG_FREE;
+        }
+    }
+
+    // 物理上相邻的下一个块
+    fn next_phys(&self) -> *mut BlockHeader {
+        (self as *const _ as usize + self.size()) as *mut BlockHeader
+    }
+
+    fn data_addr(&self) -> usize {
+        self as *const _ as usize + HEADER_SIZE
+    }
+}
+
+// 块大小 -> (一级索引, 二级索引)，用于插入空闲块
+fn mapping_insert(size: usize) -> (usize, usize) {
+    if size < SMALL_BLOCK_SIZE {
+        (0, size / (SMALL_BLOCK_SIZE / SL_COUNT))
+    } else {
+        let fl = usize::BITS - 1 - size.leading_zeros();
+        let sl = (size >> (fl - SL_INDEX_LOG2)) ^ SL_COUNT;
+        ((fl - FL_INDEX_SHIFT + 1) as usize, sl)
+    }
+}
+
+// 请求大小 -> (一级索引, 二级索引)，向上取整到下一个链表，保证链表中任意块都够大
+fn mapping_search(size: usize) -> (usize, usize) {
+    let size = if size >= SMALL_BLOCK_SIZE {
+        let round = (1 << (usize::BITS - 1 - size.leading_zeros() - SL_INDEX_LOG2)) - 1;
+        size + round
+    } else {
+        size
+    };
+    mapping_insert(size)
+}
+
+/// TLSF 分配器
+pub(super) struct TlsfHeap {
+    fl_bitmap: u32,
+    sl_bitmap: [u32; FL_COUNT],
+    blocks: [[*mut FreeBlock; SL_COUNT]; FL_COUNT],
+    regions: RegionTable,
+    counters: Counters,
+    initialized: bool,
+}
+
+impl TlsfHeap {
+    pub(super) const fn new() -> Self {
+        Self {
+            fl_bitmap: 0,
+            sl_bitmap: [0; FL_COUNT],
+            blocks: [[null_mut(); SL_COUNT]; FL_COUNT],
+            regions: RegionTable::new(),
+            counters: Counters::new(),
+            initialized: false,
+        }
+    }
+
+    // 初始化堆内存（丢弃已有区域，只保留 [heap_start, heap_end)）
+    pub(super) unsafe fn init(&mut self, heap_start: usize, heap_end: usize) {
+        println!("TlsfHeap::init: starting");
+
+        if self.initialized {
+            let allocated = self.regions.allocated();
+            if allocated > 0 {
+                println!(
+                    "TlsfHeap::init: ERROR - {} blocks still allocated",
+                    allocated
+                );
+                panic!("Heap already in use");
+            }
+            println!("TlsfHeap::init: re-initializing unused heap");
+        }
+
+        self.fl_bitmap = 0;
+        self.sl_bitmap = [0; FL_COUNT];
+        self.blocks = [[null_mut(); SL_COUNT]; FL_COUNT];
+        self.regions.clear();
+        self.counters.reset();
+        self.initialized = false;
+
+        if heap_end <= heap_start {
+            println!("TlsfHeap::init: ERROR - heap_start >= heap_end");
+            panic!("Invalid heap region");
+        }
+
+        // SAFETY: 调用者确保区域有效
+        unsafe {
+            self.add_region(heap_start, heap_end - heap_start);
+        }
+    }
+
+    // 注册一块新的堆区域 [start, start + len)
+    //
+    // 区域末尾放一个只有头部、永远处于已分配状态的哨兵块，
+    // 这样区域内最后一个块释放时不会越过区域边界去合并
+    pub(super) unsafe fn add_region(&mut self, start: usize, len: usize) {
+        println!("TlsfHeap::add_region: start=0x{:08x}, len={}", start, len);
+
+        // 整个区域是一个空闲块，不能超出一级索引的范围
+        let len = len.min(MAX_BLOCK_SIZE);
+        let (heap_start, heap_end) =
+            self.regions
+                .push(start, len, MIN_ALIGN, MIN_BLOCK_SIZE + HEADER_SIZE);
+        let sentinel_addr = heap_end - HEADER_SIZE;
+
+        let block = heap_start as *mut BlockHeader;
+        let sentinel = sentinel_addr as *mut BlockHeader;
+        // SAFETY: 两个头部都位于刚登记的区域内且已对齐
+        unsafe {
+            (*block).prev_phys = null_mut();
+            (*block).size = sentinel_addr - heap_start;
+            (*sentinel).prev_phys = block;
+            (*sentinel).size = HEADER_SIZE;
+            self.insert_free(block as *mut FreeBlock);
+        }
+        self.initialized = true;
+
+        println!(
+            "TlsfHeap::add_region: region {} added, heap_start=0x{:08x}, heap_end=0x{:08x}",
+            self.regions.len() - 1,
+            heap_start,
+            heap_end
+        );
+    }
+
+    // 把空闲块挂到对应链表头部
+    unsafe fn insert_free(&mut self, block: *mut FreeBlock) {
+        // SAFETY: 调用者确保 block 是有效的空闲块
+        unsafe {
+            (*block).header.set_free(true);
+            let (fl, sl) = mapping_insert((*block).header.size());
+            let head = self.blocks[fl][sl];
+            (*block).next_free = head;
+            (*block).prev_free = null_mut();
+            if !head.is_null() {
+                (*head).prev_free = block;
+            }
+            self.blocks[fl][sl] = block;
+            self.sl_bitmap[fl] |= 1 << sl;
+            self.fl_bitmap |= 1 << fl;
+        }
+    }
+
+    // 从链表中摘除空闲块
+    unsafe fn remove_free(&mut self, block: *mut FreeBlock) {
+        // SAFETY: 调用者确保 block 当前在空闲链表中
+        unsafe {
+            let (fl, sl) = mapping_insert((*block).header.size());
+            let next = (*block).next_free;
+            let prev = (*block).prev_free;
+            if !next.is_null() {
+                (*next).prev_free = prev;
+            }
+            if !prev.is_null() {
+                (*prev).next_free = next;
+            } else {
+                self.blocks[fl][sl] = next;
+                if next.is_null() {
+                    self.sl_bitmap[fl] &= !(1 << sl);
+                    if self.sl_bitmap[fl] == 0 {
+                        self.fl_bitmap &= !(1 << fl);
+                    }
+                }
+            }
+            (*block).header.set_free(false);
+        }
+    }
+
+    // 通过位图找到第一个不小于 (fl, sl) 的非空链表
+    fn find_suitable(&self, fl: usize, sl: usize) -> *mut FreeBlock {
+        let mut fl = fl;
+        let mut sl_map = self.sl_bitmap[fl] & (!0u32).checked_shl(sl as u32).unwrap_or(0);
+        if sl_map == 0 {
+            let fl_map = self.fl_bitmap & (!0u32).checked_shl(fl as u32 + 1).unwrap_or(0);
+            if fl_map == 0 {
+                return null_mut();
+            }
+            fl = fl_map.trailing_zeros() as usize;
+            sl_map = self.sl_bitmap[fl];
+        }
+        let sl = sl_map.trailing_zeros() as usize;
+        self.blocks[fl][sl]
+    }
+
+    pub(super) unsafe fn alloc_impl(&mut self, layout: Layout) -> *mut u8 {
+        println!("TlsfHeap::alloc_impl: layout={:?}", layout);
+
+        if !self.initialized {
+            println!("alloc_impl: ERROR - not initialized");
+            self.counters.record_failure();
+            return null_mut();
+        }
+
+        let align = layout.align().max(MIN_ALIGN);
+        let size = (HEADER_SIZE + align_up(layout.size(), align)).max(MIN_BLOCK_SIZE);
+        let size = align_up(size, MIN_ALIGN);
+
+        let (fl, sl) = mapping_search(size);
+        if fl >= FL_COUNT {
+            println!("alloc_impl: size {} too large", size);
+            self.counters.record_failure();
+            return null_mut();
+        }
+
+        let block = self.find_suitable(fl, sl);
+        if block.is_null() {
+            println!("TlsfHeap::alloc_impl: NO suitable block found");
+            self.counters.record_failure();
+            return null_mut();
+        }
+
+        // SAFETY: block 来自空闲链表
+        unsafe {
+            let block_size = (*block).header.size();
+            self.remove_free(block);
+
+            let header = block as *mut BlockHeader;
+            if block_size - size >= MIN_BLOCK_SIZE {
+                // 分割：剩余部分作为新的空闲块
+                let rest = (header as usize + size) as *mut BlockHeader;
+                (*rest).prev_phys = header;
+                (*rest).size = block_size - size;
+                (*(*rest).next_phys()).prev_phys = rest;
+                (*header).set_size(size);
+                self.insert_free(rest as *mut FreeBlock);
+            }
+
+            self.regions.record_alloc(header as usize);
+            self.counters.record_alloc((*header).size());
+
+            let data_addr = (*header).data_addr();
+            println!(
+                "alloc_impl: block at 0x{:08x}, size={}, returning 0x{:08x}",
+                header as usize,
+                (*header).size(),
+                data_addr
+            );
+            data_addr as *mut u8
+        }
+    }
+
+    pub(super) unsafe fn dealloc_impl(&mut self, ptr: *mut u8, layout: Layout) {
+        println!(
+            "TlsfHeap::dealloc_impl: ptr=0x{:p}, layout={:?}",
+            ptr, layout
+        );
+
+        if ptr.is_null() || !self.initialized {
+            return;
+        }
+
+        let mut header = (ptr as usize - HEADER_SIZE) as *mut BlockHeader;
+
+        // SAFETY: ptr 由本分配器分配，头部以及物理相邻的块都有效
+        unsafe {
+            let size = (*header).size();
+            self.regions.record_free(header as usize);
+            self.counters.record_free(size);
+
+            // 与前一个空闲块合并
+            let prev = (*header).prev_phys;
+            if !prev.is_null() && (*prev).is_free() {
+                let prev_size = (*prev).size();
+                self.remove_free(prev as *mut FreeBlock);
+                (*prev).set_size(prev_size + (*header).size());
+                header = prev;
+            }
+
+            // 与后一个空闲块合并（区域末尾的哨兵块永远不空闲）
+            let next = (*header).next_phys();
+            if (*next).is_free() {
+                let next_size = (*next).size();
+                self.remove_free(next as *mut FreeBlock);
+                (*header).set_size((*header).size() + next_size);
+            }
+
+            (*(*header).next_phys()).prev_phys = header;
+            self.insert_free(header as *mut FreeBlock);
+        }
+    }
+
+    // 原地调整已分配块的大小，使数据区至少有 new_size 字节
+    //
+    // 扩大时吞并物理上相邻的下一个空闲块，收缩时把多余的尾部切成空闲块。
+    // 无法原地扩大时返回 false，块保持不变
+    pub(super) unsafe fn resize_in_place(&mut self, ptr: *mut u8, new_size: usize) -> bool {
+        println!(
+            "TlsfHeap::resize_in_place: ptr=0x{:p}, new_size={}",
+            ptr, new_size
+        );
+
+        let header = (ptr as usize - HEADER_SIZE) as *mut BlockHeader;
+        let size = (HEADER_SIZE + align_up(new_size, MIN_ALIGN)).max(MIN_BLOCK_SIZE);
+
+        // SAFETY: ptr 由本分配器分配，头部以及物理相邻的块都有效
+        unsafe {
+            let old_size = (*header).size();
+
+            if size > old_size {
+                let next = (*header).next_phys();
+                if !(*next).is_free() || old_size + (*next).size() < size {
+                    println!("resize_in_place: cannot grow in place");
+                    return false;
+                }
+                let next_size = (*next).size();
+                self.remove_free(next as *mut FreeBlock);
+                (*header).set_size(old_size + next_size);
+                (*(*header).next_phys()).prev_phys = header;
+            }
+
+            let block_size = (*header).size();
+            if block_size - size >= MIN_BLOCK_SIZE {
+                // 多余的尾部作为空闲块，与后面的空闲块合并
+                let rest = (header as usize + size) as *mut BlockHeader;
+                (*rest).prev_phys = header;
+                (*rest).size = block_size - size;
+                (*header).set_size(size);
+
+                let next = (*rest).next_phys();
+                if (*next).is_free() {
+                    let next_size = (*next).size();
+                    self.remove_free(next as *mut FreeBlock);
+                    (*rest).set_size((*rest).size() + next_size);
+                }
+                (*(*rest).next_phys()).prev_phys = rest;
+                self.insert_free(rest as *mut FreeBlock);
+            }
+
+            self.counters.record_resize(old_size, (*header).size());
+        }
+        true
+    }
+
+    // 获取已分配块的可用数据大小（不包括头部）
+    pub(super) unsafe fn usable_size(&self, ptr: *mut u8) -> usize {
+        let header = (ptr as usize - HEADER_SIZE) as *const BlockHeader;
+        // SAFETY: 调用者确保 ptr 是本分配器返回的有效指针
+        unsafe { (*header).size() - HEADER_SIZE }
+    }
+
+    // 遍历所有空闲块：(地址, 大小)
+    fn for_each_free(&self, mut f: impl FnMut(usize, usize)) {
+        for fl in 0..FL_COUNT {
+            if self.fl_bitmap & (1 << fl) == 0 {
+                continue;
+            }
+            for sl in 0..SL_COUNT {
+                let mut block = self.blocks[fl][sl];
+                while !block.is_null() {
+                    // SAFETY: 只读遍历空闲链表
+                    unsafe {
+                        f(block as usize, (*block).header.size());
+                        block = (*block).next_free;
+                    }
+                }
+            }
+        }
+    }
+
+    pub(super) fn stats(&self) -> HeapStats {
+        let mut stats = self.counters.stats(&self.regions);
+        self.for_each_free(|_, size| stats.add_free_block(size));
+        stats
+    }
+
+    pub(super) fn region_stats(&self, index: usize) -> Option<RegionStats> {
+        let region = self.regions.get(index)?;
+        let mut stats = region.stats();
+        self.for_each_free(|addr, size| {
+            if region.contains(addr) {
+                stats.add_free_block(size);
+            }
+        });
+        Some(stats)
+    }
+
+    pub(super) fn region_count(&self) -> usize {
+        self.regions.len()
+    }
+
+    // 地址是否位于堆内
+    pub(super) fn contains(&self, addr: usize) -> bool {
+        self.regions.contains(addr)
+    }
+
+    // 完整遍历堆：每个区域内的块首尾相接、prev_phys 指向正确、没有相邻的空闲块，
+    // 并以哨兵块结束；空闲链表中的块都标记为空闲且位于正确的链表。出错时返回 (地址, 原因)
+    #[cfg(feature = "alloc-checked")]
+    pub(super) fn verify(&self) -> Result<(), (usize, &'static str)> {
+        for region in self.regions.iter() {
+            let sentinel = region.end - HEADER_SIZE;
+            let mut addr = region.start;
+            let mut prev: *mut BlockHeader = null_mut();
+            let mut prev_free = false;
+            while addr < sentinel {
+                let header = addr as *mut BlockHeader;
+                // SAFETY: addr 位于区域内，由上一个块的大小推得
+                let block = unsafe { &*header };
+                if block.prev_phys != prev {
+                    return Err((addr, "broken prev_phys link"));
+                }
+                if block.size() < MIN_BLOCK_SIZE || block.size() > sentinel - addr {
+                    return Err((addr, "invalid block size"));
+                }
+                if block.is_free() && prev_free {
+                    return Err((addr, "adjacent free blocks not merged"));
+                }
+                prev_free = block.is_free();
+                prev = header;
+                addr += block.size();
+            }
+            // SAFETY: 同上
+            let end = unsafe { &*(sentinel as *const BlockHeader) };
+            if addr != sentinel || end.size != HEADER_SIZE || end.prev_phys != prev {
+                return Err((sentinel, "region sentinel overwritten"));
+            }
+        }
+
+        for fl in 0..FL_COUNT {
+            for sl in 0..SL_COUNT {
+                let mut block = self.blocks[fl][sl];
+                let listed = self.sl_bitmap[fl] & (1 << sl) != 0;
+                if block.is_null() == listed {
+                    return Err((block as usize, "free list bitmap mismatch"));
+                }
+                while !block.is_null() {
+                    let addr = block as usize;
+                    if !self.regions.contains(addr) || !addr.is_multiple_of(MIN_ALIGN) {
+                        return Err((addr, "free list entry outside heap"));
+                    }
+                    // SAFETY: 地址已确认位于堆内且对齐
+                    let header = unsafe { &(*block).header };
+                    if !header.is_free() || mapping_insert(header.size()) != (fl, sl) {
+                        return Err((addr, "free list entry corrupted"));
+                    }
+                    // SAFETY: 同上
+                    block = unsafe { (*block).next_free };
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    // 打印空闲链表状态
+    pub(super) fn print_free_list(&self) {
+        println!("Free list status (TLSF):");
+        let mut count = 0;
+        self.for_each_free(|addr, size| {
+            let (fl, sl) = mapping_insert(size);
+            println!(
+                "  Block {}: addr=0x{:08x}, size={}, list=({}, {})",
+                count, addr, size, fl, sl
+            );
+            count += 1;
+        });
+        println!("  Total free blocks: {}", count);
+    }
+}
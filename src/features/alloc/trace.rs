@@ -0,0 +1,285 @@
+//! 结构化的分配跟踪，启用 `alloc-trace` 特性时编译
+//!
+//! 每次 alloc / free / realloc（以及失败的分配）都会向环形缓冲区写入一个 24 字节的事件：
+//! 类型、地址、大小、realloc 前的地址、调用者返回地址和系统 tick。
+//! 缓冲区写满后覆盖最旧的事件，容量由编译期环境变量 `ECOS_ALLOC_TRACE_EVENTS` 决定（默认 256）。
+//!
+//! [`dump_trace`] 和 [`dump_live_allocations`](super::dump_live_allocations) 以十六进制行的形式
+//! 把事件输出到 UART，每行后面附带可读的注释。输出时每次只在临界区内复制一小批事件，
+//! 打印在临界区外进行；输出期间被新事件覆盖掉的事件会被跳过，数量记在结束行的
+//! `dropped=` 中。抓下串口日志后可以用
+//! `tools/alloc_trace_decode.py` 解码、统计泄漏，并通过 addr2line 把调用者地址还原成源码位置：
+//!
+//! ```text
+//! ECOS-ALLOC-TRACE v1 events=2 dropped=0
+//! E 01000000001000040c000000000000001c2d0004e8030000 ; alloc 0x04001000 size=12 caller=0x04002d1c tick=1000
+//! E 02000000001000040c000000000000003a2d0004e9030000 ; free 0x04001000 size=12 caller=0x04002d3a tick=1001
+//! ECOS-ALLOC-TRACE END
+//! ```
+
+use core::alloc::Layout;
+
+use super::config::TRACE_EVENTS;
+
+/// 事件类型
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TraceKind {
+    /// 分配成功
+    Alloc = 1,
+    /// 释放
+    Free = 2,
+    /// 调整大小（`old_addr` 为原地址，原地调整时与 `addr` 相同）
+    Realloc = 3,
+    /// 分配失败（`addr` 为 0）
+    Failed = 4,
+    /// 存活的分配（只出现在 `dump_live_allocations` 的输出中）
+    Live = 5,
+}
+
+impl TraceKind {
+    fn as_str(self) -> &'static str {
+        match self {
+            TraceKind::Alloc => "alloc",
+            TraceKind::Free => "free",
+            TraceKind::Realloc => "realloc",
+            TraceKind::Failed => "failed",
+            TraceKind::Live => "live",
+        }
+    }
+}
+
+/// 一条跟踪事件，以小端序 24 字节的固定格式输出
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct TraceEvent {
+    pub kind: TraceKind,
+    pub addr: u32,
+    pub size: u32,
+    pub old_addr: u32,
+    pub caller: u32,
+    pub tick: u32,
+}
+
+/// 每个事件编码后的字节数
+pub const EVENT_SIZE: usize = 24;
+
+impl TraceEvent {
+    pub(super) const EMPTY: Self = Self {
+        kind: TraceKind::Alloc,
+        addr: 0,
+        size: 0,
+        old_addr: 0,
+        caller: 0,
+        tick: 0,
+    };
+
+    /// 编码为 `EVENT_SIZE` 字节：kind(u8) + 3 字节保留 + addr + size + old_addr + caller + tick
+    pub fn to_bytes(&self) -> [u8; EVENT_SIZE] {
+        let mut bytes = [0u8; EVENT_SIZE];
+        bytes[0] = self.kind as u8;
+        let fields = [self.addr, self.size, self.old_addr, self.caller, self.tick];
+        for (i, field) in fields.iter().enumerate() {
+            bytes[4 + i * 4..8 + i * 4].copy_from_slice(&field.to_le_bytes());
+        }
+        bytes
+    }
+}
+
+struct TraceRing {
+    events: [TraceEvent; TRACE_EVENTS],
+    written: usize, // 累计写入的事件数
+}
+
+static mut RING: TraceRing = TraceRing {
+    events: [TraceEvent::EMPTY; TRACE_EVENTS],
+    written: 0,
+};
+
+// 当前这次分配的调用者，由 GlobalAllocator 入口在临界区中设置，供守护头记录
+static mut CURRENT_CALLER: usize = 0;
+
+/// 读取调用者的返回地址
+///
+/// GlobalAlloc 的方法会被内联进 `__rg_alloc` 等入口，进入时 ra 尚未被改写，
+/// 指向调用 `__rust_alloc` 的代码。这只是尽力而为的结果，找不到时为 0
+#[inline(always)]
+pub(super) fn return_address() -> usize {
+    #[cfg(target_arch = "riscv32")]
+    {
+        let ra: usize;
+        // SAFETY: 只读取 ra 寄存器
+        unsafe { core::arch::asm!("mv {0}, ra", out(reg) ra, options(nomem, nostack)) };
+        ra
+    }
+    #[cfg(not(target_arch = "riscv32"))]
+    {
+        0
+    }
+}
+
+// 设置本次分配的调用者，在临界区中调用
+pub(super) fn set_current_caller(caller: usize) {
+    // SAFETY: 在分配器的临界区中访问
+    unsafe {
+        CURRENT_CALLER = caller;
+    }
+}
+
+// 守护头记录的调用者
+pub(super) fn current_caller() -> usize {
+    // SAFETY: 在分配器的临界区中访问
+    unsafe { CURRENT_CALLER }
+}
+
+pub(super) fn tick() -> u32 {
+    #[cfg(target_arch = "riscv32")]
+    {
+        crate::timer::Timer::get_tick()
+    }
+    #[cfg(not(target_arch = "riscv32"))]
+    {
+        0
+    }
+}
+
+// 写入一条事件
+pub(super) fn record(
+    kind: TraceKind,
+    addr: *mut u8,
+    layout: Layout,
+    old_addr: *mut u8,
+    caller: usize,
+) {
+    // SAFETY: 在分配器的临界区中访问
+    let ring = unsafe { &mut *core::ptr::addr_of_mut!(RING) };
+    ring.events[ring.written % TRACE_EVENTS] = TraceEvent {
+        kind,
+        addr: addr as u32,
+        size: layout.size() as u32,
+        old_addr: old_addr as u32,
+        caller: caller as u32,
+        tick: tick(),
+    };
+    ring.written = ring.written.wrapping_add(1);
+}
+
+/// 按时间顺序（从旧到新）遍历环形缓冲区中的事件
+///
+/// 遍历在分配器的临界区中进行，期间中断被屏蔽，`f` 应尽快返回
+pub fn for_each_event(mut f: impl FnMut(&TraceEvent)) {
+    super::critical(|| {
+        // SAFETY: 在分配器的临界区中访问
+        let ring = unsafe { &*core::ptr::addr_of!(RING) };
+        let count = ring.written.min(TRACE_EVENTS);
+        for i in ring.written - count..ring.written {
+            f(&ring.events[i % TRACE_EVENTS]);
+        }
+    })
+}
+
+/// 被覆盖掉的事件数
+pub fn dropped_events() -> usize {
+    super::critical(|| {
+        // SAFETY: 在分配器的临界区中访问
+        let written = unsafe { (*core::ptr::addr_of!(RING)).written };
+        written.saturating_sub(TRACE_EVENTS)
+    })
+}
+
+/// 清空环形缓冲区
+pub fn clear_trace() {
+    super::critical(|| {
+        // SAFETY: 在分配器的临界区中访问
+        unsafe {
+            (*core::ptr::addr_of_mut!(RING)).written = 0;
+        }
+    })
+}
+
+// 输出一行：前缀 + 十六进制编码 + 可读注释
+pub(super) fn print_event(prefix: char, event: &TraceEvent) {
+    crate::print!("{} ", prefix);
+    for byte in event.to_bytes() {
+        crate::print!("{:02x}", byte);
+    }
+    crate::print!(
+        " ; {} 0x{:08x} size={}",
+        event.kind.as_str(),
+        event.addr,
+        event.size
+    );
+    if event.kind == TraceKind::Realloc {
+        crate::print!(" from=0x{:08x}", event.old_addr);
+    }
+    crate::println!(" caller=0x{:08x} tick={}", event.caller, event.tick);
+}
+
+// 每批在临界区内复制的事件数，输出在临界区外进行
+pub(super) const DUMP_BATCH: usize = 16;
+
+// 复制序号在 [from, end) 中的前 DUMP_BATCH 个事件，在分配器的临界区中调用
+fn copy_events(
+    ring: &TraceRing,
+    from: usize,
+    end: usize,
+    batch: &mut [TraceEvent; DUMP_BATCH],
+) -> usize {
+    let n = (end - from).min(DUMP_BATCH);
+    for (i, slot) in batch[..n].iter_mut().enumerate() {
+        *slot = ring.events[(from + i) % TRACE_EVENTS];
+    }
+    n
+}
+
+/// 把环形缓冲区中的事件输出到 UART，供 `tools/alloc_trace_decode.py` 解码
+///
+/// 只输出调用时已有的事件，每次在临界区内复制一小批，打印时不屏蔽中断。
+/// 输出过程不分配内存
+pub fn dump_trace() {
+    let mut batch = [TraceEvent::EMPTY; DUMP_BATCH];
+    // 事件数与第一批在同一个临界区中取得
+    let (mut next, end, mut n) = super::critical(|| {
+        // SAFETY: 在分配器的临界区中访问
+        let ring = unsafe { &*core::ptr::addr_of!(RING) };
+        let start = ring.written - ring.written.min(TRACE_EVENTS);
+        (
+            start,
+            ring.written,
+            copy_events(ring, start, ring.written, &mut batch),
+        )
+    });
+    crate::println!("ECOS-ALLOC-TRACE v1 events={} dropped={}", end - next, next);
+
+    let mut lost = 0;
+    loop {
+        for event in &batch[..n] {
+            print_event('E', event);
+        }
+        next += n;
+        if next >= end {
+            break;
+        }
+        let skipped;
+        (n, skipped) = super::critical(|| {
+            // SAFETY: 在分配器的临界区中访问
+            let ring = unsafe { &*core::ptr::addr_of!(RING) };
+            if ring.written < end {
+                // 期间被清空
+                return (0, end - next);
+            }
+            // 打印期间被覆盖的事件跳过
+            let oldest = ring.written - ring.written.min(TRACE_EVENTS);
+            let skipped = oldest.saturating_sub(next).min(end - next);
+            (copy_events(ring, next + skipped, end, &mut batch), skipped)
+        });
+        next += skipped;
+        lost += skipped;
+    }
+
+    if lost > 0 {
+        crate::println!("ECOS-ALLOC-TRACE END dropped={}", lost);
+    } else {
+        crate::println!("ECOS-ALLOC-TRACE END");
+    }
+}
@@ -0,0 +1,606 @@
+//! 延迟（二进制）日志，启用 `log-deferred` 特性后可用
+//!
+//! [`deferred::info!`](crate::features::log::deferred::info) 等宏不在调用处格式化：
+//! 格式化字符串连同级别以外的元数据（目标、文件、行号）放在 `.ecos_log_fmt` 段中，
+//! 用它的地址作为索引；调用处只把级别、索引、时钟计数和参数的原始字节写入一个 RAM 队列，
+//! 耗时只与参数的字节数有关。队列由主循环或空闲钩子调用 [`flush`] / [`poll`] 发出。
+//!
+//! 构建时不单独生成索引表：它就是链接后 ELF 中 `.ecos_log_fmt` 段的内容。主机端用
+//! `tools/log_decode.py index` 从 ELF 中提取（可以和固件一起归档），
+//! 再用 `log_decode.py decode` 把串口数据还原成文本
+//!
+//! 参数只支持实现了 [`Encode`] 的类型（整数、浮点、`bool`、`char`、`&str`、`&[u8]`），
+//! 格式化字符串只支持按位置的 `{}` / `{:spec}` 占位符
+//!
+//! ## 串口上的格式
+//! 每条记录编码为 `0x00 COBS(记录) 0x00`，与普通的文本日志混在同一个串口上也能区分。
+//! 记录为小端序：
+//!
+//! ```text
+//! level(u8) index(u32) tick(u32) { tag(u8) 值 }*
+//! ```
+//!
+//! `index` 为 0 的记录表示队列满被丢弃的记录数（一个 u32 参数）
+//!
+//! ## 使用示例
+//! ```
+//! use ecos_ssc1::features::log::deferred::{self, info};
+//!
+//! info!("adc={} t={:.1}", raw, temp);
+//!
+//! // 主循环或空闲时
+//! deferred::flush();
+//! ```
+
+use core::ptr::addr_of_mut;
+
+use super::{Level, config, critical, sys_tick};
+#[cfg(target_arch = "riscv32")]
+use crate::uart::Uart;
+
+/// 单条记录编码后的最大字节数，超出的参数被截断
+pub const MAX_FRAME: usize = 128;
+
+const QUEUE_SIZE: usize = config::DEFERRED_BUFFER;
+const _: () = assert!(
+    QUEUE_SIZE >= MAX_FRAME + 2,
+    "ECOS_LOG_DEFERRED_BUFFER is smaller than one frame"
+);
+
+// 参数类型标记
+const TAG_U32: u8 = 1;
+const TAG_I32: u8 = 2;
+const TAG_U64: u8 = 3;
+const TAG_I64: u8 = 4;
+const TAG_F32: u8 = 5;
+const TAG_F64: u8 = 6;
+const TAG_BOOL: u8 = 7;
+const TAG_CHAR: u8 = 8;
+const TAG_STR: u8 = 9;
+const TAG_BYTES: u8 = 10;
+const TAG_TRUNCATED: u8 = 0xff;
+
+// 丢弃统计记录的索引
+const DROPPED_INDEX: u32 = 0;
+
+// ========== 参数编码 ==========
+
+/// 可以作为延迟日志参数的类型
+pub trait Encode {
+    fn encode(&self, frame: &mut Frame);
+}
+
+impl<T: Encode + ?Sized> Encode for &T {
+    fn encode(&self, frame: &mut Frame) {
+        (**self).encode(frame)
+    }
+}
+
+macro_rules! impl_encode {
+    ($tag:expr, $wide:ty => $($ty:ty),*) => {
+        $(
+            impl Encode for $ty {
+                fn encode(&self, frame: &mut Frame) {
+                    frame.put_tagged($tag, &(*self as $wide).to_le_bytes());
+                }
+            }
+        )*
+    };
+}
+
+impl_encode!(TAG_U32, u32 => u8, u16, u32, usize);
+impl_encode!(TAG_I32, i32 => i8, i16, i32, isize);
+impl_encode!(TAG_U64, u64 => u64);
+impl_encode!(TAG_I64, i64 => i64);
+impl_encode!(TAG_F32, f32 => f32);
+impl_encode!(TAG_F64, f64 => f64);
+
+impl Encode for bool {
+    fn encode(&self, frame: &mut Frame) {
+        frame.put_tagged(TAG_BOOL, &[*self as u8]);
+    }
+}
+
+impl Encode for char {
+    fn encode(&self, frame: &mut Frame) {
+        frame.put_tagged(TAG_CHAR, &(*self as u32).to_le_bytes());
+    }
+}
+
+impl Encode for str {
+    fn encode(&self, frame: &mut Frame) {
+        frame.put_slice(TAG_STR, self.as_bytes());
+    }
+}
+
+impl Encode for [u8] {
+    fn encode(&self, frame: &mut Frame) {
+        frame.put_slice(TAG_BYTES, self);
+    }
+}
+
+/// 正在编码的一条记录，由宏在栈上创建
+#[doc(hidden)]
+pub struct Frame {
+    buf: [u8; MAX_FRAME],
+    len: usize,
+    truncated: bool,
+}
+
+impl Frame {
+    #[doc(hidden)]
+    pub fn new(level: Level, index: usize) -> Self {
+        let mut frame = Self {
+            buf: [0; MAX_FRAME],
+            len: 0,
+            truncated: false,
+        };
+        let tick = sys_tick();
+        frame.put(&[level as u8]);
+        frame.put(&(index as u32).to_le_bytes());
+        frame.put(&tick.to_le_bytes());
+        frame
+    }
+
+    #[doc(hidden)]
+    pub fn push<T: Encode + ?Sized>(&mut self, value: &T) {
+        if !self.truncated {
+            value.encode(self);
+        }
+    }
+
+    // 剩余空间，为截断标记留一个字节
+    fn remaining(&self) -> usize {
+        MAX_FRAME - 1 - self.len
+    }
+
+    fn put(&mut self, bytes: &[u8]) {
+        self.buf[self.len..self.len + bytes.len()].copy_from_slice(bytes);
+        self.len += bytes.len();
+    }
+
+    fn put_tagged(&mut self, tag: u8, bytes: &[u8]) {
+        if self.remaining() < 1 + bytes.len() {
+            self.truncated = true;
+            return;
+        }
+        self.put(&[tag]);
+        self.put(bytes);
+    }
+
+    // 变长参数：u16 长度 + 内容，放不下时截断内容
+    fn put_slice(&mut self, tag: u8, bytes: &[u8]) {
+        if self.remaining() < 3 {
+            self.truncated = true;
+            return;
+        }
+        let n = bytes.len().min(self.remaining() - 3);
+        self.put(&[tag]);
+        self.put(&(n as u16).to_le_bytes());
+        self.put(&bytes[..n]);
+        if n < bytes.len() {
+            self.truncated = true;
+        }
+    }
+
+    /// 写入队列，队列满时丢弃并计数
+    #[doc(hidden)]
+    pub fn commit(mut self) {
+        if self.truncated {
+            self.put(&[TAG_TRUNCATED]);
+        }
+        // SAFETY: 队列只在临界区内访问
+        critical(|| unsafe { (*addr_of_mut!(QUEUE)).push(&self.buf[..self.len]) });
+    }
+}
+
+/// 把格式化字符串复制成以 0 结尾的字节数组，放入 `.ecos_log_fmt` 段
+#[doc(hidden)]
+pub const fn __entry<const N: usize>(s: &str) -> [u8; N] {
+    let bytes = s.as_bytes();
+    let mut out = [0u8; N];
+    let mut i = 0;
+    while i < bytes.len() {
+        out[i] = bytes[i];
+        i += 1;
+    }
+    out
+}
+
+// ========== 记录队列 ==========
+
+// 环形字节队列，每条记录前有 u16 长度
+struct Queue {
+    buf: [u8; QUEUE_SIZE],
+    head: usize, // 下一个写入位置
+    len: usize,
+    frames: usize,
+    dropped: u32,
+}
+
+static mut QUEUE: Queue = Queue::new();
+
+impl Queue {
+    const fn new() -> Self {
+        Self {
+            buf: [0; QUEUE_SIZE],
+            head: 0,
+            len: 0,
+            frames: 0,
+            dropped: 0,
+        }
+    }
+
+    fn push(&mut self, frame: &[u8]) {
+        if QUEUE_SIZE - self.len < 2 + frame.len() {
+            self.dropped = self.dropped.saturating_add(1);
+            return;
+        }
+        for &b in (frame.len() as u16).to_le_bytes().iter().chain(frame) {
+            self.buf[self.head] = b;
+            self.head = (self.head + 1) % QUEUE_SIZE;
+        }
+        self.len += 2 + frame.len();
+        self.frames += 1;
+    }
+
+    // 取出最早的一条记录，返回长度
+    fn pop(&mut self, out: &mut [u8; MAX_FRAME]) -> Option<usize> {
+        if self.len == 0 {
+            return None;
+        }
+        let start = (self.head + QUEUE_SIZE - self.len) % QUEUE_SIZE;
+        let byte = |i: usize| self.buf[(start + i) % QUEUE_SIZE];
+        let n = u16::from_le_bytes([byte(0), byte(1)]) as usize;
+        for (i, slot) in out[..n].iter_mut().enumerate() {
+            *slot = byte(2 + i);
+        }
+        self.len -= 2 + n;
+        self.frames -= 1;
+        Some(n)
+    }
+}
+
+// ========== 发送 ==========
+
+/// 队列中尚未发出的字节数
+pub fn pending() -> usize {
+    // SAFETY: 同 Frame::commit
+    critical(|| unsafe { (*addr_of_mut!(QUEUE)).len })
+}
+
+/// 因队列满被丢弃、尚未报告的记录数
+pub fn dropped() -> u32 {
+    // SAFETY: 同 Frame::commit
+    critical(|| unsafe { (*addr_of_mut!(QUEUE)).dropped })
+}
+
+// 在临界区内取出一条记录；有丢弃计数时先取出丢弃统计记录
+fn take_frame(out: &mut [u8; MAX_FRAME]) -> Option<usize> {
+    critical(|| {
+        // SAFETY: 同 Frame::commit
+        let queue = unsafe { &mut *addr_of_mut!(QUEUE) };
+        if queue.dropped > 0 {
+            let mut frame = Frame::new(Level::Warn, DROPPED_INDEX as usize);
+            frame.push(&queue.dropped);
+            queue.dropped = 0;
+            out[..frame.len].copy_from_slice(&frame.buf[..frame.len]);
+            return Some(frame.len);
+        }
+        queue.pop(out)
+    })
+}
+
+/// 取出最多 `max_frames` 条记录，每条按串口格式编码后调用一次 `write`，返回取出的条数
+///
+/// 只有取记录时关中断，编码和输出都在临界区外进行
+pub fn drain(max_frames: usize, mut write: impl FnMut(&[u8])) -> usize {
+    let mut frame = [0u8; MAX_FRAME];
+    let mut encoded = [0u8; ENCODED_MAX];
+    let mut count = 0;
+    while count < max_frames {
+        let Some(len) = take_frame(&mut frame) else {
+            break;
+        };
+        let n = cobs_encode(&frame[..len], &mut encoded);
+        write(&encoded[..n]);
+        count += 1;
+    }
+    count
+}
+
+/// 把最多 `max_frames` 条记录发到 SYS UART，适合在空闲钩子中调用以限制每次的耗时
+///
+/// 每条记录在临界区内整条写出，不会被中断里的文本日志插入
+#[cfg(target_arch = "riscv32")]
+pub fn poll(max_frames: usize) -> usize {
+    drain(max_frames, |bytes| critical(|| Uart::write_bytes(bytes)))
+}
+
+/// 把调用时队列中已有的记录全部发到 SYS UART
+///
+/// 只发出调用时已有的记录（包括丢弃统计），期间中断里新写入的留给下次，
+/// 日志很密集时也不会一直停在这里
+#[cfg(target_arch = "riscv32")]
+pub fn flush() {
+    // SAFETY: 同 Frame::commit
+    let frames = critical(|| {
+        let queue = unsafe { &*addr_of_mut!(QUEUE) };
+        queue.frames + (queue.dropped > 0) as usize
+    });
+    poll(frames);
+}
+
+// 一条记录编码后的最大长度：每 254 字节多一个字节，再加首尾的 0x00
+const ENCODED_MAX: usize = MAX_FRAME + MAX_FRAME / 254 + 1 + 2;
+
+// 以 0x00 包围的 COBS 编码：记录内部不出现 0x00，返回编码后的长度。
+// out 至少要有 data.len() + data.len() / 254 + 3 字节
+fn cobs_encode(data: &[u8], out: &mut [u8]) -> usize {
+    let mut len = 0;
+    let mut put = |bytes: &[u8]| {
+        out[len..len + bytes.len()].copy_from_slice(bytes);
+        len += bytes.len();
+    };
+
+    put(&[0]);
+    let mut rest = data;
+    loop {
+        match rest.iter().take(254).position(|&b| b == 0) {
+            Some(n) => {
+                put(&[n as u8 + 1]);
+                put(&rest[..n]);
+                rest = &rest[n + 1..];
+            }
+            None if rest.len() >= 254 => {
+                put(&[0xff]);
+                put(&rest[..254]);
+                rest = &rest[254..];
+            }
+            None => {
+                put(&[rest.len() as u8 + 1]);
+                put(rest);
+                break;
+            }
+        }
+    }
+    put(&[0]);
+    len
+}
+
+// ========== 日志宏 ==========
+
+/// 通用的延迟日志宏
+#[macro_export]
+macro_rules! __deferred_log {
+    ($level:expr, $fmt:literal $(, $arg:expr)* $(,)?) => {{
+        let level: $crate::features::log::Level = $level;
+        let target = module_path!();
+        if $crate::features::log::STATIC_MAX_LEVEL.accepts(level)
+            && $crate::features::log::enabled(level, target)
+        {
+            // 目标、位置和格式化字符串以 0x1f 分隔，只存在于 ELF 中，由主机端解码
+            const ENTRY: &str = concat!(module_path!(), "\x1f", file!(), ":", line!(), "\x1f", $fmt);
+            #[unsafe(link_section = ".ecos_log_fmt")]
+            static FMT: [u8; ENTRY.len() + 1] = $crate::features::log::deferred::__entry(ENTRY);
+
+            let mut frame = $crate::features::log::deferred::Frame::new(
+                level,
+                ::core::ptr::addr_of!(FMT) as usize,
+            );
+            $( frame.push(&$arg); )*
+            frame.commit();
+        }
+    }};
+}
+
+/// 错误级别延迟日志宏
+#[macro_export]
+macro_rules! __deferred_error {
+    ($($arg:tt)*) => {
+        $crate::__deferred_log!($crate::features::log::Level::Error, $($arg)*)
+    };
+}
+
+/// 警告级别延迟日志宏
+#[macro_export]
+macro_rules! __deferred_warn {
+    ($($arg:tt)*) => {
+        $crate::__deferred_log!($crate::features::log::Level::Warn, $($arg)*)
+    };
+}
+
+/// 信息级别延迟日志宏
+#[macro_export]
+macro_rules! __deferred_info {
+    ($($arg:tt)*) => {
+        $crate::__deferred_log!($crate::features::log::Level::Info, $($arg)*)
+    };
+}
+
+/// 调试级别延迟日志宏
+#[macro_export]
+macro_rules! __deferred_debug {
+    ($($arg:tt)*) => {
+        $crate::__deferred_log!($crate::features::log::Level::Debug, $($arg)*)
+    };
+}
+
+/// 跟踪级别延迟日志宏
+#[macro_export]
+macro_rules! __deferred_trace {
+    ($($arg:tt)*) => {
+        $crate::__deferred_log!($crate::features::log::Level::Trace, $($arg)*)
+    };
+}
+
+pub use crate::{
+    __deferred_debug as debug, __deferred_error as error, __deferred_info as info,
+    __deferred_log as log, __deferred_trace as trace, __deferred_warn as warn,
+};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // COBS 解码：去掉首尾的 0x00，还原记录
+    fn cobs_decode(encoded: &[u8]) -> Vec<u8> {
+        assert_eq!(encoded.first(), Some(&0), "missing leading delimiter");
+        assert_eq!(encoded.last(), Some(&0), "missing trailing delimiter");
+        let body = &encoded[1..encoded.len() - 1];
+        assert!(!body.contains(&0), "zero inside frame");
+
+        let mut out = Vec::new();
+        let mut i = 0;
+        while i < body.len() {
+            let code = body[i] as usize;
+            out.extend_from_slice(&body[i + 1..i + code]);
+            i += code;
+            // 0xff 的组后面没有隐含的 0，最后一组也没有
+            if code != 0xff && i < body.len() {
+                out.push(0);
+            }
+        }
+        out
+    }
+
+    fn encode(data: &[u8]) -> Vec<u8> {
+        let mut out = vec![0u8; data.len() + data.len() / 254 + 3];
+        let n = cobs_encode(data, &mut out);
+        out.truncate(n);
+        out
+    }
+
+    #[test]
+    fn cobs_zero_bytes() {
+        assert_eq!(encode(&[]), [0, 0x01, 0]);
+        assert_eq!(encode(&[0]), [0, 0x01, 0x01, 0]);
+        assert_eq!(
+            encode(&[0x11, 0, 0x22, 0, 0, 0x33]),
+            [0, 0x02, 0x11, 0x02, 0x22, 0x01, 0x02, 0x33, 0]
+        );
+        assert_eq!(encode(&[0x11, 0x22, 0]), [0, 0x03, 0x11, 0x22, 0x01, 0]);
+
+        for data in [&[0, 0, 0][..], &[1, 0, 2, 0], &[0, 7]] {
+            assert_eq!(cobs_decode(&encode(data)), data);
+        }
+    }
+
+    #[test]
+    fn cobs_254_byte_runs() {
+        let run: Vec<u8> = (0..254).map(|i| (i % 255 + 1) as u8).collect();
+
+        // 正好 254 个非零字节：一个 0xff 组，后面是空的最后一组
+        let encoded = encode(&run);
+        assert_eq!(encoded.len(), 254 + 4);
+        assert_eq!(&encoded[..2], [0, 0xff]);
+        assert_eq!(&encoded[2..256], &run[..]);
+        assert_eq!(&encoded[256..], [0x01, 0]);
+        assert_eq!(cobs_decode(&encoded), run);
+
+        // 255 个：多出的一个字节进入下一组
+        let mut longer = run.clone();
+        longer.push(0x42);
+        let encoded = encode(&longer);
+        assert_eq!(&encoded[256..], [0x02, 0x42, 0]);
+        assert_eq!(cobs_decode(&encoded), longer);
+
+        // 0 紧跟在 254 字节之后、多个 254 字节的组相连
+        let mut data = run.clone();
+        data.push(0);
+        data.extend_from_slice(&run);
+        data.extend_from_slice(&run);
+        data.push(5);
+        let encoded = encode(&data);
+        assert_eq!(encoded[256], 0x01);
+        assert_eq!(cobs_decode(&encoded), data);
+
+        // 253 个非零字节加 0：不需要 0xff 组
+        let mut data = run[..253].to_vec();
+        data.push(0);
+        let encoded = encode(&data);
+        assert_eq!(encoded[1], 254);
+        assert_eq!(cobs_decode(&encoded), data);
+    }
+
+    #[test]
+    fn queue_wraps_around() {
+        let mut queue = Queue::new();
+        let mut out = [0u8; MAX_FRAME];
+        let mut next = 0u8;
+        let mut expected = std::collections::VecDeque::new();
+        let mut wrapped = 0;
+
+        // 每条 2 + 100 字节，与 QUEUE_SIZE 不成整数倍，多圈之后记录会跨过缓冲区末尾
+        for _ in 0..QUEUE_SIZE / 10 {
+            let frame: Vec<u8> = (0..100).map(|i| next.wrapping_add(i)).collect();
+            next = next.wrapping_add(1);
+            while QUEUE_SIZE - queue.len < 2 + frame.len() {
+                let n = queue.pop(&mut out).unwrap();
+                assert_eq!(&out[..n], expected.pop_front().unwrap());
+            }
+            let head = queue.head;
+            queue.push(&frame);
+            if queue.head < head {
+                wrapped += 1;
+            }
+            expected.push_back(frame);
+            assert_eq!(queue.frames, expected.len());
+        }
+        assert!(wrapped >= 2, "queue never wrapped");
+        assert_eq!(queue.dropped, 0);
+
+        while let Some(n) = queue.pop(&mut out) {
+            assert_eq!(&out[..n], expected.pop_front().unwrap());
+        }
+        assert!(expected.is_empty());
+        assert_eq!((queue.len, queue.frames), (0, 0));
+    }
+
+    #[test]
+    fn queue_full_drops() {
+        let mut queue = Queue::new();
+        let frame = [0xAB; MAX_FRAME];
+        let fits = QUEUE_SIZE / (2 + MAX_FRAME);
+        for _ in 0..fits {
+            queue.push(&frame);
+        }
+        let len = queue.len;
+        queue.push(&frame);
+        queue.push(&frame);
+        assert_eq!((queue.len, queue.frames, queue.dropped), (len, fits, 2));
+    }
+
+    // 唯一使用全局 QUEUE 的测试
+    #[test]
+    fn dropped_record_frame() {
+        let fits = QUEUE_SIZE / (2 + 1 + 4 + 4 + 5);
+        for i in 0..fits + 3 {
+            let mut frame = Frame::new(Level::Info, 0x1000);
+            frame.push(&(i as u32));
+            frame.commit();
+        }
+        assert_eq!(dropped(), 3);
+
+        let mut frames = Vec::new();
+        let count = drain(usize::MAX, |bytes| frames.push(cobs_decode(bytes)));
+        assert_eq!(count, fits + 1);
+        assert_eq!((pending(), dropped()), (0, 0));
+
+        // 先发出丢弃统计：Warn、索引 0、时钟计数、一个 u32 参数
+        let mut expected = vec![Level::Warn as u8];
+        expected.extend_from_slice(&DROPPED_INDEX.to_le_bytes());
+        expected.extend_from_slice(&sys_tick().to_le_bytes());
+        expected.push(TAG_U32);
+        expected.extend_from_slice(&3u32.to_le_bytes());
+        assert_eq!(frames[0], expected);
+
+        // 之后是按写入顺序保留下来的记录
+        for (i, frame) in frames[1..].iter().enumerate() {
+            assert_eq!(frame[0], Level::Info as u8);
+            assert_eq!(&frame[1..5], 0x1000u32.to_le_bytes());
+            assert_eq!(frame[9], TAG_U32);
+            assert_eq!(&frame[10..], (i as u32).to_le_bytes());
+        }
+    }
+}
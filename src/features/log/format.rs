@@ -0,0 +1,280 @@
+//! 日志格式模板与开机时间
+//!
+//! 模板的字段见上级模块文档的“格式模板”一节。模板在设置时检查一遍，
+//! 输出时再逐段扫描，不需要额外的存储
+
+use core::fmt;
+use core::ptr::addr_of_mut;
+
+use super::{Record, critical, sys_tick};
+
+/// 不显示时间戳时的默认格式
+pub const DEFAULT_FORMAT: &str = "[{level}] [{target}] {message}";
+/// 显示时间戳时的默认格式
+pub const DEFAULT_FORMAT_TIMESTAMP: &str = "[{timestamp}] [{level}] [{target}] {message}";
+
+/// 格式模板错误
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FormatError {
+    /// 无法识别的字段名
+    UnknownField,
+    /// `{` 没有对应的 `}`，或单独出现的 `}`
+    Unbalanced,
+}
+
+impl fmt::Display for FormatError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FormatError::UnknownField => write!(f, "unknown log format field"),
+            FormatError::Unbalanced => write!(f, "unbalanced braces in log format"),
+        }
+    }
+}
+
+/// `{timestamp}` 的精度
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimestampUnit {
+    /// 毫秒，如 `12.345`
+    Millis,
+    /// 微秒，如 `12.345678`
+    Micros,
+}
+
+#[derive(Clone, Copy)]
+enum Field {
+    Level,
+    ShortLevel,
+    Target,
+    File,
+    Line,
+    Location,
+    Timestamp,
+    Uptime,
+    Message,
+}
+
+impl Field {
+    fn parse(name: &str) -> Result<Self, FormatError> {
+        Ok(match name {
+            "level" => Field::Level,
+            "short_level" => Field::ShortLevel,
+            "target" => Field::Target,
+            "file" => Field::File,
+            "line" => Field::Line,
+            "location" => Field::Location,
+            "timestamp" => Field::Timestamp,
+            "uptime" => Field::Uptime,
+            "message" => Field::Message,
+            _ => return Err(FormatError::UnknownField),
+        })
+    }
+}
+
+enum Segment<'a> {
+    Text(&'a str),
+    Field(Field),
+}
+
+// 依次给出模板中的文本和字段
+struct Segments<'a> {
+    rest: &'a str,
+}
+
+impl<'a> Iterator for Segments<'a> {
+    type Item = Result<Segment<'a>, FormatError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let rest = self.rest;
+        if rest.is_empty() {
+            return None;
+        }
+        if let Some(after) = rest.strip_prefix("{{") {
+            self.rest = after;
+            return Some(Ok(Segment::Text("{")));
+        }
+        if let Some(after) = rest.strip_prefix("}}") {
+            self.rest = after;
+            return Some(Ok(Segment::Text("}")));
+        }
+        if let Some(after) = rest.strip_prefix('{') {
+            let Some((name, after)) = after.split_once('}') else {
+                return Some(Err(FormatError::Unbalanced));
+            };
+            self.rest = after;
+            return Some(Field::parse(name.trim()).map(Segment::Field));
+        }
+        if rest.starts_with('}') {
+            return Some(Err(FormatError::Unbalanced));
+        }
+
+        let end = rest.find(['{', '}']).unwrap_or(rest.len());
+        self.rest = &rest[end..];
+        Some(Ok(Segment::Text(&rest[..end])))
+    }
+}
+
+/// 一个已检查过的格式模板
+#[derive(Clone, Copy)]
+pub(super) struct Template {
+    source: &'static str,
+    needs_time: bool,
+}
+
+impl Template {
+    pub(super) const DEFAULT: Self = Self {
+        source: DEFAULT_FORMAT,
+        needs_time: false,
+    };
+    pub(super) const DEFAULT_TIMESTAMP: Self = Self {
+        source: DEFAULT_FORMAT_TIMESTAMP,
+        needs_time: true,
+    };
+
+    pub(super) fn parse(source: &'static str) -> Result<Self, FormatError> {
+        let mut needs_time = false;
+        for segment in (Segments { rest: source }) {
+            if let Segment::Field(Field::Timestamp | Field::Uptime) = segment? {
+                needs_time = true;
+            }
+        }
+        Ok(Self { source, needs_time })
+    }
+
+    pub(super) fn source(&self) -> &'static str {
+        self.source
+    }
+
+    /// 是否包含时间字段，不包含时不读取时钟
+    pub(super) fn needs_time(&self) -> bool {
+        self.needs_time
+    }
+
+    /// 按模板写出一条日志（包括结尾的换行）
+    pub(super) fn write(
+        &self,
+        out: &mut impl fmt::Write,
+        record: &Record,
+        uptime_us: u64,
+        unit: TimestampUnit,
+        colored: bool,
+    ) -> fmt::Result {
+        for segment in (Segments { rest: self.source }) {
+            match segment {
+                Ok(Segment::Text(text)) => out.write_str(text)?,
+                Ok(Segment::Field(field)) => {
+                    write_field(out, field, record, uptime_us, unit, colored)?
+                }
+                Err(_) => return Err(fmt::Error),
+            }
+        }
+        out.write_str("\n")
+    }
+}
+
+fn write_field(
+    out: &mut impl fmt::Write,
+    field: Field,
+    record: &Record,
+    uptime_us: u64,
+    unit: TimestampUnit,
+    colored: bool,
+) -> fmt::Result {
+    #[cfg(feature = "log-colored")]
+    if colored {
+        use super::ansi::{BOLD, BRIGHT_BLUE, RESET, color_for_level};
+
+        let color = color_for_level(record.level());
+        match field {
+            Field::Level => return write!(out, "{}{}{}{}", color, BOLD, record.level(), RESET),
+            Field::ShortLevel => {
+                return write!(
+                    out,
+                    "{}{}{}{}",
+                    color,
+                    BOLD,
+                    record.level().as_short(),
+                    RESET
+                );
+            }
+            Field::Target => return write!(out, "{}{}{}", BRIGHT_BLUE, record.target(), RESET),
+            _ => {}
+        }
+    }
+    #[cfg(not(feature = "log-colored"))]
+    let _ = colored;
+
+    match field {
+        Field::Level => write!(out, "{}", record.level()),
+        Field::ShortLevel => write!(out, "{}", record.level().as_short()),
+        Field::Target => out.write_str(record.target()),
+        Field::File => out.write_str(record.file().unwrap_or("?")),
+        Field::Line => match record.line() {
+            Some(line) => write!(out, "{}", line),
+            None => out.write_str("?"),
+        },
+        Field::Location => match (record.file(), record.line()) {
+            (Some(file), Some(line)) => write!(out, "{}:{}", file, line),
+            _ => out.write_str("?"),
+        },
+        Field::Timestamp => {
+            let secs = uptime_us / 1_000_000;
+            let micros = uptime_us % 1_000_000;
+            match unit {
+                TimestampUnit::Millis => write!(out, "{}.{:03}", secs, micros / 1000),
+                TimestampUnit::Micros => write!(out, "{}.{:06}", secs, micros),
+            }
+        }
+        Field::Uptime => {
+            let secs = uptime_us / 1_000_000;
+            write!(
+                out,
+                "{:02}:{:02}:{:02}.{:03}",
+                secs / 3600,
+                secs / 60 % 60,
+                secs % 60,
+                uptime_us % 1_000_000 / 1000
+            )
+        }
+        Field::Message => write!(out, "{}", record.args()),
+    }
+}
+
+// ========== 开机时间 ==========
+
+// 把 32 位的 sys tick 扩展为 64 位：每次读取时与上一次比较，变小说明回绕了一次
+struct Clock {
+    last: u32,
+    wraps: u32,
+}
+
+static mut CLOCK: Clock = Clock { last: 0, wraps: 0 };
+
+#[cfg(target_arch = "riscv32")]
+const TICKS_PER_US: u64 = crate::bindings::CONFIG_CPU_FREQ_MHZ as u64;
+// 宿主机上 sys tick 恒为 0，取值无关紧要
+#[cfg(not(target_arch = "riscv32"))]
+const TICKS_PER_US: u64 = 1;
+
+/// 开机以来的微秒数
+///
+/// sys tick 按 `CONFIG_CPU_FREQ_MHZ` 计数，32 位在 72 MHz 下约 59 秒回绕一次。
+/// 这里在每次读取时检测回绕，所以只要两次调用（包括每条带时间的日志）的间隔不超过
+/// 一个回绕周期，结果就是单调的；长时间没有日志时可以在主循环中定期调用
+pub fn uptime_us() -> u64 {
+    // SAFETY: 只在临界区内访问
+    critical(|| unsafe {
+        let clock = &mut *addr_of_mut!(CLOCK);
+        let tick = sys_tick();
+        if tick < clock.last {
+            clock.wraps += 1;
+        }
+        clock.last = tick;
+        let ticks = ((clock.wraps as u64) << 32) | tick as u64;
+        ticks / TICKS_PER_US
+    })
+}
+
+/// 开机以来的毫秒数，见 [`uptime_us`]
+pub fn uptime_ms() -> u64 {
+    uptime_us() / 1000
+}
@@ -0,0 +1,139 @@
+//! 单条日志的行缓冲与发送中到达的日志的暂存队列
+
+use core::fmt;
+
+use super::Level;
+use super::sink::LogSink;
+
+/// 一条日志格式化后的最大字节数（包括换行），超出部分被截断
+pub const MAX_LINE_LEN: usize = 256;
+
+// 截断时补在行尾的颜色复位和换行
+const TRUNCATED_TAIL: &str = "\x1b[0m\n";
+
+/// 定长的行缓冲，写满后丢弃后面的内容，截断处总在字符边界上
+pub(super) struct LineBuffer {
+    buf: [u8; MAX_LINE_LEN],
+    len: usize,
+    truncated: bool,
+}
+
+impl LineBuffer {
+    pub(super) const fn new() -> Self {
+        Self {
+            buf: [0; MAX_LINE_LEN],
+            len: 0,
+            truncated: false,
+        }
+    }
+
+    /// 结束这一行：被截断时补上颜色复位（彩色时）和换行
+    pub(super) fn finish(&mut self, colored: bool) -> &str {
+        if self.truncated {
+            let tail = if colored {
+                TRUNCATED_TAIL
+            } else {
+                &TRUNCATED_TAIL[TRUNCATED_TAIL.len() - 1..]
+            };
+            self.buf[self.len..self.len + tail.len()].copy_from_slice(tail.as_bytes());
+            self.len += tail.len();
+        }
+        // SAFETY: 只由完整的字符写入
+        unsafe { core::str::from_utf8_unchecked(&self.buf[..self.len]) }
+    }
+}
+
+impl fmt::Write for LineBuffer {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        if self.truncated {
+            return Ok(());
+        }
+        // 为截断时的结尾留出空间
+        let space = MAX_LINE_LEN - TRUNCATED_TAIL.len() - self.len;
+        let mut n = s.len();
+        if n > space {
+            n = space;
+            while !s.is_char_boundary(n) {
+                n -= 1;
+            }
+            self.truncated = true;
+        }
+        self.buf[self.len..self.len + n].copy_from_slice(&s.as_bytes()[..n]);
+        self.len += n;
+        Ok(())
+    }
+}
+
+/// 去掉 ANSI 颜色序列后写入，用于不支持颜色的 sink
+pub(super) fn write_plain(sink: &mut dyn LogSink, text: &str) {
+    let mut rest = text;
+    while let Some(pos) = rest.find('\x1b') {
+        sink.write_str(&rest[..pos]);
+        rest = match rest[pos..].find('m') {
+            Some(end) => &rest[pos + end + 1..],
+            None => "",
+        };
+    }
+    sink.write_str(rest);
+}
+
+// ========== 暂存队列 ==========
+
+const PENDING_SIZE: usize = 2 * MAX_LINE_LEN;
+
+/// 发送过程中到达的日志，等当前这条发送完后再发出
+///
+/// 每项为 级别(u8) 长度(u16) 内容
+pub(super) struct PendingLines {
+    buf: [u8; PENDING_SIZE],
+    head: usize, // 下一个写入位置
+    len: usize,
+}
+
+impl PendingLines {
+    pub(super) const fn new() -> Self {
+        Self {
+            buf: [0; PENDING_SIZE],
+            head: 0,
+            len: 0,
+        }
+    }
+
+    /// 放不下时返回 false
+    pub(super) fn push(&mut self, level: Level, text: &str) -> bool {
+        if PENDING_SIZE - self.len < 3 + text.len() {
+            return false;
+        }
+        let header = [level as u8];
+        let len = (text.len() as u16).to_le_bytes();
+        for &b in header.iter().chain(&len).chain(text.as_bytes()) {
+            self.buf[self.head] = b;
+            self.head = (self.head + 1) % PENDING_SIZE;
+        }
+        self.len += 3 + text.len();
+        true
+    }
+
+    /// 取出最早的一条，复制到 `line` 中
+    pub(super) fn pop<'a>(&mut self, line: &'a mut [u8; MAX_LINE_LEN]) -> Option<(Level, &'a str)> {
+        if self.len == 0 {
+            return None;
+        }
+        let start = (self.head + PENDING_SIZE - self.len) % PENDING_SIZE;
+        let byte = |i: usize| self.buf[(start + i) % PENDING_SIZE];
+        let level = match byte(0) {
+            0 => Level::Error,
+            1 => Level::Warn,
+            2 => Level::Info,
+            3 => Level::Debug,
+            _ => Level::Trace,
+        };
+        let n = u16::from_le_bytes([byte(1), byte(2)]) as usize;
+        for (i, slot) in line[..n].iter_mut().enumerate() {
+            *slot = byte(3 + i);
+        }
+        self.len -= 3 + n;
+        // SAFETY: 由完整的 &str 复制而来
+        Some((level, unsafe { core::str::from_utf8_unchecked(&line[..n]) }))
+    }
+}
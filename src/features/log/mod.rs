@@ -0,0 +1,1337 @@
+//! # LOG
+//!
+//! 本模块自带一套与 `log` crate 接口相近的日志宏；启用 `log-facade` 后，
+//! 通过 `log = "0.4"` 门面输出日志的第三方驱动也会经由同一个记录器输出，共用级别和过滤规则
+//!
+//! ## 特性
+//! - `log`: 启用日志系统
+//! - `log-colored`: 启用彩色ANSI输出（手动实现）
+//! - `log-facade`: 初始化时把记录器注册为 `log` crate 的全局 logger
+//! - `log-deferred`: 延迟（二进制）日志，见 [`deferred`]
+//! - `log-shell`: 通过串口在运行时调整日志级别，见 [`shell`]
+//! - `log-retain`: 在 `.noinit` 段保留最近的日志，软复位后下次启动时输出，见 [`retain`]
+//! - `log-max-level-{off,error,warn,info,debug,trace}`: 编译期去掉更低级别的日志，见 [`STATIC_MAX_LEVEL`]
+//! - `log-release-max-level-{off,error,warn,info,debug,trace}`: 同上，只在 release 构建时生效
+//!
+//! ## 使用示例
+//! ```
+//! use ecos_ssc1::features::log::init_logger;
+//!
+//! init_logger();
+//!
+//! use ecos_ssc1::log::info;
+//! info!("系统已启动");
+//! ```
+//!
+//! ## 按模块过滤
+//! 过滤规则与 `env_logger` 相同：逗号分隔，单独的级别设置默认级别，`目标=级别` 设置某个模块
+//! （及其子模块）的级别，只写目标表示该模块全部输出。目标按模块路径匹配，取最长的匹配项。
+//! 规则可以在运行时用 [`set_filter`] 设置，也可以在编译期通过环境变量 `ECOS_LOG` 给出，
+//! 后者在初始化时生效。被过滤掉的日志在格式化之前就返回，不产生格式化开销
+//! ```
+//! use ecos_ssc1::features::log::set_filter;
+//!
+//! set_filter("info,my_app::sensors=trace,ecos_ssc1::qspi=off").unwrap();
+//! ```
+//!
+//! ## 格式模板
+//! 默认格式为 [`DEFAULT_FORMAT`]，初始化时打开时间戳则为 [`DEFAULT_FORMAT_TIMESTAMP`]，
+//! 时间戳是由 sys tick 按 `CONFIG_CPU_FREQ_MHZ` 换算的开机时间（见 [`uptime_us`]）。
+//! 可以用 [`set_format`] 换成自定义模板，可用的字段：
+//!
+//! | 字段 | 内容 |
+//! |------|------|
+//! | `{level}` | 级别，定宽 5 个字符，如 `INFO ` |
+//! | `{short_level}` | 级别的单字母表示，如 `I` |
+//! | `{target}` | 目标（模块路径） |
+//! | `{file}` / `{line}` | 源文件 / 行号 |
+//! | `{location}` | `文件:行号` |
+//! | `{timestamp}` | 开机以来的秒数，按 [`TimestampUnit`] 精确到毫秒或微秒，如 `12.345` |
+//! | `{uptime}` | 开机时长，如 `00:01:23.456` |
+//! | `{message}` | 日志内容 |
+//!
+//! `{{` / `}}` 输出花括号本身。启用彩色输出时 `{level}` / `{short_level}` 按级别着色，
+//! `{target}` 为蓝色
+//! ```
+//! use ecos_ssc1::features::log::{TimestampUnit, set_format, set_timestamp_unit};
+//!
+//! set_format("{uptime} {short_level} {location}: {message}").unwrap();
+//! set_timestamp_unit(TimestampUnit::Micros);
+//! ```
+//!
+//! ## 并发
+//! 每条日志先在栈上的行缓冲（[`MAX_LINE_LEN`] 字节，超出截断）中格式化，再在关中断的
+//! 临界区内一次写给所有输出目标，所以中断里打的日志不会和主循环的日志交错在一行里。
+//! 关中断挡不住的情况（异常处理、sink 自身打日志）按 [`set_reentrancy_policy`] 暂存或丢弃，
+//! 丢弃的条数见 [`dropped_records`]
+//!
+//! ## 输出目标
+//! 默认输出到 SYS UART。还可以用 [`add_sink`] 注册 HP UART、RAM 环形缓冲区或闭包等
+//! 实现了 [`LogSink`] 的输出目标，每个目标有自己的级别，在全局过滤规则之后再过滤一次
+//! ```
+//! use ecos_ssc1::features::log::{LevelFilter, RingBufferSink, SinkId, add_sink, set_sink_level};
+//!
+//! static mut RING: RingBufferSink<4096> = RingBufferSink::new();
+//!
+//! add_sink(unsafe { &mut *core::ptr::addr_of_mut!(RING) }, LevelFilter::Trace).unwrap();
+//! set_sink_level(SinkId::SYS_UART, LevelFilter::Warn).unwrap();
+//! ```
+
+#[allow(unused)]
+use crate::{print, println};
+use core::fmt;
+
+mod sink;
+#[cfg(target_arch = "riscv32")]
+pub use sink::HpUartSink;
+use sink::Sinks;
+pub use sink::{FnSink, LogSink, MAX_SINKS, RingBufferSink, SinkError, SinkId, SysUartSink};
+
+mod format;
+use format::Template;
+
+mod line;
+pub use format::{
+    DEFAULT_FORMAT, DEFAULT_FORMAT_TIMESTAMP, FormatError, TimestampUnit, uptime_ms, uptime_us,
+};
+pub use line::MAX_LINE_LEN;
+use line::{LineBuffer, PendingLines, write_plain};
+
+#[cfg(feature = "log-deferred")]
+pub mod deferred;
+
+// 命令从 SYS UART 读入，只在板上可用
+#[cfg(all(feature = "log-shell", target_arch = "riscv32"))]
+pub mod shell;
+
+#[cfg(feature = "log-retain")]
+pub mod retain;
+
+#[cfg(test)]
+mod tests;
+
+// 临界区：板上屏蔽中断，可以嵌套；宿主机上没有中断，直接执行（只在测试中使用）
+#[inline(always)]
+fn critical<R>(f: impl FnOnce() -> R) -> R {
+    #[cfg(target_arch = "riscv32")]
+    {
+        critical(f)
+    }
+    #[cfg(not(target_arch = "riscv32"))]
+    {
+        f()
+    }
+}
+
+// sys tick 计数，宿主机上恒为 0
+fn sys_tick() -> u32 {
+    // SAFETY: 只读时钟计数
+    #[cfg(target_arch = "riscv32")]
+    unsafe {
+        crate::bindings::get_sys_tick()
+    }
+    #[cfg(not(target_arch = "riscv32"))]
+    {
+        0
+    }
+}
+
+// ========== 日志级别定义 ==========
+
+/// 日志级别枚举
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Level {
+    /// 最高级别：严重的错误
+    Error = 0,
+    /// 警告级别
+    Warn = 1,
+    /// 信息级别
+    Info = 2,
+    /// 调试级别
+    Debug = 3,
+    /// 最低级别：详细的跟踪信息
+    Trace = 4,
+}
+
+impl Level {
+    /// 获取级别的字符串表示
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Level::Error => "ERROR",
+            Level::Warn => "WARN ",
+            Level::Info => "INFO ",
+            Level::Debug => "DEBUG",
+            Level::Trace => "TRACE",
+        }
+    }
+
+    /// 获取级别的简短表示
+    pub fn as_short(&self) -> char {
+        match self {
+            Level::Error => 'E',
+            Level::Warn => 'W',
+            Level::Info => 'I',
+            Level::Debug => 'D',
+            Level::Trace => 'T',
+        }
+    }
+}
+
+impl fmt::Display for Level {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+/// 日志级别过滤器
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+pub enum LevelFilter {
+    /// 关闭所有日志
+    Off,
+    /// 只显示错误
+    Error,
+    /// 显示错误和警告
+    Warn,
+    /// 显示错误、警告和信息
+    #[default]
+    Info,
+    /// 显示错误、警告、信息和调试
+    Debug,
+    /// 显示所有日志
+    Trace,
+}
+
+impl LevelFilter {
+    /// 检查给定的级别是否满足过滤器要求
+    pub const fn accepts(&self, level: Level) -> bool {
+        match self {
+            LevelFilter::Off => false,
+            LevelFilter::Error => level as usize <= Level::Error as usize,
+            LevelFilter::Warn => level as usize <= Level::Warn as usize,
+            LevelFilter::Info => level as usize <= Level::Info as usize,
+            LevelFilter::Debug => level as usize <= Level::Debug as usize,
+            LevelFilter::Trace => level as usize <= Level::Trace as usize,
+        }
+    }
+}
+
+/// 编译期允许的最高日志级别，由 `log-max-level-*` / `log-release-max-level-*` 特性决定
+///
+/// 高于它的日志宏在编译期就被判定为关闭，连同格式化字符串一起被优化掉，不占用 flash；
+/// 运行时的 [`set_max_level`] / [`set_filter`] 无法打开这些级别。
+/// `log-release-max-level-*` 只在 release 构建（没有 `debug_assertions`）时生效。
+/// 两类特性同时启用时（包括 release 构建中两者都有），取其中最严格的一个
+pub const STATIC_MAX_LEVEL: LevelFilter = static_max_level();
+
+const fn static_max_level() -> LevelFilter {
+    let level = max_level_feature();
+    let release = release_max_level_feature();
+    if (release as usize) < (level as usize) {
+        release
+    } else {
+        level
+    }
+}
+
+// `log-max-level-*` 给出的级别，同时启用多个时取最严格的一个
+#[allow(unreachable_code)]
+const fn max_level_feature() -> LevelFilter {
+    #[cfg(feature = "log-max-level-off")]
+    return LevelFilter::Off;
+    #[cfg(feature = "log-max-level-error")]
+    return LevelFilter::Error;
+    #[cfg(feature = "log-max-level-warn")]
+    return LevelFilter::Warn;
+    #[cfg(feature = "log-max-level-info")]
+    return LevelFilter::Info;
+    #[cfg(feature = "log-max-level-debug")]
+    return LevelFilter::Debug;
+
+    LevelFilter::Trace
+}
+
+// `log-release-max-level-*` 给出的级别，只在 release 构建时生效
+#[allow(unreachable_code)]
+const fn release_max_level_feature() -> LevelFilter {
+    #[cfg(all(not(debug_assertions), feature = "log-release-max-level-off"))]
+    return LevelFilter::Off;
+    #[cfg(all(not(debug_assertions), feature = "log-release-max-level-error"))]
+    return LevelFilter::Error;
+    #[cfg(all(not(debug_assertions), feature = "log-release-max-level-warn"))]
+    return LevelFilter::Warn;
+    #[cfg(all(not(debug_assertions), feature = "log-release-max-level-info"))]
+    return LevelFilter::Info;
+    #[cfg(all(not(debug_assertions), feature = "log-release-max-level-debug"))]
+    return LevelFilter::Debug;
+
+    LevelFilter::Trace
+}
+
+impl fmt::Display for LevelFilter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LevelFilter::Off => write!(f, "Off"),
+            LevelFilter::Error => write!(f, "Error"),
+            LevelFilter::Warn => write!(f, "Warn"),
+            LevelFilter::Info => write!(f, "Info"),
+            LevelFilter::Debug => write!(f, "Debug"),
+            LevelFilter::Trace => write!(f, "Trace"),
+        }
+    }
+}
+
+impl From<Level> for LevelFilter {
+    fn from(level: Level) -> Self {
+        match level {
+            Level::Error => LevelFilter::Error,
+            Level::Warn => LevelFilter::Warn,
+            Level::Info => LevelFilter::Info,
+            Level::Debug => LevelFilter::Debug,
+            Level::Trace => LevelFilter::Trace,
+        }
+    }
+}
+
+impl core::str::FromStr for LevelFilter {
+    type Err = FilterError;
+
+    /// 解析级别名称，不区分大小写：`off` / `error` / `warn` / `info` / `debug` / `trace`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        [
+            ("off", LevelFilter::Off),
+            ("error", LevelFilter::Error),
+            ("warn", LevelFilter::Warn),
+            ("info", LevelFilter::Info),
+            ("debug", LevelFilter::Debug),
+            ("trace", LevelFilter::Trace),
+        ]
+        .into_iter()
+        .find(|(name, _)| s.eq_ignore_ascii_case(name))
+        .map(|(_, filter)| filter)
+        .ok_or(FilterError::InvalidLevel)
+    }
+}
+
+/// 过滤规则错误
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FilterError {
+    /// 无法识别的级别名称
+    InvalidLevel,
+    /// 按目标的规则超过 [`MAX_DIRECTIVES`] 条
+    TooManyDirectives,
+    /// 目标路径超过 [`MAX_TARGET_LEN`] 字节
+    TargetTooLong,
+}
+
+impl fmt::Display for FilterError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FilterError::InvalidLevel => write!(f, "invalid log level"),
+            FilterError::TooManyDirectives => {
+                write!(f, "too many log directives (max {})", MAX_DIRECTIVES)
+            }
+            FilterError::TargetTooLong => {
+                write!(f, "log target too long (max {} bytes)", MAX_TARGET_LEN)
+            }
+        }
+    }
+}
+
+// ========== ANSI 颜色定义 ==========
+
+#[cfg(feature = "log-colored")]
+#[allow(unused)]
+mod ansi {
+    pub const RESET: &str = "\x1b[0m";
+    pub const BOLD: &str = "\x1b[1m";
+
+    pub const RED: &str = "\x1b[31m";
+    pub const GREEN: &str = "\x1b[32m";
+    pub const YELLOW: &str = "\x1b[33m";
+    pub const BLUE: &str = "\x1b[34m";
+    pub const MAGENTA: &str = "\x1b[35m";
+    pub const CYAN: &str = "\x1b[36m";
+
+    pub const BRIGHT_RED: &str = "\x1b[91m";
+    pub const BRIGHT_GREEN: &str = "\x1b[92m";
+    pub const BRIGHT_YELLOW: &str = "\x1b[93m";
+    pub const BRIGHT_BLUE: &str = "\x1b[94m";
+    pub const BRIGHT_MAGENTA: &str = "\x1b[95m";
+    pub const BRIGHT_CYAN: &str = "\x1b[96m";
+
+    /// 根据日志级别获取颜色
+    pub fn color_for_level(level: crate::features::log::Level) -> &'static str {
+        match level {
+            crate::features::log::Level::Error => BRIGHT_RED,
+            crate::features::log::Level::Warn => BRIGHT_YELLOW,
+            crate::features::log::Level::Info => BRIGHT_GREEN,
+            crate::features::log::Level::Debug => BRIGHT_CYAN,
+            crate::features::log::Level::Trace => BRIGHT_MAGENTA,
+        }
+    }
+}
+
+// ========== 元数据定义 ==========
+
+/// 日志元数据
+pub struct Metadata<'a> {
+    level: Level,
+    target: &'a str,
+}
+
+impl<'a> Metadata<'a> {
+    /// 创建新的元数据
+    pub const fn new(level: Level, target: &'a str) -> Self {
+        Self { level, target }
+    }
+
+    /// 获取日志级别
+    pub fn level(&self) -> Level {
+        self.level
+    }
+
+    /// 获取目标模块/组件
+    pub fn target(&self) -> &'a str {
+        self.target
+    }
+}
+
+// ========== 记录定义 ==========
+
+/// 日志记录
+pub struct Record<'a> {
+    metadata: Metadata<'a>,
+    args: fmt::Arguments<'a>,
+    file: Option<&'a str>,
+    line: Option<u32>,
+}
+
+impl<'a> Record<'a> {
+    /// 创建新的日志记录
+    pub fn new(metadata: Metadata<'a>, args: fmt::Arguments<'a>) -> Self {
+        Self {
+            metadata,
+            args,
+            file: None,
+            line: None,
+        }
+    }
+
+    /// 附上产生日志的源文件和行号
+    pub fn with_location(mut self, file: &'a str, line: u32) -> Self {
+        self.file = Some(file);
+        self.line = Some(line);
+        self
+    }
+
+    /// 获取源文件
+    pub fn file(&self) -> Option<&'a str> {
+        self.file
+    }
+
+    /// 获取行号
+    pub fn line(&self) -> Option<u32> {
+        self.line
+    }
+
+    /// 获取元数据
+    pub fn metadata(&self) -> &Metadata<'a> {
+        &self.metadata
+    }
+
+    /// 获取格式化参数
+    pub fn args(&self) -> fmt::Arguments<'a> {
+        self.args
+    }
+
+    /// 获取日志级别
+    pub fn level(&self) -> Level {
+        self.metadata.level
+    }
+
+    /// 获取目标模块
+    pub fn target(&self) -> &'a str {
+        self.metadata.target
+    }
+}
+
+// ========== 过滤规则定义 ==========
+
+/// 最多可设置的按目标过滤规则数
+pub const MAX_DIRECTIVES: usize = 8;
+/// 过滤规则中目标路径的最大长度（字节）
+pub const MAX_TARGET_LEN: usize = 48;
+
+// 由 build.rs 根据 ECOS_LOG 生成，未启用的子特性用不到其中的部分常量
+#[allow(dead_code)]
+mod config {
+    include!(concat!(env!("OUT_DIR"), "/log_config.rs"));
+}
+
+/// 一条按目标的过滤规则，目标保存在定长缓冲区中，不依赖 `alloc`
+#[derive(Clone, Copy)]
+struct Directive {
+    target: [u8; MAX_TARGET_LEN],
+    len: usize,
+    level: LevelFilter,
+}
+
+impl Directive {
+    const EMPTY: Self = Self {
+        target: [0; MAX_TARGET_LEN],
+        len: 0,
+        level: LevelFilter::Off,
+    };
+
+    fn new(target: &str, level: LevelFilter) -> Result<Self, FilterError> {
+        if target.len() > MAX_TARGET_LEN {
+            return Err(FilterError::TargetTooLong);
+        }
+        let mut directive = Self::EMPTY;
+        directive.target[..target.len()].copy_from_slice(target.as_bytes());
+        directive.len = target.len();
+        directive.level = level;
+        Ok(directive)
+    }
+
+    fn target(&self) -> &str {
+        // SAFETY: 由完整的 &str 复制而来
+        unsafe { core::str::from_utf8_unchecked(&self.target[..self.len]) }
+    }
+
+    // 目标是否为该模块本身或其子模块
+    fn matches(&self, target: &str) -> bool {
+        let prefix = self.target();
+        match target.strip_prefix(prefix) {
+            Some(rest) => rest.is_empty() || rest.starts_with("::"),
+            None => false,
+        }
+    }
+}
+
+/// 按目标的过滤规则表
+#[derive(Clone)]
+struct Directives {
+    items: [Directive; MAX_DIRECTIVES],
+    count: usize,
+}
+
+impl Directives {
+    const fn new() -> Self {
+        Self {
+            items: [Directive::EMPTY; MAX_DIRECTIVES],
+            count: 0,
+        }
+    }
+
+    fn iter(&self) -> impl Iterator<Item = &Directive> {
+        self.items[..self.count].iter()
+    }
+
+    // 新增或更新一条规则
+    fn set(&mut self, target: &str, level: LevelFilter) -> Result<(), FilterError> {
+        let directive = Directive::new(target, level)?;
+        if let Some(existing) = self.items[..self.count]
+            .iter_mut()
+            .find(|d| d.target() == target)
+        {
+            existing.level = level;
+            return Ok(());
+        }
+        if self.count >= MAX_DIRECTIVES {
+            return Err(FilterError::TooManyDirectives);
+        }
+        self.items[self.count] = directive;
+        self.count += 1;
+        Ok(())
+    }
+
+    // 最长匹配的规则给出的级别
+    fn level_for(&self, target: &str) -> Option<LevelFilter> {
+        self.iter()
+            .filter(|d| d.matches(target))
+            .max_by_key(|d| d.len)
+            .map(|d| d.level)
+    }
+
+    // 所有规则中最宽松的级别
+    fn max_level(&self) -> LevelFilter {
+        self.iter()
+            .map(|d| d.level)
+            .max()
+            .unwrap_or(LevelFilter::Off)
+    }
+}
+
+// 解析 env_logger 风格的规则串，返回 (默认级别, 规则表)；整串有效才返回 Ok
+fn parse_filter(spec: &str) -> Result<(Option<LevelFilter>, Directives), FilterError> {
+    let mut default = None;
+    let mut directives = Directives::new();
+
+    for item in spec.split(',').map(str::trim).filter(|s| !s.is_empty()) {
+        match item.split_once('=') {
+            Some((target, level)) => directives.set(target.trim(), level.parse()?)?,
+            None => match item.parse() {
+                Ok(level) => default = Some(level),
+                // 只写目标：该模块全部输出
+                Err(_) => directives.set(item, LevelFilter::Trace)?,
+            },
+        }
+    }
+
+    Ok((default, directives))
+}
+
+// ========== 日志记录器实现 ==========
+
+/// 日志记录器结构体
+pub struct EcosLogger {
+    /// 是否启用彩色输出
+    use_colors: bool,
+    /// 日志级别过滤器（没有匹配的按目标规则时使用）
+    max_level: LevelFilter,
+    /// 按目标的过滤规则
+    directives: Directives,
+    /// max_level 与所有规则中最宽松的级别，用于快速拒绝
+    enabled_level: LevelFilter,
+    /// 是否显示时间戳（没有设置格式模板时选择默认模板）
+    show_timestamp: bool,
+    /// 格式模板，`None` 时使用默认模板
+    format: Option<Template>,
+    /// `{timestamp}` 的精度
+    timestamp_unit: TimestampUnit,
+    /// 输出目标
+    sinks: Sinks,
+    /// 发送过程中又有日志到达时的处理方式
+    reentrancy: ReentrancyPolicy,
+    /// 是否正在发送
+    emitting: bool,
+    /// 发送过程中到达、等待发送的日志
+    pending: PendingLines,
+    /// 被丢弃的日志数
+    dropped: u32,
+    /// 是否已初始化
+    initialized: bool,
+}
+
+impl EcosLogger {
+    /// 创建新的日志记录器（未初始化状态）
+    const fn new() -> Self {
+        Self {
+            use_colors: cfg!(feature = "log-colored"),
+            max_level: LevelFilter::Info,
+            directives: Directives::new(),
+            enabled_level: LevelFilter::Info,
+            show_timestamp: false,
+            format: None,
+            timestamp_unit: TimestampUnit::Millis,
+            sinks: Sinks::new(),
+            reentrancy: ReentrancyPolicy::Queue,
+            emitting: false,
+            pending: PendingLines::new(),
+            dropped: 0,
+            initialized: false,
+        }
+    }
+
+    /// 初始化日志记录器
+    ///
+    /// 编译期通过 `ECOS_LOG` 给出的过滤规则在这里生效，其中的默认级别优先于 `max_level`
+    pub fn init(&mut self, use_colors: bool, max_level: LevelFilter, show_timestamp: bool) {
+        self.use_colors = use_colors;
+        self.max_level = max_level;
+        self.show_timestamp = show_timestamp;
+        self.initialized = true;
+        self.update_enabled_level();
+
+        if let Some(spec) = config::LOG_FILTER
+            && let Err(err) = self.set_filter(spec)
+        {
+            println!("[log] ignoring ECOS_LOG \"{}\": {}", spec, err);
+        }
+    }
+
+    fn update_enabled_level(&mut self) {
+        self.enabled_level = self
+            .max_level
+            .max(self.directives.max_level())
+            .min(self.sinks.max_level());
+        // 同步 log crate 的全局级别，让门面宏同样在格式化之前被过滤
+        #[cfg(feature = "log-facade")]
+        if self.initialized {
+            ::log::set_max_level(self.enabled_level.min(STATIC_MAX_LEVEL).into());
+        }
+    }
+
+    /// 按 env_logger 风格的规则串设置默认级别和所有按目标的规则
+    ///
+    /// 规则串有误时返回错误，原有配置保持不变
+    pub fn set_filter(&mut self, spec: &str) -> Result<(), FilterError> {
+        let (default, directives) = parse_filter(spec)?;
+        if let Some(level) = default {
+            self.max_level = level;
+        }
+        self.directives = directives;
+        self.update_enabled_level();
+        Ok(())
+    }
+
+    // 按规则串新增或更新规则，其他规则保留；单独的级别设置默认级别。
+    // 先在副本上应用整串，有误时返回错误，配置保持不变
+    fn update_filter(&mut self, spec: &str) -> Result<(), FilterError> {
+        let mut max_level = self.max_level;
+        let mut directives = self.directives.clone();
+        for item in spec.split(',').map(str::trim).filter(|s| !s.is_empty()) {
+            match item.split_once('=') {
+                Some((target, level)) => directives.set(target.trim(), level.parse()?)?,
+                None => max_level = item.parse()?,
+            }
+        }
+
+        self.max_level = max_level;
+        self.directives = directives;
+        self.update_enabled_level();
+        Ok(())
+    }
+
+    /// 新增或更新一个目标的级别
+    pub fn set_target_level(
+        &mut self,
+        target: &str,
+        level: LevelFilter,
+    ) -> Result<(), FilterError> {
+        self.directives.set(target.trim(), level)?;
+        self.update_enabled_level();
+        Ok(())
+    }
+
+    /// 清除所有按目标的规则
+    pub fn clear_target_levels(&mut self) {
+        self.directives = Directives::new();
+        self.update_enabled_level();
+    }
+
+    /// 目标实际生效的级别
+    pub fn target_level(&self, target: &str) -> LevelFilter {
+        self.directives.level_for(target).unwrap_or(self.max_level)
+    }
+
+    /// 检查是否已初始化
+    pub fn is_initialized(&self) -> bool {
+        self.initialized
+    }
+
+    /// 检查是否接受指定级别、指定目标的日志
+    fn accepts(&self, level: Level, target: &str) -> bool {
+        // 先与所有规则中最宽松的级别比较，绝大多数被过滤的日志不需要匹配目标
+        if !self.initialized || !self.enabled_level.accepts(level) {
+            return false;
+        }
+        if self.directives.count == 0 {
+            return true;
+        }
+        self.target_level(target).accepts(level)
+    }
+
+    // 当前生效的格式模板
+    fn template(&self) -> Template {
+        match self.format {
+            Some(template) => template,
+            None if self.show_timestamp => Template::DEFAULT_TIMESTAMP,
+            None => Template::DEFAULT,
+        }
+    }
+
+    /// 格式化并输出日志记录到每个接受该级别的输出目标
+    fn log_record(&mut self, record: &Record) {
+        // 检查是否接受该级别的日志
+        if !self.accepts(record.level(), record.target()) {
+            return;
+        }
+
+        let template = self.template();
+        let uptime_us = if template.needs_time() {
+            uptime_us()
+        } else {
+            0
+        };
+        // 只格式化一次：有 sink 需要颜色时带颜色格式化，其余 sink 输出时再去掉
+        let colored = self.use_colors && self.sinks.any_ansi(record.level());
+
+        // 格式化在临界区外进行，缓冲区在栈上，中断里的日志不会与它冲突
+        let mut line = LineBuffer::new();
+        let _ = template.write(&mut line, record, uptime_us, self.timestamp_unit, colored);
+        let text = line.finish(colored);
+
+        critical(|| self.emit(record.level(), text));
+    }
+
+    // 在临界区内发送一行，整行一次写完，不会被中断里的日志打断。
+    // 关中断挡不住异常处理或 sink 自身打的日志，这些日志按 reentrancy 丢弃或暂存，
+    // 暂存的日志由外层在当前这条发送完后发出
+    fn emit(&mut self, level: Level, text: &str) {
+        if self.emitting {
+            let queued =
+                self.reentrancy == ReentrancyPolicy::Queue && self.pending.push(level, text);
+            if !queued {
+                self.dropped = self.dropped.saturating_add(1);
+            }
+            return;
+        }
+
+        self.emitting = true;
+        self.write_line(level, text);
+        let mut line = [0u8; MAX_LINE_LEN];
+        while let Some((level, text)) = self.pending.pop(&mut line) {
+            self.write_line(level, text);
+        }
+        self.emitting = false;
+    }
+
+    fn write_line(&mut self, level: Level, text: &str) {
+        let use_colors = self.use_colors;
+        self.sinks.for_each(level, |sink| {
+            if use_colors && sink.ansi() {
+                sink.write_str(text);
+            } else {
+                write_plain(sink, text);
+            }
+        });
+    }
+}
+
+/// 一条日志正在发送时，又有日志到达（异常处理或 sink 自身打日志）的处理方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReentrancyPolicy {
+    /// 丢弃新的日志并计数
+    Drop,
+    /// 暂存新的日志，当前这条发送完后发出；暂存区满时丢弃并计数
+    Queue,
+}
+
+// 全局日志记录器实例
+static mut LOGGER: EcosLogger = EcosLogger::new();
+
+// ========== log 门面桥接 ==========
+
+#[cfg(feature = "log-facade")]
+mod facade {
+    use super::{EcosLogger, LOGGER, Level, LevelFilter, Metadata, Record};
+
+    impl From<::log::Level> for Level {
+        fn from(level: ::log::Level) -> Self {
+            match level {
+                ::log::Level::Error => Level::Error,
+                ::log::Level::Warn => Level::Warn,
+                ::log::Level::Info => Level::Info,
+                ::log::Level::Debug => Level::Debug,
+                ::log::Level::Trace => Level::Trace,
+            }
+        }
+    }
+
+    impl From<Level> for ::log::Level {
+        fn from(level: Level) -> Self {
+            match level {
+                Level::Error => ::log::Level::Error,
+                Level::Warn => ::log::Level::Warn,
+                Level::Info => ::log::Level::Info,
+                Level::Debug => ::log::Level::Debug,
+                Level::Trace => ::log::Level::Trace,
+            }
+        }
+    }
+
+    impl From<LevelFilter> for ::log::LevelFilter {
+        fn from(filter: LevelFilter) -> Self {
+            match filter {
+                LevelFilter::Off => ::log::LevelFilter::Off,
+                LevelFilter::Error => ::log::LevelFilter::Error,
+                LevelFilter::Warn => ::log::LevelFilter::Warn,
+                LevelFilter::Info => ::log::LevelFilter::Info,
+                LevelFilter::Debug => ::log::LevelFilter::Debug,
+                LevelFilter::Trace => ::log::LevelFilter::Trace,
+            }
+        }
+    }
+
+    /// 注册给 `log` crate 的记录器，转发给全局的 [`EcosLogger`]
+    ///
+    /// `EcosLogger` 存放在 `static mut` 中，不满足 `log::set_logger` 对 `Sync` 的要求，
+    /// 所以通过这个零大小的类型转发
+    struct Facade;
+
+    static FACADE: Facade = Facade;
+
+    impl ::log::Log for Facade {
+        fn enabled(&self, metadata: &::log::Metadata) -> bool {
+            super::enabled(metadata.level().into(), metadata.target())
+        }
+
+        fn log(&self, record: &::log::Record) {
+            let metadata = Metadata::new(record.level().into(), record.target());
+            let mut ecos_record = Record::new(metadata, *record.args());
+            if let (Some(file), Some(line)) = (record.file(), record.line()) {
+                ecos_record = ecos_record.with_location(file, line);
+            }
+            // SAFETY: 与本模块的宏相同的访问方式
+            unsafe {
+                let logger: *mut EcosLogger = core::ptr::addr_of_mut!(LOGGER);
+                (*logger).log_record(&ecos_record);
+            }
+        }
+
+        fn flush(&self) {
+            super::flush();
+        }
+    }
+
+    // 注册为 log crate 的全局记录器，重复调用时忽略
+    pub(super) fn install() {
+        let _ = ::log::set_logger(&FACADE);
+    }
+}
+
+// ========== 初始化函数 ==========
+
+/// 初始化日志系统
+///
+/// # 参数
+/// - `use_colors`: 是否启用彩色输出（仅当log-colored特性启用时有效）
+/// - `max_level`: 最大日志级别
+/// - `show_timestamp`: 是否显示时间戳
+///
+/// # 示例
+/// ```
+/// // 使用默认配置初始化
+/// init_logger();
+///
+/// // 自定义配置
+/// init_with_config(false, LevelFilter::Debug, true);
+/// ```
+pub fn init_with_config(use_colors: bool, max_level: LevelFilter, show_timestamp: bool) {
+    // Rust 2024 中允许使用 addr_of_mut! 来获取静态可变变量的地址
+    use core::ptr::addr_of_mut;
+
+    unsafe {
+        // 使用 addr_of_mut! 而不是直接引用
+        let logger = addr_of_mut!(LOGGER);
+        (*logger).init(use_colors, max_level, show_timestamp);
+    }
+
+    #[cfg(feature = "log-facade")]
+    facade::install();
+
+    #[cfg(feature = "log-retain")]
+    retain::init();
+}
+
+/// 使用默认配置初始化日志系统
+///
+/// 默认配置：
+/// - 彩色输出：如果启用了log-colored特性则启用
+/// - 日志级别：Info
+/// - 时间戳：不显示
+pub fn init_logger() {
+    init_with_config(cfg!(feature = "log-colored"), LevelFilter::Info, false);
+}
+
+/// 检查日志系统是否已初始化
+pub fn is_initialized() -> bool {
+    // Rust 2024 中允许使用 addr_of! 来获取静态变量的地址
+    use core::ptr::addr_of;
+
+    unsafe {
+        let logger = addr_of!(LOGGER);
+        (*logger).is_initialized()
+    }
+}
+
+/// 获取当前日志级别过滤器
+pub fn max_level() -> LevelFilter {
+    use core::ptr::addr_of;
+
+    unsafe {
+        let logger = addr_of!(LOGGER);
+        (*logger).max_level
+    }
+}
+
+/// 设置日志级别过滤器
+pub fn set_max_level(level: LevelFilter) {
+    use core::ptr::addr_of_mut;
+
+    unsafe {
+        let logger = addr_of_mut!(LOGGER);
+        if (*logger).is_initialized() {
+            (*logger).max_level = level;
+            (*logger).update_enabled_level();
+        }
+    }
+}
+
+/// 按 env_logger 风格的规则串设置过滤规则，如 `"info,my_app::sensors=trace,ecos_ssc1::qspi=off"`
+///
+/// 单独的级别设置默认级别（同 [`set_max_level`]），`目标=级别` 设置模块及其子模块的级别，
+/// 只写目标表示该模块全部输出。原有的按目标规则全部被替换；规则串有误时返回错误，配置保持不变
+pub fn set_filter(spec: &str) -> Result<(), FilterError> {
+    use core::ptr::addr_of_mut;
+
+    critical(|| unsafe {
+        let logger = addr_of_mut!(LOGGER);
+        (*logger).set_filter(spec)
+    })
+}
+
+/// 按规则串新增或更新规则，串中没有提到的规则保留
+///
+/// 与 [`set_filter`] 的写法相同，但不会清除原有的按目标规则；单独的级别设置默认级别。
+/// 整串有效才生效，有误时返回错误，配置保持不变
+pub fn update_filter(spec: &str) -> Result<(), FilterError> {
+    use core::ptr::addr_of_mut;
+
+    critical(|| unsafe {
+        let logger = addr_of_mut!(LOGGER);
+        (*logger).update_filter(spec)
+    })
+}
+
+/// 新增或更新一个目标（模块路径）的级别，不影响其他规则
+pub fn set_target_level(target: &str, level: LevelFilter) -> Result<(), FilterError> {
+    use core::ptr::addr_of_mut;
+
+    critical(|| unsafe {
+        let logger = addr_of_mut!(LOGGER);
+        (*logger).set_target_level(target, level)
+    })
+}
+
+/// 清除所有按目标的规则，只保留默认级别
+pub fn clear_target_levels() {
+    use core::ptr::addr_of_mut;
+
+    critical(|| unsafe {
+        let logger = addr_of_mut!(LOGGER);
+        (*logger).clear_target_levels();
+    })
+}
+
+/// 目标实际生效的级别
+pub fn target_level(target: &str) -> LevelFilter {
+    use core::ptr::addr_of;
+
+    unsafe {
+        let logger = addr_of!(LOGGER);
+        (*logger).target_level(target)
+    }
+}
+
+/// 遍历所有按目标的规则：(目标, 级别)
+pub fn for_each_target_level(mut f: impl FnMut(&str, LevelFilter)) {
+    use core::ptr::addr_of;
+
+    unsafe {
+        let logger = addr_of!(LOGGER);
+        for directive in (*logger).directives.iter() {
+            f(directive.target(), directive.level);
+        }
+    }
+}
+
+/// 设置日志的格式模板，可用的字段见模块文档的“格式模板”一节
+///
+/// 模板有误时返回错误，原有格式保持不变
+pub fn set_format(template: &'static str) -> Result<(), FormatError> {
+    use core::ptr::addr_of_mut;
+
+    let template = Template::parse(template)?;
+    unsafe {
+        let logger = addr_of_mut!(LOGGER);
+        (*logger).format = Some(template);
+    }
+    Ok(())
+}
+
+/// 恢复默认格式（由初始化时的 `show_timestamp` 决定是否带时间戳）
+pub fn reset_format() {
+    use core::ptr::addr_of_mut;
+
+    unsafe {
+        let logger = addr_of_mut!(LOGGER);
+        (*logger).format = None;
+    }
+}
+
+/// 当前生效的格式模板
+pub fn format() -> &'static str {
+    use core::ptr::addr_of;
+
+    unsafe {
+        let logger = addr_of!(LOGGER);
+        (*logger).template().source()
+    }
+}
+
+/// 设置 `{timestamp}` 精确到毫秒还是微秒
+pub fn set_timestamp_unit(unit: TimestampUnit) {
+    use core::ptr::addr_of_mut;
+
+    unsafe {
+        let logger = addr_of_mut!(LOGGER);
+        (*logger).timestamp_unit = unit;
+    }
+}
+
+/// 注册一个输出目标，级别低于 `level` 的日志不会写入它
+///
+/// 返回的编号用于 [`set_sink_level`] / [`remove_sink`]
+pub fn add_sink(sink: &'static mut dyn LogSink, level: LevelFilter) -> Result<SinkId, SinkError> {
+    use core::ptr::addr_of_mut;
+
+    critical(|| unsafe {
+        let logger = addr_of_mut!(LOGGER);
+        let id = (*logger).sinks.add(sink, level)?;
+        (*logger).update_enabled_level();
+        Ok(id)
+    })
+}
+
+/// 移除一个输出目标并把它交还给调用者；SYS UART 不能移除，只能把级别设为 `Off`
+pub fn remove_sink(id: SinkId) -> Result<&'static mut dyn LogSink, SinkError> {
+    use core::ptr::addr_of_mut;
+
+    critical(|| unsafe {
+        let logger = addr_of_mut!(LOGGER);
+        let sink = (*logger).sinks.remove(id)?;
+        (*logger).update_enabled_level();
+        Ok(sink)
+    })
+}
+
+/// 设置某个输出目标的级别
+pub fn set_sink_level(id: SinkId, level: LevelFilter) -> Result<(), SinkError> {
+    use core::ptr::addr_of_mut;
+
+    critical(|| unsafe {
+        let logger = addr_of_mut!(LOGGER);
+        (*logger).sinks.set_level(id, level)?;
+        (*logger).update_enabled_level();
+        Ok(())
+    })
+}
+
+/// 获取某个输出目标的级别，编号不存在时返回 `None`
+pub fn sink_level(id: SinkId) -> Option<LevelFilter> {
+    use core::ptr::addr_of;
+
+    unsafe {
+        let logger = addr_of!(LOGGER);
+        (*logger).sinks.level(id)
+    }
+}
+
+/// 设置发送过程中又有日志到达时的处理方式，默认 [`ReentrancyPolicy::Queue`]
+pub fn set_reentrancy_policy(policy: ReentrancyPolicy) {
+    use core::ptr::addr_of_mut;
+
+    unsafe {
+        let logger = addr_of_mut!(LOGGER);
+        (*logger).reentrancy = policy;
+    }
+}
+
+/// 因重入被丢弃的日志数
+pub fn dropped_records() -> u32 {
+    use core::ptr::addr_of;
+
+    unsafe {
+        let logger = addr_of!(LOGGER);
+        (*logger).dropped
+    }
+}
+
+/// 遍历所有输出目标：(编号, 级别)
+pub fn for_each_sink(mut f: impl FnMut(SinkId, LevelFilter)) {
+    use core::ptr::addr_of;
+
+    unsafe {
+        let logger = addr_of!(LOGGER);
+        for (id, level) in (*logger).sinks.levels() {
+            f(id, level);
+        }
+    }
+}
+
+/// 让所有输出目标输出缓冲中的内容
+pub fn flush() {
+    use core::ptr::addr_of_mut;
+
+    critical(|| unsafe {
+        let logger = addr_of_mut!(LOGGER);
+        (*logger).sinks.flush();
+    })
+}
+
+/// 进入 panic 处理时调用
+///
+/// panic 可能发生在发送日志的途中（如 sink 内部），这时发送中的标记不会被清除，
+/// 之后的日志（包括 panic 信息）都会被当作重入的日志暂存而不再发出，所以这里强制清除
+#[cfg(all(feature = "panic", target_arch = "riscv32"))]
+pub(crate) fn on_panic() {
+    use core::ptr::addr_of_mut;
+
+    unsafe {
+        let logger = addr_of_mut!(LOGGER);
+        (*logger).emitting = false;
+    }
+
+    #[cfg(feature = "log-retain")]
+    retain::mark_panic();
+}
+
+/// 检查指定级别、指定目标的日志是否会被输出
+///
+/// 日志宏在格式化参数之前调用，被过滤掉的日志没有格式化开销
+#[inline]
+pub fn enabled(level: Level, target: &str) -> bool {
+    use core::ptr::addr_of;
+
+    unsafe {
+        let logger = addr_of!(LOGGER);
+        (*logger).accepts(level, target)
+    }
+}
+
+/// 内部日志函数（供宏使用）
+#[doc(hidden)]
+pub fn __log_internal(
+    level: Level,
+    target: &'static str,
+    file: &'static str,
+    line: u32,
+    args: fmt::Arguments,
+) {
+    use core::ptr::addr_of_mut;
+
+    unsafe {
+        let logger = addr_of_mut!(LOGGER);
+        if (*logger).accepts(level, target) {
+            let metadata = Metadata::new(level, target);
+            let record = Record::new(metadata, args).with_location(file, line);
+            (*logger).log_record(&record);
+        }
+    }
+}
+
+// ========== 日志宏 ==========
+
+/// 通用的日志宏
+#[macro_export]
+macro_rules! __log {
+    ($level:expr, $($arg:tt)*) => {{
+        let level = $level;
+        // 使用模块路径作为目标
+        let target = module_path!();
+        // 过滤在格式化之前完成；先与编译期上限比较，关闭的级别整个分支被优化掉
+        if $crate::features::log::STATIC_MAX_LEVEL.accepts(level)
+            && $crate::features::log::enabled(level, target)
+        {
+            $crate::features::log::__log_internal(
+                level,
+                target,
+                file!(),
+                line!(),
+                format_args!($($arg)*),
+            );
+        }
+    }};
+}
+
+/// 错误级别日志宏
+#[macro_export]
+macro_rules! __error {
+    ($($arg:tt)*) => {{
+        $crate::log!($crate::features::log::Level::Error, $($arg)*);
+    }};
+}
+
+/// 警告级别日志宏
+#[macro_export]
+macro_rules! __warn {
+    ($($arg:tt)*) => {{
+        $crate::log!($crate::features::log::Level::Warn, $($arg)*);
+    }};
+}
+
+/// 信息级别日志宏
+#[macro_export]
+macro_rules! __info {
+    ($($arg:tt)*) => {{
+        $crate::log!($crate::features::log::Level::Info, $($arg)*);
+    }};
+}
+
+/// 调试级别日志宏
+#[macro_export]
+macro_rules! __debug {
+    ($($arg:tt)*) => {{
+        $crate::log!($crate::features::log::Level::Debug, $($arg)*);
+    }};
+}
+
+/// 跟踪级别日志宏
+#[macro_export]
+macro_rules! __trace {
+    ($($arg:tt)*) => {{
+        $crate::log!($crate::features::log::Level::Trace, $($arg)*);
+    }};
+}
+
+/// 便利的日志初始化宏
+#[macro_export]
+macro_rules! init_logging {
+    () => {{
+        $crate::features::log::init_logger();
+        $crate::log::info!("日志系统已初始化");
+    }};
+
+    ($level:expr) => {{
+        use $crate::features::log::{LevelFilter, init_with_config};
+        let level = $level;
+        init_with_config(cfg!(feature = "log-colored"), level, false);
+        $crate::log::info!("日志系统已初始化，级别: {}", level);
+    }};
+
+    ($level:expr, $colors:expr) => {{
+        use $crate::features::log::{LevelFilter, init_with_config};
+        let level = $level;
+        let use_colors = $colors;
+        init_with_config(use_colors, level, false);
+        $crate::log::info!(
+            "日志系统已初始化，级别: {}，彩色输出: {}",
+            level,
+            if use_colors { "启用" } else { "禁用" }
+        );
+    }};
+
+    ($level:expr, $colors:expr, $timestamp:expr) => {{
+        use $crate::features::log::{LevelFilter, init_with_config};
+        let level = $level;
+        let use_colors = $colors;
+        let show_timestamp = $timestamp;
+        init_with_config(use_colors, level, show_timestamp);
+        $crate::log::info!(
+            "日志系统已初始化，级别: {}，彩色输出: {}，时间戳: {}",
+            level,
+            if use_colors { "启用" } else { "禁用" },
+            if show_timestamp { "启用" } else { "禁用" }
+        );
+    }};
+}
+
+// 保持不变
+pub use crate::{
+    __debug as debug, __error as error, __info as info, __log as log, __trace as trace,
+    __warn as warn,
+};
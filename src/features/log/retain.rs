@@ -0,0 +1,116 @@
+//! 跨复位保留的最近日志，启用 `log-retain` 特性后可用
+//!
+//! 初始化日志系统时注册一个放在 `.noinit` 段的 [`RingBufferSink`]，保存最近约
+//! `ECOS_LOG_RETAIN_BUFFER` 字节（默认 1K）的文本日志，空间不够时丢弃最早的整行。
+//! 这个段不被启动代码清零，软复位（看门狗、调试器复位等）后内容仍在：下次初始化
+//! （`ecos_main` 会自动调用 [`init_logger`](super::init_logger)）时先把它夹在
+//! `previous boot log` 标记之间输出到 SYS UART，再清空并开始保存本次的日志。
+//! 上次以 panic 结束时标记中会注明
+//!
+//! 上电后 RAM 的内容是随机的，靠头部的魔数和长度检查区分。链接脚本需要把 `.noinit`
+//! 放在 RAM 中并标为 `NOLOAD`，不能并入 `.bss`：
+//!
+//! ```text
+//! .noinit (NOLOAD) : { KEEP(*(.noinit .noinit.*)) } > RAM
+//! ```
+//!
+//! 保留缓冲区占用一个输出目标的位置（见 [`MAX_SINKS`](super::MAX_SINKS)），
+//! 默认接受所有级别，可以用 [`sink_id`] 和 [`set_sink_level`](super::set_sink_level) 调整
+
+use core::mem::MaybeUninit;
+use core::ptr::{addr_of, addr_of_mut};
+
+use super::sink::{RingBufferSink, SysUartSink};
+use super::{LevelFilter, SinkId, add_sink, config, critical};
+use crate::println;
+
+const RETAIN_SIZE: usize = config::RETAIN_BUFFER;
+
+// 头部的魔数，与随机内容区分
+const MAGIC: u32 = 0xec05_1061;
+
+#[repr(C)]
+struct Retained {
+    magic: u32,
+    /// 非 0 表示以 panic 结束
+    panicked: u32,
+    ring: RingBufferSink<RETAIN_SIZE>,
+}
+
+#[unsafe(link_section = ".noinit")]
+static mut RETAINED: MaybeUninit<Retained> = MaybeUninit::uninit();
+
+static mut SINK: Option<SinkId> = None;
+
+/// 保留缓冲区对应的输出目标，尚未初始化或注册失败时为 `None`
+pub fn sink_id() -> Option<SinkId> {
+    // SAFETY: 只在初始化时写入
+    unsafe { *addr_of!(SINK) }
+}
+
+// 初始化日志系统时调用：输出上次保留的日志，然后清空并注册为输出目标
+pub(super) fn init() {
+    if sink_id().is_some() {
+        return;
+    }
+
+    // SAFETY: 注册为输出目标之前只有这里访问；魔数不对时内容只读不用。
+    // 所有字段都是整数，任意位模式都合法，下标在使用前经过检查
+    let retained = unsafe { &mut *(*addr_of_mut!(RETAINED)).as_mut_ptr() };
+
+    // 在临界区内检查，在临界区外输出，避免长时间关中断
+    let valid = critical(|| {
+        // SAFETY: 读取整数字段
+        let magic = unsafe { addr_of!(retained.magic).read_volatile() };
+        magic == MAGIC && retained.ring.is_consistent()
+    });
+    if valid {
+        report(retained);
+    }
+
+    critical(|| {
+        retained.ring.clear();
+        retained.panicked = 0;
+        // SAFETY: 写入整数字段
+        unsafe { addr_of_mut!(retained.magic).write_volatile(MAGIC) };
+
+        // SAFETY: 只在初始化时写入
+        unsafe { *addr_of_mut!(SINK) = add_sink(&mut retained.ring, LevelFilter::Trace).ok() };
+    })
+}
+
+fn report(retained: &Retained) {
+    let ring = &retained.ring;
+    if ring.is_empty() {
+        return;
+    }
+
+    let ending = if retained.panicked != 0 {
+        ", ended in panic"
+    } else {
+        ""
+    };
+    println!(
+        "===== previous boot log ({} bytes{}) =====",
+        ring.len(),
+        ending
+    );
+    if ring.overwritten() > 0 {
+        println!("... {} earlier bytes overwritten", ring.overwritten());
+    }
+    ring.dump_to(&mut SysUartSink);
+    println!("===== end of previous boot log =====");
+}
+
+// panic 时调用，下次启动时标记中会注明
+#[cfg(all(feature = "panic", target_arch = "riscv32"))]
+pub(super) fn mark_panic() {
+    if sink_id().is_none() {
+        return;
+    }
+    // SAFETY: 已初始化，只写一个整数
+    unsafe {
+        let retained = (*addr_of_mut!(RETAINED)).as_mut_ptr();
+        addr_of_mut!((*retained).panicked).write_volatile(1);
+    }
+}
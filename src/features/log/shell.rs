@@ -0,0 +1,198 @@
+//! 串口日志控制命令，启用 `log-shell` 特性后可用
+//!
+//! 不需要重新烧录就能在现场调整日志级别：在主循环或空闲钩子中调用 [`poll`]，
+//! 它以非阻塞方式读取 SYS UART 上已收到的字节，凑成一行后执行。支持的命令：
+//!
+//! | 命令 | 作用 |
+//! |------|------|
+//! | `log` / `log show` | 显示当前配置 |
+//! | `log level <级别>` | 设置默认级别，同 [`set_max_level`] |
+//! | `log filter <目标>=<级别>[,...]` | 新增或更新按目标的规则，同 [`update_filter`] |
+//! | `log clear` | 清除所有按目标的规则 |
+//! | `log sink <编号> <级别>` | 设置输出目标的级别，同 [`set_sink_level`] |
+//! | `log help` | 显示帮助 |
+//!
+//! 命令的回显和结果直接写到 SYS UART，不受日志级别影响
+//!
+//! ```
+//! use ecos_ssc1::features::log::shell;
+//!
+//! loop {
+//!     // ... 主循环的工作
+//!     shell::poll();
+//! }
+//! ```
+
+use core::ptr::addr_of_mut;
+
+use super::{
+    LevelFilter, STATIC_MAX_LEVEL, SinkId, clear_target_levels, dropped_records, for_each_sink,
+    for_each_target_level, format, max_level, set_max_level, set_sink_level, update_filter,
+};
+use crate::uart::Uart;
+use crate::{print, println};
+
+/// 一行命令的最大长度，超出部分被忽略
+pub const MAX_COMMAND_LEN: usize = 96;
+
+/// 按行接收并执行日志控制命令
+pub struct LogShell {
+    line: [u8; MAX_COMMAND_LEN],
+    len: usize,
+    overflow: bool,
+    echo: bool,
+}
+
+impl LogShell {
+    /// `echo` 为 true 时把收到的字符回显到 SYS UART，适合直接用串口终端输入
+    pub const fn new(echo: bool) -> Self {
+        Self {
+            line: [0; MAX_COMMAND_LEN],
+            len: 0,
+            overflow: false,
+            echo,
+        }
+    }
+
+    /// 处理一个输入字节，收到完整的一行时执行并返回 true
+    pub fn feed(&mut self, byte: u8) -> bool {
+        match byte {
+            b'\r' | b'\n' => {
+                if self.echo {
+                    println!();
+                }
+                let len = core::mem::take(&mut self.len);
+                if core::mem::take(&mut self.overflow) {
+                    println!("error: command too long (max {} bytes)", MAX_COMMAND_LEN);
+                    return false;
+                }
+                if len == 0 {
+                    return false;
+                }
+                let line = self.line;
+                match core::str::from_utf8(&line[..len]) {
+                    Ok(line) => execute(line),
+                    Err(_) => println!("error: command is not valid UTF-8"),
+                }
+                true
+            }
+            // 退格
+            0x08 | 0x7f => {
+                if self.len > 0 {
+                    self.len -= 1;
+                    if self.echo {
+                        print!("\x08 \x08");
+                    }
+                }
+                false
+            }
+            _ => {
+                if self.len < MAX_COMMAND_LEN {
+                    self.line[self.len] = byte;
+                    self.len += 1;
+                } else {
+                    self.overflow = true;
+                }
+                if self.echo {
+                    Uart::write_byte(byte);
+                }
+                false
+            }
+        }
+    }
+
+    /// 读取 SYS UART 上所有已收到的字节，返回执行了的命令数
+    pub fn poll(&mut self) -> usize {
+        let mut executed = 0;
+        while let Some(byte) = Uart::read_byte_nonblock() {
+            if self.feed(byte) {
+                executed += 1;
+            }
+        }
+        executed
+    }
+}
+
+static mut SHELL: LogShell = LogShell::new(true);
+
+/// 用内置的命令行处理 SYS UART 上已收到的输入（带回显），不会阻塞，返回执行了的命令数
+pub fn poll() -> usize {
+    // SAFETY: 只应在主循环或空闲钩子这一个上下文中调用
+    unsafe { (*addr_of_mut!(SHELL)).poll() }
+}
+
+/// 执行一行命令，结果写到 SYS UART
+pub fn execute(line: &str) {
+    let mut words = line.split_whitespace();
+    if words.next() != Some("log") {
+        println!("error: unknown command, try `log help`");
+        return;
+    }
+
+    match (words.next(), words.next(), words.next()) {
+        (None | Some("show"), None, None) => show(),
+        (Some("level"), Some(level), None) => match level.parse::<LevelFilter>() {
+            Ok(level) => {
+                set_max_level(level);
+                if STATIC_MAX_LEVEL < level {
+                    println!("note: levels above {} are compiled out", STATIC_MAX_LEVEL);
+                }
+                println!("log level: {}", max_level());
+            }
+            Err(err) => println!("error: {}", err),
+        },
+        (Some("filter"), Some(spec), None) => match update_filter(spec) {
+            Ok(()) => show(),
+            Err(err) => println!("error: {}", err),
+        },
+        (Some("clear"), None, None) => {
+            clear_target_levels();
+            println!("target filters cleared");
+        }
+        (Some("sink"), Some(id), Some(level)) => {
+            let Ok(id) = id.parse::<usize>() else {
+                println!("error: invalid sink id {:?}", id);
+                return;
+            };
+            let level = match level.parse::<LevelFilter>() {
+                Ok(level) => level,
+                Err(err) => {
+                    println!("error: {}", err);
+                    return;
+                }
+            };
+            match set_sink_level(SinkId(id), level) {
+                Ok(()) => println!("sink {}: {}", id, level),
+                Err(err) => println!("error: {}", err),
+            }
+        }
+        (Some("help"), None, None) => help(),
+        _ => println!("error: invalid arguments, try `log help`"),
+    }
+}
+
+fn show() {
+    println!(
+        "log level: {} (compile-time max: {})",
+        max_level(),
+        STATIC_MAX_LEVEL
+    );
+    for_each_target_level(|target, level| println!("  filter {}={}", target, level));
+    for_each_sink(|id, level| {
+        if id == SinkId::SYS_UART {
+            println!("  sink {} (sys uart): {}", id.index(), level);
+        } else {
+            println!("  sink {}: {}", id.index(), level);
+        }
+    });
+    println!("  format: {:?}", format());
+    println!("  dropped: {}", dropped_records());
+}
+
+fn help() {
+    println!("log                        show current configuration");
+    println!("log level <level>          set default level (off/error/warn/info/debug/trace)");
+    println!("log filter <target>=<level>[,...]  add or update target filters");
+    println!("log clear                  remove all target filters");
+    println!("log sink <id> <level>      set the level of an output sink");
+}
@@ -0,0 +1,404 @@
+//! 日志输出目标（sink）
+//!
+//! 每条日志通过全局过滤规则后，依次写入级别允许它的每个 sink。SYS UART 固定占用
+//! [`SinkId::SYS_UART`]，不能移除，只能把它的级别设为 `Off`；此外最多还能注册
+//! [`MAX_SINKS`] 个 sink
+
+use core::fmt;
+
+use super::{Level, LevelFilter};
+#[cfg(target_arch = "riscv32")]
+use crate::bindings;
+
+/// 除 SYS UART 外最多可注册的 sink 数
+pub const MAX_SINKS: usize = 4;
+
+/// 日志输出目标
+///
+/// 一条日志会分几次调用 `write_str` 写入，最后以 `\n` 结束
+pub trait LogSink {
+    /// 写入一段已格式化的文本
+    fn write_str(&mut self, s: &str);
+
+    /// 是否输出 ANSI 颜色，默认不输出（同时还受 `log-colored` 和初始化参数控制）
+    fn ansi(&self) -> bool {
+        false
+    }
+
+    /// 输出缓冲中的内容，默认什么也不做
+    fn flush(&mut self) {}
+}
+
+/// SYS UART，与 `print!` 相同，经由 `sys_putchar` 输出
+pub struct SysUartSink;
+
+impl LogSink for SysUartSink {
+    fn write_str(&mut self, s: &str) {
+        #[cfg(target_arch = "riscv32")]
+        crate::uart::Uart::write_str(s);
+        // 宿主机上输出到标准输出
+        #[cfg(not(target_arch = "riscv32"))]
+        crate::print!("{}", s);
+    }
+
+    fn ansi(&self) -> bool {
+        true
+    }
+}
+
+/// HP UART，直接操作 `REG_UART_1_*` 寄存器发送
+///
+/// 只负责发送，波特率和帧格式需要事先配置好
+#[cfg(target_arch = "riscv32")]
+pub struct HpUartSink;
+
+#[cfg(target_arch = "riscv32")]
+impl HpUartSink {
+    // LSR 中的发送 FIFO 满标志
+    const LSR_TX_FULL: u32 = 1 << 8;
+
+    /// 发送一个字节，发送 FIFO 满时忙等
+    pub fn write_byte(b: u8) {
+        unsafe {
+            let lsr = bindings::REG_UART_1_LSR as *const u32;
+            while core::ptr::read_volatile(lsr) & Self::LSR_TX_FULL != 0 {}
+            core::ptr::write_volatile(bindings::REG_UART_1_TRX as *mut u32, b as u32);
+        }
+    }
+}
+
+#[cfg(target_arch = "riscv32")]
+impl LogSink for HpUartSink {
+    fn write_str(&mut self, s: &str) {
+        for b in s.bytes() {
+            Self::write_byte(b);
+        }
+    }
+
+    fn ansi(&self) -> bool {
+        true
+    }
+}
+
+/// RAM 环形缓冲区，保存最近写入的 `N` 字节日志，可以在故障后读出
+///
+/// 空间不够时丢弃最早的整行，所以缓冲区总是从一行的开头开始
+pub struct RingBufferSink<const N: usize> {
+    buf: [u8; N],
+    head: usize, // 下一个写入位置
+    len: usize,
+    overwritten: usize,
+}
+
+impl<const N: usize> RingBufferSink<N> {
+    pub const fn new() -> Self {
+        Self {
+            buf: [0; N],
+            head: 0,
+            len: 0,
+            overwritten: 0,
+        }
+    }
+
+    /// 缓冲区中的字节数
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub const fn capacity(&self) -> usize {
+        N
+    }
+
+    /// 因空间不够被丢弃的字节数
+    pub fn overwritten(&self) -> usize {
+        self.overwritten
+    }
+
+    pub fn clear(&mut self) {
+        self.head = 0;
+        self.len = 0;
+        self.overwritten = 0;
+    }
+
+    // 下标是否在范围内，用于检查复位后保留下来的内容
+    #[cfg(feature = "log-retain")]
+    pub(super) fn is_consistent(&self) -> bool {
+        (self.head < N || N == 0) && self.len <= N
+    }
+
+    // 最早的字节所在位置
+    fn start(&self) -> usize {
+        (self.head + N - self.len) % N
+    }
+
+    /// 按写入顺序返回缓冲区内容，回绕时分成两段
+    pub fn as_slices(&self) -> (&[u8], &[u8]) {
+        if self.len == 0 {
+            return (&[], &[]);
+        }
+        let start = self.start();
+        if start + self.len <= N {
+            (&self.buf[start..start + self.len], &[])
+        } else {
+            (&self.buf[start..], &self.buf[..self.head])
+        }
+    }
+
+    /// 取出最早的数据放入 `out`，返回取出的字节数
+    pub fn read(&mut self, out: &mut [u8]) -> usize {
+        let (first, second) = self.as_slices();
+        let n = out.len().min(self.len);
+        let split = n.min(first.len());
+        out[..split].copy_from_slice(&first[..split]);
+        out[split..n].copy_from_slice(&second[..n - split]);
+        self.len -= n;
+        n
+    }
+
+    /// 把缓冲区内容原样写入另一个 sink，如在故障处理中输出到 UART
+    pub fn dump_to(&self, sink: &mut dyn LogSink) {
+        let (first, second) = self.as_slices();
+
+        // 回绕处可能切开一个多字节字符，把它拼到临时缓冲区里单独输出
+        let tail = first
+            .utf8_chunks()
+            .last()
+            .map_or(0, |chunk| chunk.invalid().len());
+        let (first, partial) = first.split_at(first.len() - tail);
+        write_utf8(sink, first);
+
+        let mut joined = [0u8; 4];
+        let need = partial
+            .first()
+            .map_or(0, |&b| utf8_width(b).saturating_sub(partial.len()))
+            .min(second.len());
+        joined[..partial.len()].copy_from_slice(partial);
+        joined[partial.len()..partial.len() + need].copy_from_slice(&second[..need]);
+        write_utf8(sink, &joined[..partial.len() + need]);
+
+        write_utf8(sink, &second[need..]);
+    }
+
+    // 丢弃最早的一行（包括换行符）
+    fn discard_line(&mut self) {
+        let start = self.start();
+        let mut n = 0;
+        while n < self.len {
+            let b = self.buf[(start + n) % N];
+            n += 1;
+            if b == b'\n' {
+                break;
+            }
+        }
+        self.len -= n;
+        self.overwritten += n;
+    }
+}
+
+impl<const N: usize> Default for RingBufferSink<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> LogSink for RingBufferSink<N> {
+    fn write_str(&mut self, s: &str) {
+        let mut bytes = s.as_bytes();
+        if N == 0 {
+            return;
+        }
+        if bytes.len() > N {
+            let skip = bytes.len() - N;
+            self.overwritten += skip;
+            bytes = &bytes[skip..];
+        }
+
+        while N - self.len < bytes.len() {
+            self.discard_line();
+        }
+        for &b in bytes {
+            self.buf[self.head] = b;
+            self.head = (self.head + 1) % N;
+        }
+        self.len += bytes.len();
+    }
+}
+
+// 首字节给出的 UTF-8 字符长度
+fn utf8_width(b: u8) -> usize {
+    match b {
+        0xc0..=0xdf => 2,
+        0xe0..=0xef => 3,
+        0xf0..=0xf7 => 4,
+        _ => 1,
+    }
+}
+
+// 只输出合法的 UTF-8 部分
+fn write_utf8(sink: &mut dyn LogSink, bytes: &[u8]) {
+    for chunk in bytes.utf8_chunks() {
+        sink.write_str(chunk.valid());
+    }
+}
+
+/// 用闭包作为 sink
+///
+/// 注册需要 `'static` 的可变引用：闭包不捕获状态时可以放在 `static mut` 中，
+/// 否则可以在启用 `alloc` 时用 `Box::leak` 得到
+pub struct FnSink<F: FnMut(&str)>(F);
+
+impl<F: FnMut(&str)> FnSink<F> {
+    pub const fn new(f: F) -> Self {
+        Self(f)
+    }
+}
+
+impl<F: FnMut(&str)> LogSink for FnSink<F> {
+    fn write_str(&mut self, s: &str) {
+        (self.0)(s)
+    }
+}
+
+// ========== sink 注册表 ==========
+
+/// 已注册 sink 的编号
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SinkId(pub(super) usize);
+
+impl SinkId {
+    /// SYS UART，始终存在
+    pub const SYS_UART: SinkId = SinkId(0);
+
+    /// 编号的数值，SYS UART 为 0
+    pub const fn index(self) -> usize {
+        self.0
+    }
+}
+
+/// sink 注册错误
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SinkError {
+    /// 已注册 [`MAX_SINKS`] 个 sink
+    Full,
+    /// 编号不存在（或对 SYS UART 执行了移除）
+    InvalidId,
+}
+
+impl fmt::Display for SinkError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SinkError::Full => write!(f, "too many log sinks (max {})", MAX_SINKS),
+            SinkError::InvalidId => write!(f, "invalid log sink id"),
+        }
+    }
+}
+
+struct Slot {
+    sink: &'static mut dyn LogSink,
+    level: LevelFilter,
+}
+
+/// sink 注册表：SYS UART 只记录级别，其余 sink 放在定长表中，编号为下标加一
+pub(super) struct Sinks {
+    sys_uart: LevelFilter,
+    slots: [Option<Slot>; MAX_SINKS],
+}
+
+impl Sinks {
+    pub(super) const fn new() -> Self {
+        Self {
+            sys_uart: LevelFilter::Trace,
+            slots: [const { None }; MAX_SINKS],
+        }
+    }
+
+    pub(super) fn add(
+        &mut self,
+        sink: &'static mut dyn LogSink,
+        level: LevelFilter,
+    ) -> Result<SinkId, SinkError> {
+        let index = self
+            .slots
+            .iter()
+            .position(Option::is_none)
+            .ok_or(SinkError::Full)?;
+        self.slots[index] = Some(Slot { sink, level });
+        Ok(SinkId(index + 1))
+    }
+
+    pub(super) fn remove(&mut self, id: SinkId) -> Result<&'static mut dyn LogSink, SinkError> {
+        match id.0.checked_sub(1).and_then(|i| self.slots.get_mut(i)) {
+            Some(slot) => slot.take().map(|s| s.sink).ok_or(SinkError::InvalidId),
+            None => Err(SinkError::InvalidId),
+        }
+    }
+
+    fn level_mut(&mut self, id: SinkId) -> Option<&mut LevelFilter> {
+        match id.0 {
+            0 => Some(&mut self.sys_uart),
+            n => self.slots.get_mut(n - 1)?.as_mut().map(|s| &mut s.level),
+        }
+    }
+
+    pub(super) fn set_level(&mut self, id: SinkId, level: LevelFilter) -> Result<(), SinkError> {
+        *self.level_mut(id).ok_or(SinkError::InvalidId)? = level;
+        Ok(())
+    }
+
+    pub(super) fn level(&self, id: SinkId) -> Option<LevelFilter> {
+        match id.0 {
+            0 => Some(self.sys_uart),
+            n => self.slots.get(n - 1)?.as_ref().map(|s| s.level),
+        }
+    }
+
+    // 已注册的 sink 及其级别
+    pub(super) fn levels(&self) -> impl Iterator<Item = (SinkId, LevelFilter)> + '_ {
+        let user =
+            self.slots.iter().enumerate().filter_map(|(index, slot)| {
+                slot.as_ref().map(|slot| (SinkId(index + 1), slot.level))
+            });
+        core::iter::once((SinkId::SYS_UART, self.sys_uart)).chain(user)
+    }
+
+    // 所有 sink 中最宽松的级别
+    pub(super) fn max_level(&self) -> LevelFilter {
+        self.slots
+            .iter()
+            .flatten()
+            .map(|s| s.level)
+            .fold(self.sys_uart, LevelFilter::max)
+    }
+
+    // 接受该级别的 sink 中是否有输出颜色的
+    pub(super) fn any_ansi(&self, level: Level) -> bool {
+        (self.sys_uart.accepts(level) && SysUartSink.ansi())
+            || self
+                .slots
+                .iter()
+                .flatten()
+                .any(|slot| slot.level.accepts(level) && slot.sink.ansi())
+    }
+
+    // 对每个接受该级别的 sink 调用 f
+    pub(super) fn for_each(&mut self, level: Level, mut f: impl FnMut(&mut dyn LogSink)) {
+        if self.sys_uart.accepts(level) {
+            f(&mut SysUartSink);
+        }
+        for slot in self.slots.iter_mut().flatten() {
+            if slot.level.accepts(level) {
+                f(&mut *slot.sink);
+            }
+        }
+    }
+
+    pub(super) fn flush(&mut self) {
+        for slot in self.slots.iter_mut().flatten() {
+            slot.sink.flush();
+        }
+    }
+}
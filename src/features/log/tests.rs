@@ -0,0 +1,176 @@
+//! 宿主机上的过滤规则测试，`cargo test --lib --features log` 运行
+//!
+//! 只用独立的 [`EcosLogger`] 实例，不经过全局的 `LOGGER` 和输出目标
+
+use super::{Directives, EcosLogger, FilterError, LevelFilter, MAX_DIRECTIVES, parse_filter};
+
+// 规则表的内容：(目标, 级别)
+fn rules(directives: &Directives) -> Vec<(&str, LevelFilter)> {
+    directives.iter().map(|d| (d.target(), d.level)).collect()
+}
+
+fn parse(spec: &str) -> (Option<LevelFilter>, Vec<(String, LevelFilter)>) {
+    let (default, directives) = parse_filter(spec).unwrap();
+    let rules = rules(&directives)
+        .into_iter()
+        .map(|(target, level)| (target.to_string(), level))
+        .collect();
+    (default, rules)
+}
+
+fn owned(rules: &[(&str, LevelFilter)]) -> Vec<(String, LevelFilter)> {
+    rules.iter().map(|&(t, l)| (t.to_string(), l)).collect()
+}
+
+#[test]
+fn bare_level() {
+    assert_eq!(parse("warn"), (Some(LevelFilter::Warn), vec![]));
+    assert_eq!(parse("TRACE"), (Some(LevelFilter::Trace), vec![]));
+    assert_eq!(parse("off"), (Some(LevelFilter::Off), vec![]));
+    // 后出现的默认级别覆盖前面的
+    assert_eq!(parse("info,debug"), (Some(LevelFilter::Debug), vec![]));
+    assert_eq!(parse(""), (None, vec![]));
+}
+
+#[test]
+fn target_with_level() {
+    assert_eq!(
+        parse("my_app::sensors=trace,ecos_ssc1::qspi=off"),
+        (
+            None,
+            owned(&[
+                ("my_app::sensors", LevelFilter::Trace),
+                ("ecos_ssc1::qspi", LevelFilter::Off),
+            ])
+        )
+    );
+    // 同一目标出现两次时后者生效
+    assert_eq!(
+        parse("a=info,a=error"),
+        (None, owned(&[("a", LevelFilter::Error)]))
+    );
+}
+
+#[test]
+fn target_without_level() {
+    assert_eq!(
+        parse("info,my_app::net"),
+        (
+            Some(LevelFilter::Info),
+            owned(&[("my_app::net", LevelFilter::Trace)])
+        )
+    );
+}
+
+#[test]
+fn trailing_comma_and_whitespace() {
+    assert_eq!(
+        parse("  warn , my_app = debug ,, other::mod=error ,"),
+        (
+            Some(LevelFilter::Warn),
+            owned(&[
+                ("my_app", LevelFilter::Debug),
+                ("other::mod", LevelFilter::Error)
+            ])
+        )
+    );
+    assert_eq!(parse(" , ,"), (None, vec![]));
+}
+
+#[test]
+fn invalid_specs() {
+    assert_eq!(
+        parse_filter("my_app=verbose").err(),
+        Some(FilterError::InvalidLevel)
+    );
+    assert_eq!(
+        parse_filter("info,my_app=").err(),
+        Some(FilterError::InvalidLevel)
+    );
+
+    let long = "x".repeat(super::MAX_TARGET_LEN + 1);
+    assert_eq!(
+        parse_filter(&format!("{}=info", long)).err(),
+        Some(FilterError::TargetTooLong)
+    );
+
+    let too_many = (0..=MAX_DIRECTIVES)
+        .map(|i| format!("t{}=info", i))
+        .collect::<Vec<_>>()
+        .join(",");
+    assert_eq!(
+        parse_filter(&too_many).err(),
+        Some(FilterError::TooManyDirectives)
+    );
+}
+
+#[test]
+fn longest_prefix_wins() {
+    let (_, directives) =
+        parse_filter("app=warn,app::net=debug,app::net::tcp=off,app::netx=error").unwrap();
+    assert_eq!(directives.level_for("app"), Some(LevelFilter::Warn));
+    assert_eq!(directives.level_for("app::io"), Some(LevelFilter::Warn));
+    assert_eq!(directives.level_for("app::net"), Some(LevelFilter::Debug));
+    assert_eq!(
+        directives.level_for("app::net::udp"),
+        Some(LevelFilter::Debug)
+    );
+    assert_eq!(
+        directives.level_for("app::net::tcp::rx"),
+        Some(LevelFilter::Off)
+    );
+    assert_eq!(directives.level_for("app::netx"), Some(LevelFilter::Error));
+    // 只按完整的模块路径匹配，不按字符串前缀
+    assert_eq!(directives.level_for("apples"), None);
+    assert_eq!(directives.level_for("other"), None);
+    assert_eq!(directives.max_level(), LevelFilter::Debug);
+}
+
+#[test]
+fn update_keeps_other_rules() {
+    let mut logger = EcosLogger::new();
+    logger.set_filter("info,a=debug,b=warn").unwrap();
+
+    logger.update_filter("b=error,c=trace,warn").unwrap();
+    assert_eq!(logger.max_level, LevelFilter::Warn);
+    assert_eq!(
+        rules(&logger.directives),
+        [
+            ("a", LevelFilter::Debug),
+            ("b", LevelFilter::Error),
+            ("c", LevelFilter::Trace),
+        ]
+    );
+    assert_eq!(logger.target_level("a::x"), LevelFilter::Debug);
+    assert_eq!(logger.target_level("z"), LevelFilter::Warn);
+    assert_eq!(logger.enabled_level, LevelFilter::Trace);
+}
+
+#[test]
+fn errors_leave_config_unchanged() {
+    let mut logger = EcosLogger::new();
+    logger.set_filter("debug,a=trace").unwrap();
+
+    // 错误出现在串的后部，前面已解析的部分也不生效
+    assert_eq!(
+        logger.update_filter("error,b=off,c=loud"),
+        Err(FilterError::InvalidLevel)
+    );
+    assert_eq!(
+        logger.set_filter("warn,b=off,c=loud"),
+        Err(FilterError::InvalidLevel)
+    );
+    let too_many = (0..MAX_DIRECTIVES)
+        .map(|i| format!("t{}=info", i))
+        .collect::<Vec<_>>()
+        .join(",");
+    assert_eq!(
+        logger.update_filter(&too_many),
+        Err(FilterError::TooManyDirectives)
+    );
+
+    assert_eq!(logger.max_level, LevelFilter::Debug);
+    assert_eq!(rules(&logger.directives), [("a", LevelFilter::Trace)]);
+    assert_eq!(logger.target_level("b"), LevelFilter::Debug);
+    assert_eq!(logger.enabled_level, LevelFilter::Trace);
+}
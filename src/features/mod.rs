@@ -3,3 +3,6 @@ pub mod alloc;
 
 #[cfg(feature = "log")]
 pub mod log;
+
+#[cfg(feature = "pool")]
+pub mod pool;
@@ -0,0 +1,252 @@
+//! # POOL
+//!
+//! 固定大小的对象池：`N` 个 `T` 大小的槽位，全部在编译期确定，不依赖 `alloc`。
+//! 适合驱动里需要确定性内存的场景——池放在静态内存中，分配只会在 `N` 个槽位都被占用时失败，
+//! 只要按最大并发数确定 `N`，启动后的分配就不会失败。分配/释放只是原子地占用/归还一个槽位，
+//! 不涉及全局堆，在中断上下文中也可以使用
+//!
+//! ## 特性
+//! - `pool`: 启用对象池
+//!
+//! ## 使用示例
+//! ```
+//! use ecos_ssc1::features::pool::{Pool, PoolBox};
+//!
+//! struct Packet {
+//!     len: usize,
+//!     data: [u8; 64],
+//! }
+//!
+//! static PACKETS: Pool<Packet, 8> = Pool::new();
+//!
+//! let mut packet: PoolBox<Packet> = PACKETS.alloc(Packet { len: 0, data: [0; 64] }).ok().unwrap();
+//! packet.data[0] = 0x55;
+//! packet.len = 1;
+//! assert_eq!(PACKETS.available(), 7);
+//! drop(packet); // 槽位归还给池
+//! assert_eq!(PACKETS.available(), 8);
+//! ```
+
+use core::cell::UnsafeCell;
+use core::fmt;
+use core::mem::MaybeUninit;
+use core::ops::{Deref, DerefMut};
+use core::ptr::NonNull;
+use core::sync::atomic::{AtomicBool, Ordering};
+
+/// 容纳 `N` 个 `T` 的对象池
+///
+/// 可以用 `const fn new()` 直接定义为 `static`
+pub struct Pool<T, const N: usize> {
+    used: [AtomicBool; N],
+    slots: [UnsafeCell<MaybeUninit<T>>; N],
+}
+
+// SAFETY: 每个槽位通过 used 标志原子地独占，同一时刻只有一个 PoolBox 能访问它
+unsafe impl<T: Send, const N: usize> Sync for Pool<T, N> {}
+
+impl<T, const N: usize> Pool<T, N> {
+    /// 创建一个空的对象池
+    pub const fn new() -> Self {
+        Self {
+            used: [const { AtomicBool::new(false) }; N],
+            slots: [const { UnsafeCell::new(MaybeUninit::uninit()) }; N],
+        }
+    }
+
+    /// 槽位总数
+    pub const fn capacity(&self) -> usize {
+        N
+    }
+
+    /// 当前空闲的槽位数
+    pub fn available(&self) -> usize {
+        self.used
+            .iter()
+            .filter(|used| !used.load(Ordering::Relaxed))
+            .count()
+    }
+
+    /// 当前被占用的槽位数
+    pub fn in_use(&self) -> usize {
+        N - self.available()
+    }
+
+    /// 占用一个槽位存放 `value`
+    ///
+    /// 所有槽位都被占用时把 `value` 原样返回
+    pub fn alloc(&'static self, value: T) -> Result<PoolBox<T>, T> {
+        for (used, slot) in self.used.iter().zip(self.slots.iter()) {
+            if used
+                .compare_exchange(false, true, Ordering::Acquire, Ordering::Relaxed)
+                .is_ok()
+            {
+                // SAFETY: 刚刚独占了这个槽位
+                let ptr = unsafe { (*slot.get()).as_mut_ptr() };
+                // SAFETY: 槽位未初始化，直接写入
+                unsafe { ptr.write(value) };
+                return Ok(PoolBox {
+                    // SAFETY: 槽位地址不会为 null
+                    ptr: unsafe { NonNull::new_unchecked(ptr) },
+                    used,
+                });
+            }
+        }
+        Err(value)
+    }
+
+    /// 占用一个槽位存放 `value`，池已满时 panic
+    ///
+    /// 用于按最大并发数确定了 `N`、不应该失败的场景
+    pub fn alloc_or_panic(&'static self, value: T) -> PoolBox<T> {
+        match self.alloc(value) {
+            Ok(boxed) => boxed,
+            Err(_) => panic!("Pool exhausted ({} slots)", N),
+        }
+    }
+}
+
+impl<T, const N: usize> Default for Pool<T, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// 指向对象池中一个槽位的智能指针，`Drop` 时析构对象并归还槽位
+pub struct PoolBox<T: 'static> {
+    ptr: NonNull<T>,
+    used: &'static AtomicBool,
+}
+
+// SAFETY: PoolBox 独占所指向的对象，与 Box<T> 一致
+unsafe impl<T: Send> Send for PoolBox<T> {}
+// SAFETY: 同上
+unsafe impl<T: Sync> Sync for PoolBox<T> {}
+
+impl<T> PoolBox<T> {
+    /// 取出对象并归还槽位
+    pub fn into_inner(boxed: Self) -> T {
+        let boxed = core::mem::ManuallyDrop::new(boxed);
+        // SAFETY: 对象已初始化，读出后槽位不再使用
+        let value = unsafe { boxed.ptr.as_ptr().read() };
+        boxed.used.store(false, Ordering::Release);
+        value
+    }
+}
+
+impl<T> Deref for PoolBox<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        // SAFETY: 对象已初始化且由本 PoolBox 独占
+        unsafe { self.ptr.as_ref() }
+    }
+}
+
+impl<T> DerefMut for PoolBox<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        // SAFETY: 同上
+        unsafe { self.ptr.as_mut() }
+    }
+}
+
+impl<T> Drop for PoolBox<T> {
+    fn drop(&mut self) {
+        // SAFETY: 对象已初始化，析构后不再访问
+        unsafe { self.ptr.as_ptr().drop_in_place() };
+        self.used.store(false, Ordering::Release);
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for PoolBox<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+impl<T: fmt::Display> fmt::Display for PoolBox<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&**self, f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::sync::atomic::AtomicUsize;
+
+    // 每个测试使用自己的静态池，测试可以并行运行
+
+    #[test]
+    fn full_pool_returns_value() {
+        static POOL: Pool<u32, 2> = Pool::new();
+
+        let a = POOL.alloc(1).unwrap();
+        let b = POOL.alloc(2).unwrap();
+        assert_eq!(POOL.available(), 0);
+        assert_eq!(POOL.alloc(3).err(), Some(3));
+        assert_eq!((*a, *b), (1, 2));
+    }
+
+    #[test]
+    fn slot_reused_after_drop() {
+        static POOL: Pool<u32, 2> = Pool::new();
+
+        let a = POOL.alloc(1).unwrap();
+        let b = POOL.alloc(2).unwrap();
+        let addr = &*a as *const u32;
+        drop(a);
+        assert_eq!(POOL.in_use(), 1);
+
+        let c = POOL.alloc(3).unwrap();
+        assert_eq!(&*c as *const u32, addr);
+        assert_eq!((*b, *c), (2, 3));
+        assert!(POOL.alloc(4).is_err());
+    }
+
+    #[test]
+    fn into_inner_frees_slot() {
+        static POOL: Pool<u32, 1> = Pool::new();
+
+        let a = POOL.alloc(7).unwrap();
+        assert_eq!(POOL.available(), 0);
+        assert_eq!(PoolBox::into_inner(a), 7);
+        assert_eq!(POOL.available(), 1);
+
+        let b = POOL.alloc(8).unwrap();
+        assert_eq!(*b, 8);
+    }
+
+    #[test]
+    fn destructor_runs_once() {
+        static DROPS: AtomicUsize = AtomicUsize::new(0);
+
+        struct Counted;
+
+        impl Drop for Counted {
+            fn drop(&mut self) {
+                DROPS.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+
+        static POOL: Pool<Counted, 1> = Pool::new();
+
+        // Drop 析构一次
+        drop(POOL.alloc(Counted).ok().unwrap());
+        assert_eq!(DROPS.load(Ordering::Relaxed), 1);
+
+        // into_inner 不析构，交出的值离开作用域时析构一次
+        let value = PoolBox::into_inner(POOL.alloc(Counted).ok().unwrap());
+        assert_eq!(DROPS.load(Ordering::Relaxed), 1);
+        drop(value);
+        assert_eq!(DROPS.load(Ordering::Relaxed), 2);
+
+        // 池满时原样返回的值只析构一次
+        let held = POOL.alloc(Counted).ok().unwrap();
+        let rejected = POOL.alloc(Counted).err().unwrap();
+        assert_eq!(DROPS.load(Ordering::Relaxed), 2);
+        drop(rejected);
+        drop(held);
+        assert_eq!(DROPS.load(Ordering::Relaxed), 4);
+    }
+}
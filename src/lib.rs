@@ -1,14 +1,19 @@
-#![no_std]
+#![cfg_attr(not(test), no_std)]
 #![doc = include_str!("../README.md")]
 
+// 外设驱动只在目标板上编译，宿主机上（`cargo test`）只保留分配器等与硬件无关的部分；
+// qspi 在宿主机上只有线宽和比特流的纯计算部分
+#[cfg(target_arch = "riscv32")]
 pub mod gpio;
 pub mod qspi;
+#[cfg(target_arch = "riscv32")]
 pub mod timer;
+#[cfg(target_arch = "riscv32")]
 pub mod uart;
 
 pub mod features;
 
-#[cfg(feature = "panic")]
+#[cfg(all(feature = "panic", target_arch = "riscv32"))]
 pub mod panic;
 
 #[cfg(feature = "alloc")]
@@ -30,12 +35,16 @@ pub use crate::__log as log;
 
 pub use macros::{ecos_main, rust_main};
 
+#[cfg(target_arch = "riscv32")]
 pub use self::qspi::{
-    Qspi, QspiConfig, QspiError, get_qspi, init_qspi, write_bytes, write_u8, write_u16, write_u32,
-    write_words,
+    Qspi, QspiCommand, QspiConfig, QspiData, QspiError, QspiMode, QspiWidth, get_qspi, init_qspi,
+    is_dma_busy, on_qspi_interrupt, start_write_dma, transfer, write_bytes, write_dma, write_u8,
+    write_u16, write_u32, write_words,
 };
+#[cfg(target_arch = "riscv32")]
 pub use crate::{gpio::Gpio, gpio::GpioPin, timer::Timer, uart::Uart};
 
+#[cfg(target_arch = "riscv32")]
 #[macro_export]
 macro_rules! print {
     ($($arg:tt)*) => {{
@@ -44,12 +53,23 @@ macro_rules! print {
     }};
 }
 
+// 宿主机上没有串口，输出到标准输出
+#[cfg(not(target_arch = "riscv32"))]
+#[macro_export]
+macro_rules! print {
+    ($($arg:tt)*) => {{
+        extern crate std;
+        std::print!($($arg)*);
+    }};
+}
+
 #[macro_export]
 macro_rules! println {
     () => ($crate::print!("\n"));
     ($($arg:tt)*) => ($crate::print!("{}\n", format_args!($($arg)*)));
 }
 
+#[cfg(target_arch = "riscv32")]
 #[allow(nonstandard_style)]
 pub mod bindings {
     include!(concat!(env!("OUT_DIR"), "/bindings.rs"));
@@ -166,10 +186,12 @@ pub mod bindings {
     pub const I2C_STATUS_IF: u32 = 0x01; // (1 << 0)
 }
 
+#[cfg(target_arch = "riscv32")]
 unsafe extern "C" {
     fn start();
 }
 
+#[cfg(target_arch = "riscv32")]
 #[unsafe(no_mangle)]
 #[used]
 pub static _start: unsafe extern "C" fn() = start;
@@ -18,9 +18,18 @@ pub(super) fn log_panic(info: &PanicInfo) {
 }
 
 pub(super) fn panic(info: &PanicInfo) -> ! {
+    #[cfg(feature = "log")]
+    crate::features::log::on_panic();
+
+    // 先发出 panic 之前积压的延迟日志，保持时间顺序
+    #[cfg(feature = "log-deferred")]
+    crate::features::log::deferred::flush();
+
     {
         loop {
             log_panic(info);
+            #[cfg(feature = "log")]
+            crate::features::log::flush();
 
             #[cfg(not(feature = "dev"))]
             break;
@@ -0,0 +1,859 @@
+#![allow(static_mut_refs)]
+
+use core::marker::PhantomData;
+use tock_registers::interfaces::{Readable, Writeable};
+use tock_registers::{
+    register_bitfields, register_structs,
+    registers::{ReadOnly, ReadWrite, WriteOnly},
+};
+
+use super::mode::HeaderBits;
+use super::{QspiError, QspiMode, QspiWidth};
+
+// ========== 寄存器位域定义 ==========
+register_bitfields![u32,
+    /// STATUS 寄存器
+    pub Status [
+        BUSY        OFFSET(0)  NUMBITS(1) [],
+        DONE        OFFSET(1)  NUMBITS(15) [],
+        RESET       OFFSET(16) NUMBITS(1) [],
+        INT_FLAG    OFFSET(31) NUMBITS(1) [],
+    ],
+
+    /// LEN 寄存器 - C代码写入的是 (比特数 << 16)
+    pub Len [
+        LENGTH      OFFSET(16) NUMBITS(16) [],
+        CTRL        OFFSET(0)  NUMBITS(16) [],
+    ],
+
+    /// CMD 寄存器
+    pub Cmd [
+        MODE        OFFSET(0)  NUMBITS(2) [
+            Standard = 0,
+            Dual = 1,
+            Quad = 2,
+        ],
+        TYPE        OFFSET(2)  NUMBITS(2) [
+            WriteOnly = 0,
+            ReadOnly = 1,
+            WriteRead = 2,
+        ],
+        ADDR_BYTES  OFFSET(4)  NUMBITS(2) [
+            Addr0Byte = 0,
+            Addr1Byte = 1,
+            Addr2Byte = 2,
+            Addr3Byte = 3,
+        ],
+        DATA_BYTES  OFFSET(6)  NUMBITS(2) [
+            Data0Byte = 0,
+            Data1Byte = 1,
+            Data2Byte = 2,
+            Data4Byte = 3,
+        ],
+        DMA_EN      OFFSET(8)  NUMBITS(1) [],
+        START       OFFSET(31) NUMBITS(1) [],
+    ],
+
+    /// INTCFG 寄存器
+    pub IntCfg [
+        TX_COMPLETE OFFSET(0)  NUMBITS(1) [],
+        RX_COMPLETE OFFSET(1)  NUMBITS(1) [],
+        TX_THRESH   OFFSET(2)  NUMBITS(1) [],
+        RX_THRESH   OFFSET(3)  NUMBITS(1) [],
+        TX_THRESH_VAL OFFSET(8)  NUMBITS(4) [],
+        RX_THRESH_VAL OFFSET(12) NUMBITS(4) [],
+    ],
+
+    /// INTSTA 寄存器
+    pub IntSta [
+        TX_COMPLETE OFFSET(0)  NUMBITS(1) [],
+        RX_COMPLETE OFFSET(1)  NUMBITS(1) [],
+        TX_THRESH   OFFSET(2)  NUMBITS(1) [],
+        RX_THRESH   OFFSET(3)  NUMBITS(1) [],
+    ],
+];
+
+// ========== 寄存器结构体定义 ==========
+register_structs! {
+    #[allow(non_snake_case)]
+    pub QspiRegisters {
+        (0x00 => status: ReadWrite<u32, Status::Register>),
+        (0x04 => clkdiv: ReadWrite<u32>),
+        (0x08 => cmd: ReadWrite<u32, Cmd::Register>),
+        (0x0C => adr: ReadWrite<u32>),
+        (0x10 => len: ReadWrite<u32, Len::Register>),
+        (0x14 => dum: ReadWrite<u32>),
+        (0x18 => txfifo: WriteOnly<u32>),
+        (0x1C => _reserved1: [u8; 4]),
+        (0x20 => rxfifo: ReadOnly<u32>),
+        (0x24 => intcfg: ReadWrite<u32, IntCfg::Register>),
+        (0x28 => intsta: ReadWrite<u32, IntSta::Register>),
+        (0x2C => @END),
+    }
+}
+
+// ========== QSPI基地址 ==========
+const QSPI0_BASE: usize = crate::bindings::REG_QSPI_0_BASE as usize;
+
+/// 获取QSPI0寄存器实例
+#[inline(always)]
+fn qspi0() -> &'static mut QspiRegisters {
+    unsafe { &mut *(QSPI0_BASE as *mut QspiRegisters) }
+}
+
+// ========== QSPI配置 ==========
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct qspi_config_t {
+    pub clkdiv: u32,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct QspiConfig {
+    pub clkdiv: u32,
+}
+
+impl From<crate::bindings::qspi_config_t> for QspiConfig {
+    fn from(c_config: crate::bindings::qspi_config_t) -> Self {
+        Self {
+            clkdiv: c_config.clkdiv,
+        }
+    }
+}
+
+impl Default for QspiConfig {
+    fn default() -> Self {
+        Self { clkdiv: 0 }
+    }
+}
+
+// ========== 传输描述 ==========
+
+/// 数据阶段
+#[derive(Debug, Default)]
+pub enum QspiData<'a> {
+    /// 没有数据阶段
+    #[default]
+    None,
+    /// 读入到缓冲区
+    Read(&'a mut [u8]),
+    /// 写出缓冲区的内容，连同展开的命令/地址不能超过 TX FIFO 的容量
+    Write(&'a [u8]),
+}
+
+/// 一次完整的 QSPI 传输：命令 → 地址 → 空周期 → 数据，不需要的阶段留空即可
+///
+/// ```
+/// // 读 SPI Flash 的 JEDEC ID
+/// let mut id = [0u8; 3];
+/// qspi.transfer(&mut QspiCommand {
+///     opcode: Some(0x9f),
+///     data: QspiData::Read(&mut id),
+///     ..Default::default()
+/// })?;
+///
+/// // 快速读（0x0b）：3 字节地址，8 个空周期
+/// let mut buf = [0u8; 256];
+/// qspi.transfer(&mut QspiCommand {
+///     opcode: Some(0x0b),
+///     address: 0x1000,
+///     addr_bytes: 3,
+///     dummy_cycles: 8,
+///     data: QspiData::Read(&mut buf),
+///     ..Default::default()
+/// })?;
+///
+/// // 四线输出快速读（0x6b，1-1-4）
+/// qspi.transfer(&mut QspiCommand {
+///     opcode: Some(0x6b),
+///     address: 0x1000,
+///     addr_bytes: 3,
+///     dummy_cycles: 8,
+///     mode: QspiMode::QUAD_OUTPUT,
+///     data: QspiData::Read(&mut buf),
+/// })?;
+/// ```
+#[derive(Debug, Default)]
+pub struct QspiCommand<'a> {
+    /// 命令字节，`None` 时没有命令阶段
+    pub opcode: Option<u8>,
+    /// 地址，只发出低 `addr_bytes` 个字节
+    pub address: u32,
+    /// 地址字节数，0 ~ 3，0 表示没有地址阶段
+    pub addr_bytes: u8,
+    /// 地址之后、数据之前的空周期数
+    pub dummy_cycles: u16,
+    /// 各阶段的线宽，默认 1-1-1
+    pub mode: QspiMode,
+    /// 数据阶段
+    pub data: QspiData<'a>,
+}
+
+// 除数据以外的各阶段，拆分传输时每次都重新发出
+#[derive(Clone, Copy)]
+struct Header {
+    opcode: Option<u8>,
+    addr_bytes: u8,
+    dummy_cycles: u16,
+    mode: QspiMode,
+}
+
+/// 收发 FIFO 的深度（32 位字），与 `write_words` 最大的分块相同
+pub const FIFO_WORDS: usize = 32;
+
+/// 一次传输最多读入的字节数，读入的数据在传输结束后才从 RX FIFO 取出
+pub const MAX_READ_BYTES: usize = FIFO_WORDS * 4;
+
+/// DMA 每次突发的字数，DMA 写入的每一块都是它的整数倍，剩余部分走 FIFO
+pub const DMA_BURST_WORDS: usize = 8;
+
+/// 一次 DMA 传输最多的字数：LEN 中 16 位的比特数以内、突发长度的整数倍
+pub const DMA_MAX_WORDS: usize = 0xFFFF / 32 / DMA_BURST_WORDS * DMA_BURST_WORDS;
+
+// 下一块 DMA 的字数，不足一个突发时为 0
+fn dma_chunk_len(remaining: usize) -> usize {
+    remaining.min(DMA_MAX_WORDS) / DMA_BURST_WORDS * DMA_BURST_WORDS
+}
+
+// 启动传输时写入 STATUS 的值：片选 0（bit 8）| 写（bit 1），即 C 代码中的 258
+const STATUS_START_WRITE: u32 = 0x102;
+// 片选 0 | 读（bit 0）
+const STATUS_START_READ: u32 = 0x101;
+
+// 最多 4 个字节拼成一个 FIFO 字，高位在前，不足 4 字节时靠高位对齐（同 write_bytes）
+fn pack_word(bytes: &[u8]) -> u32 {
+    bytes
+        .iter()
+        .enumerate()
+        .fold(0, |word, (i, &b)| word | (b as u32) << (24 - i * 8))
+}
+
+// pack_word 的逆过程
+fn unpack_word(word: u32, out: &mut [u8]) {
+    for (i, b) in out.iter_mut().enumerate() {
+        *b = (word >> (24 - i * 8)) as u8;
+    }
+}
+
+// ========== 主QSPI驱动结构 ==========
+pub struct Qspi {
+    regs: &'static mut QspiRegisters,
+    /// 尚未开始的 DMA 数据
+    dma_pending: &'static [u32],
+    /// 是否有 DMA 传输在进行
+    dma_active: bool,
+    _private: PhantomData<*mut ()>,
+}
+
+impl Qspi {
+    /// 创建新的QSPI实例
+    pub fn new() -> Self {
+        let regs = qspi0();
+        Self {
+            regs,
+            dma_pending: &[],
+            dma_active: false,
+            _private: PhantomData,
+        }
+    }
+
+    /// 初始化QSPI控制器 - 完全按照C代码逻辑
+    pub fn init(&mut self, clkdiv: u32) {
+        // 完全按照C代码的顺序和值
+        self.regs.status.set(0b10000); // STATUS = 0b10000
+        self.regs.status.set(0); // STATUS = 0
+        self.regs.intcfg.set(0); // INTCFG = 0
+        self.regs.dum.set(0); // DUM = 0
+        self.regs.clkdiv.set(clkdiv); // CLKDIV = clkdiv
+    }
+
+    /// 等待传输完成 - 严格按照C代码逻辑
+    pub fn wait_transfer_complete(&self) -> Result<(), QspiError> {
+        let mut timeout = 100_000;
+
+        // C代码: while ((REG_QSPI_0_STATUS & 0xFFFF) != 1)
+        while timeout > 0 {
+            if (self.regs.status.get() & 0xFFFF) == 1 {
+                return Ok(());
+            }
+            timeout -= 1;
+        }
+
+        Err(QspiError::Timeout)
+    }
+
+    /// 等待传输完成 - 对于write_16/32等函数
+    pub fn wait_transfer_complete_full(&self) -> Result<(), QspiError> {
+        let mut timeout = 100_000;
+
+        // C代码: while ((REG_QSPI_0_STATUS & 0xFFFFFFFF) != 1)
+        while timeout > 0 {
+            if (self.regs.status.get() & 0xFFFFFFFF) == 1 {
+                return Ok(());
+            }
+            timeout -= 1;
+        }
+
+        Err(QspiError::Timeout)
+    }
+
+    // ========== 写入函数 - 完全按照C代码逻辑 ==========
+
+    /// 写入8位数据 - 与C代码完全一致
+    pub fn write_u8(&mut self, data: u8) -> Result<(), QspiError> {
+        let wdat = (data as u32) << 24; // C代码: ((uint32_t)data) << 24
+        self.regs.len.set(0x80000); // C代码: REG_QSPI_0_LEN = 0x80000
+        self.regs.txfifo.set(wdat); // C代码: REG_QSPI_0_TXFIFO = wdat
+        self.regs.status.set(258); // C代码: REG_QSPI_0_STATUS = 258
+        self.wait_transfer_complete() // C代码的while循环
+    }
+
+    /// 写入16位数据 - 与C代码完全一致
+    pub fn write_u16(&mut self, data: u16) -> Result<(), QspiError> {
+        let wdat = (data as u32) << 16; // C代码: ((uint32_t)data) << 16
+        self.regs.len.set(0x100000); // C代码: REG_QSPI_0_LEN = 0x100000
+        self.regs.txfifo.set(wdat);
+        self.regs.status.set(258);
+        self.wait_transfer_complete_full()
+    }
+
+    /// 写入32位数据 - 与C代码完全一致
+    pub fn write_u32(&mut self, data: u32) -> Result<(), QspiError> {
+        self.regs.len.set(0x200000); // C代码: REG_QSPI_0_LEN = 0x200000
+        self.regs.txfifo.set(data);
+        self.regs.status.set(258);
+        self.wait_transfer_complete_full()
+    }
+
+    /// 写入2个32位数据 - 与C代码完全一致
+    pub fn write_u32x2(&mut self, data1: u32, data2: u32) -> Result<(), QspiError> {
+        self.regs.len.set(0x400000); // C代码: REG_QSPI_0_LEN = 0x400000
+        self.regs.txfifo.set(data1);
+        self.regs.txfifo.set(data2);
+        self.regs.status.set(258);
+        self.wait_transfer_complete_full()
+    }
+
+    /// 写入8个32位数据 - 与C代码完全一致
+    pub fn write_u32x8(&mut self, data: [u32; 8]) -> Result<(), QspiError> {
+        self.regs.len.set(0x1000000); // C代码: REG_QSPI_0_LEN = 0x1000000
+        for &d in &data {
+            self.regs.txfifo.set(d);
+        }
+        self.regs.status.set(258);
+        self.wait_transfer_complete_full()
+    }
+
+    /// 写入16个32位数据 - 与C代码完全一致
+    pub fn write_u32x16(&mut self, data: [u32; 16]) -> Result<(), QspiError> {
+        self.regs.len.set(0x2000000); // C代码: REG_QSPI_0_LEN = 0x2000000
+        for &d in &data {
+            self.regs.txfifo.set(d);
+        }
+        self.regs.status.set(258);
+        self.wait_transfer_complete_full()
+    }
+
+    /// 写入32个32位数据 - 与C代码完全一致
+    pub fn write_u32x32(&mut self, data: [u32; 32]) -> Result<(), QspiError> {
+        self.regs.len.set(0x4000000); // C代码: REG_QSPI_0_LEN = 0x4000000
+        for &d in &data {
+            self.regs.txfifo.set(d);
+        }
+        self.regs.status.set(258);
+        self.wait_transfer_complete_full()
+    }
+
+    /// 通用的字节写入函数
+    pub fn write_bytes(&mut self, data: &[u8]) -> Result<(), QspiError> {
+        if data.is_empty() {
+            return Ok(());
+        }
+
+        // 按照C代码逻辑：比特数 = 字节数 × 8
+        let bits = data.len() * 8;
+        let len_value = (bits as u32) << 16;
+
+        self.regs.len.set(len_value);
+
+        // 按C代码的逻辑组织数据
+        let mut i = 0;
+        while i < data.len() {
+            let mut word: u32 = 0;
+            for j in 0..4 {
+                if i + j < data.len() {
+                    let shift = 24 - (j * 8);
+                    word |= (data[i + j] as u32) << shift;
+                }
+            }
+            self.regs.txfifo.set(word);
+            i += 4;
+        }
+
+        self.regs.status.set(258);
+        self.wait_transfer_complete_full()
+    }
+
+    /// 按照u32写入数据直到全部完成
+    pub fn write_words(&mut self, data: &[u32]) -> Result<(), QspiError> {
+        if data.is_empty() {
+            return Ok(());
+        }
+
+        let mut index = 0;
+        let total = data.len();
+
+        loop {
+            let remaining = total - index;
+            if remaining == 0 {
+                break;
+            }
+
+            match remaining {
+                r if r >= 32 => {
+                    let chunk: [u32; 32] = data[index..index + 32]
+                        .try_into()
+                        .map_err(|_| QspiError::InvalidParameter)?;
+                    self.write_u32x32(chunk)?;
+                    index += 32;
+                }
+                r if r >= 16 => {
+                    let chunk: [u32; 16] = data[index..index + 16]
+                        .try_into()
+                        .map_err(|_| QspiError::InvalidParameter)?;
+                    self.write_u32x16(chunk)?;
+                    index += 16;
+                }
+                r if r >= 8 => {
+                    let chunk: [u32; 8] = data[index..index + 8]
+                        .try_into()
+                        .map_err(|_| QspiError::InvalidParameter)?;
+                    self.write_u32x8(chunk)?;
+                    index += 8;
+                }
+                r if r >= 2 => {
+                    self.write_u32x2(data[index], data[index + 1])?;
+                    index += 2;
+                }
+                1 => {
+                    self.write_u32(data[index])?;
+                    index += 1;
+                }
+                _ => unreachable!(),
+            }
+        }
+
+        Ok(())
+    }
+
+    /// 从接收FIFO读取32位数据
+    pub fn read_u32(&self) -> u32 {
+        self.regs.rxfifo.get()
+    }
+
+    // ========== 通用传输 ==========
+
+    /// 执行一次命令/地址/空周期/数据传输，见 [`QspiCommand`]
+    ///
+    /// 读入超过 [`MAX_READ_BYTES`] 字节时按地址拆成多次传输（每次重新发出命令和地址），
+    /// 所以只适用于带地址、地址自动递增的器件（Flash、PSRAM 等）；没有地址时返回
+    /// [`QspiError::InvalidParameter`]
+    ///
+    /// 写出的数据在启动前全部放入 TX FIFO，和展开后的命令/地址一起最多 [`FIFO_WORDS`]
+    /// 个字（没有展开时即 `FIFO_WORDS * 4` 字节），超出时返回 [`QspiError::InvalidParameter`]
+    pub fn transfer(&mut self, command: &mut QspiCommand) -> Result<(), QspiError> {
+        if self.dma_active {
+            return Err(QspiError::Busy);
+        }
+        let QspiCommand {
+            opcode,
+            address,
+            addr_bytes,
+            dummy_cycles,
+            mode,
+            ref mut data,
+        } = *command;
+        if addr_bytes > 3 {
+            return Err(QspiError::InvalidParameter);
+        }
+        let header = Header {
+            opcode,
+            addr_bytes,
+            dummy_cycles,
+            mode,
+        };
+
+        let result = match data {
+            QspiData::Read(buf) if buf.len() > MAX_READ_BYTES => {
+                if addr_bytes == 0 {
+                    Err(QspiError::InvalidParameter)
+                } else {
+                    let mask = (1u32 << (addr_bytes * 8)) - 1;
+                    buf.chunks_mut(MAX_READ_BYTES)
+                        .enumerate()
+                        .try_for_each(|(i, chunk)| {
+                            let offset = (i * MAX_READ_BYTES) as u32;
+                            let address = address.wrapping_add(offset) & mask;
+                            self.transfer_once(&header, address, QspiData::Read(chunk))
+                        })
+                }
+            }
+            QspiData::Read(buf) => self.transfer_once(&header, address, QspiData::Read(buf)),
+            QspiData::Write(buf) => self.transfer_once(&header, address, QspiData::Write(buf)),
+            QspiData::None => self.transfer_once(&header, address, QspiData::None),
+        };
+
+        // 恢复默认配置，不影响 write_u8 等按 C 代码直接启动的传输
+        self.regs.cmd.set(0);
+        self.regs.dum.set(0);
+        result
+    }
+
+    // 一次传输：命令字节（以及需要展开时的地址）经 TX FIFO 发出，LEN.CTRL 为其比特数；
+    // 地址和空周期由 ADR / DUM 寄存器给出，数据经 TX / RX FIFO，每个字高位在前
+    fn transfer_once(
+        &mut self,
+        header: &Header,
+        address: u32,
+        data: QspiData,
+    ) -> Result<(), QspiError> {
+        let data_bits = match &data {
+            QspiData::None => 0,
+            QspiData::Read(buf) => buf.len() * 8,
+            QspiData::Write(buf) => buf.len() * 8,
+        };
+        if data_bits > 0xFFFF {
+            return Err(QspiError::InvalidParameter);
+        }
+        let width = header.mode.bus_width(
+            header.opcode.is_some(),
+            header.addr_bytes > 0,
+            data_bits > 0,
+        )?;
+
+        // 与 MODE 不同宽的（单线）阶段展开后放进 TX FIFO，地址也随之不再走 ADR
+        let mut stream = HeaderBits::new();
+        if let Some(opcode) = header.opcode {
+            stream.push_byte(opcode, header.mode.cmd, width);
+        }
+        let mut addr_bytes = header.addr_bytes;
+        if addr_bytes > 0 && header.mode.addr != width {
+            for i in (0..addr_bytes).rev() {
+                stream.push_byte((address >> (i * 8)) as u8, header.mode.addr, width);
+            }
+            addr_bytes = 0;
+        }
+        // 数据要在启动前全部放入 TX FIFO，放不下的部分会丢失
+        if let QspiData::Write(buf) = &data
+            && buf.len() > (FIFO_WORDS - stream.words().len()) * 4
+        {
+            return Err(QspiError::InvalidParameter);
+        }
+
+        let kind = match &data {
+            QspiData::Read(_)
+                if stream.bits == 0 && addr_bytes == 0 && header.dummy_cycles == 0 =>
+            {
+                Cmd::TYPE::ReadOnly
+            }
+            QspiData::Read(_) => Cmd::TYPE::WriteRead,
+            _ => Cmd::TYPE::WriteOnly,
+        };
+        let mode = match width {
+            QspiWidth::Standard => Cmd::MODE::Standard,
+            QspiWidth::Dual => Cmd::MODE::Dual,
+            QspiWidth::Quad => Cmd::MODE::Quad,
+        };
+
+        self.regs.cmd.write(
+            mode + kind + Cmd::ADDR_BYTES.val(addr_bytes as u32) + Cmd::DATA_BYTES::Data4Byte,
+        );
+        self.regs.adr.set(address);
+        self.regs.dum.set(header.dummy_cycles as u32);
+        self.regs
+            .len
+            .write(Len::LENGTH.val(data_bits as u32) + Len::CTRL.val(stream.bits));
+
+        for &word in stream.words() {
+            self.regs.txfifo.set(word);
+        }
+        if let QspiData::Write(buf) = &data {
+            for chunk in buf.chunks(4) {
+                self.regs.txfifo.set(pack_word(chunk));
+            }
+        }
+
+        let start = match &data {
+            QspiData::Read(_) => STATUS_START_READ,
+            _ => STATUS_START_WRITE,
+        };
+        self.regs.status.set(start);
+        self.wait_transfer_complete_full()?;
+
+        if let QspiData::Read(buf) = data {
+            for chunk in buf.chunks_mut(4) {
+                unpack_word(self.regs.rxfifo.get(), chunk);
+            }
+        }
+        Ok(())
+    }
+
+    // ========== DMA 写入 ==========
+
+    /// 用 DMA 写出 `data`，等待全部完成后返回
+    ///
+    /// 数据按 [`DMA_MAX_WORDS`] 分块，每块由控制器直接从内存读取，CPU 不再逐字写 TX FIFO；
+    /// 末尾不足 [`DMA_BURST_WORDS`] 的部分走 FIFO。不想忙等时用 [`Qspi::start_write_dma`]
+    ///
+    /// 每块的等待上限按该块的比特数和当前的时钟分频计算。超时返回 [`QspiError::Timeout`]，
+    /// 但不复位控制器（DMA 可能仍在读内存），传输保持进行中的状态，可以继续调用
+    /// [`Qspi::poll_dma`] 等它结束
+    pub fn write_dma(&mut self, data: &'static [u32]) -> Result<(), QspiError> {
+        self.start_write_dma(data, false)?;
+        let mut pending = usize::MAX;
+        let mut timeout = 0;
+        loop {
+            // 开始了新的一块，重新计算等待上限
+            if self.dma_pending.len() != pending {
+                pending = self.dma_pending.len();
+                timeout = self.dma_chunk_timeout();
+            }
+            if self.poll_dma()? {
+                return Ok(());
+            }
+            if timeout == 0 {
+                return Err(QspiError::Timeout);
+            }
+            timeout -= 1;
+        }
+    }
+
+    /// 开始 DMA 写入，立即返回
+    ///
+    /// 之后在主循环中调用 [`Qspi::poll_dma`]，由它启动下一块并在最后补发 FIFO 部分。
+    /// `interrupt` 为 true 时每块完成会触发 QSPI 中断（TX_COMPLETE），用于全局实例时
+    /// 应改用 [`start_write_dma`] 并在中断处理函数中调用 [`on_qspi_interrupt`]。
+    /// 完成前 [`Qspi::transfer`] 和新的 DMA 写入返回 [`QspiError::Busy`]；`write_u8` 等
+    /// 直接启动的传输不做检查，需要调用者自己避开
+    pub fn start_write_dma(
+        &mut self,
+        data: &'static [u32],
+        interrupt: bool,
+    ) -> Result<(), QspiError> {
+        if self.dma_active {
+            return Err(QspiError::Busy);
+        }
+        if data.len() < DMA_BURST_WORDS {
+            return self.write_words(data);
+        }
+
+        if interrupt {
+            self.regs.intsta.write(IntSta::TX_COMPLETE::SET);
+            self.regs.intcfg.write(IntCfg::TX_COMPLETE::SET);
+        }
+        let n = dma_chunk_len(data.len());
+        self.dma_pending = &data[n..];
+        self.dma_active = true;
+        self.start_dma_chunk(&data[..n]);
+        Ok(())
+    }
+
+    /// 推进 DMA 写入：当前块完成时启动下一块，全部完成（或没有 DMA 传输）时返回 true
+    pub fn poll_dma(&mut self) -> Result<bool, QspiError> {
+        if !self.dma_active {
+            return Ok(true);
+        }
+        if self.regs.status.get() != 1 {
+            return Ok(false);
+        }
+        // 清除完成标志（写 1 清零）
+        self.regs.intsta.write(IntSta::TX_COMPLETE::SET);
+
+        let rest = self.dma_pending;
+        let n = dma_chunk_len(rest.len());
+        if n > 0 {
+            self.dma_pending = &rest[n..];
+            self.start_dma_chunk(&rest[..n]);
+            return Ok(false);
+        }
+
+        self.finish_dma();
+        self.write_words(rest)?;
+        Ok(true)
+    }
+
+    /// 是否有 DMA 传输在进行
+    pub fn is_dma_busy(&self) -> bool {
+        self.dma_active
+    }
+
+    // 等待当前这一块完成的最大轮询次数：每个比特至多 2 * (CLKDIV + 1) 个系统时钟，
+    // 每次轮询至少一个时钟，另加与 wait_transfer_complete 相同的余量
+    fn dma_chunk_timeout(&self) -> u32 {
+        let bits = self.regs.len.read(Len::LENGTH);
+        let per_bit = self.regs.clkdiv.get().saturating_add(1).saturating_mul(2);
+        bits.saturating_mul(per_bit).saturating_add(100_000)
+    }
+
+    // DMA_EN 置位时没有地址阶段，ADR 给出数据在内存中的地址
+    fn start_dma_chunk(&mut self, words: &[u32]) {
+        self.regs.cmd.write(
+            Cmd::MODE::Standard
+                + Cmd::TYPE::WriteOnly
+                + Cmd::DATA_BYTES::Data4Byte
+                + Cmd::DMA_EN::SET,
+        );
+        self.regs.adr.set(words.as_ptr() as usize as u32);
+        self.regs
+            .len
+            .write(Len::LENGTH.val((words.len() * 32) as u32));
+        self.regs.status.set(STATUS_START_WRITE);
+    }
+
+    // 结束 DMA 传输，恢复默认配置
+    fn finish_dma(&mut self) {
+        self.regs.intcfg.set(0);
+        self.regs.cmd.set(0);
+        self.dma_pending = &[];
+        self.dma_active = false;
+    }
+
+    /// 设置传输地址
+    pub fn set_address(&mut self, address: u32) {
+        self.regs.adr.set(address);
+    }
+
+    /// 设置时钟分频
+    pub fn set_clock_divider(&mut self, clkdiv: u32) {
+        self.regs.clkdiv.set(clkdiv);
+    }
+}
+
+// ========== 简单的全局实例（无原子操作） ==========
+
+static mut QSPI_INSTANCE: Option<Qspi> = None;
+
+/// 获取QSPI实例 - 简单全局访问
+pub fn get_qspi() -> Option<&'static mut Qspi> {
+    unsafe { QSPI_INSTANCE.as_mut() }
+}
+
+/// 初始化QSPI
+pub fn init_qspi(clkdiv: u32) {
+    unsafe {
+        if QSPI_INSTANCE.is_none() {
+            let mut qspi = Qspi::new();
+            qspi.init(clkdiv);
+            QSPI_INSTANCE = Some(qspi);
+        }
+    }
+}
+
+/// 便捷函数：写入8位数据
+pub fn write_u8(data: u8) -> Result<(), QspiError> {
+    unsafe {
+        if let Some(qspi) = QSPI_INSTANCE.as_mut() {
+            qspi.write_u8(data)
+        } else {
+            Err(QspiError::TransferFailed)
+        }
+    }
+}
+
+/// 便捷函数：写入16位数据
+pub fn write_u16(data: u16) -> Result<(), QspiError> {
+    unsafe {
+        if let Some(qspi) = QSPI_INSTANCE.as_mut() {
+            qspi.write_u16(data)
+        } else {
+            Err(QspiError::TransferFailed)
+        }
+    }
+}
+
+/// 便捷函数：写入32位数据
+pub fn write_u32(data: u32) -> Result<(), QspiError> {
+    unsafe {
+        if let Some(qspi) = QSPI_INSTANCE.as_mut() {
+            qspi.write_u32(data)
+        } else {
+            Err(QspiError::TransferFailed)
+        }
+    }
+}
+
+/// 全局函数：按照u8写入数据直到全部完成
+pub fn write_bytes(data: &[u8]) -> Result<(), QspiError> {
+    unsafe {
+        if let Some(qspi) = QSPI_INSTANCE.as_mut() {
+            qspi.write_bytes(data)
+        } else {
+            Err(QspiError::TransferFailed)
+        }
+    }
+}
+
+/// 全局函数：按照u32写入数据直到全部完成
+pub fn write_words(data: &[u32]) -> Result<(), QspiError> {
+    unsafe {
+        if let Some(qspi) = QSPI_INSTANCE.as_mut() {
+            qspi.write_words(data)
+        } else {
+            Err(QspiError::TransferFailed)
+        }
+    }
+}
+
+/// 全局函数：执行一次命令/地址/空周期/数据传输，见 [`Qspi::transfer`]
+pub fn transfer(command: &mut QspiCommand) -> Result<(), QspiError> {
+    unsafe {
+        if let Some(qspi) = QSPI_INSTANCE.as_mut() {
+            qspi.transfer(command)
+        } else {
+            Err(QspiError::TransferFailed)
+        }
+    }
+}
+
+/// 全局函数：用 DMA 写出数据并等待完成，见 [`Qspi::write_dma`]
+pub fn write_dma(data: &'static [u32]) -> Result<(), QspiError> {
+    unsafe {
+        if let Some(qspi) = QSPI_INSTANCE.as_mut() {
+            qspi.write_dma(data)
+        } else {
+            Err(QspiError::TransferFailed)
+        }
+    }
+}
+
+/// 全局函数：开始由中断推进的 DMA 写入，立即返回，见 [`Qspi::start_write_dma`]
+///
+/// 需要先使能 QSPI 中断，并在其处理函数中调用 [`on_qspi_interrupt`]。完成前不要在主循环中
+/// 调用 `poll_dma` 或 `write_dma`，用 [`is_dma_busy`] 查询是否结束
+pub fn start_write_dma(data: &'static [u32]) -> Result<(), QspiError> {
+    // 在临界区内设置好状态再放开中断，避免中断处理函数看到一半的状态
+    riscv::interrupt::free(|| unsafe {
+        if let Some(qspi) = QSPI_INSTANCE.as_mut() {
+            qspi.start_write_dma(data, true)
+        } else {
+            Err(QspiError::TransferFailed)
+        }
+    })
+}
+
+/// 全局函数：全局实例是否有 DMA 传输在进行
+pub fn is_dma_busy() -> bool {
+    riscv::interrupt::free(|| unsafe { QSPI_INSTANCE.as_ref().is_some_and(Qspi::is_dma_busy) })
+}
+
+/// 在 QSPI 中断处理函数中调用：推进由 [`start_write_dma`] 开始的 DMA 写入
+///
+/// 启动下一块或在最后补发 FIFO 部分，返回传输是否已全部结束；补发出错时同样结束传输
+pub fn on_qspi_interrupt() -> bool {
+    riscv::interrupt::free(|| unsafe {
+        match QSPI_INSTANCE.as_mut() {
+            Some(qspi) => qspi.poll_dma().unwrap_or(true),
+            None => true,
+        }
+    })
+}
@@ -0,0 +1,12 @@
+//! QSPI0 驱动
+//!
+//! 线宽组合与命令/地址比特流的展开（`mode.rs`）不依赖硬件，宿主机上也会编译；
+//! 访问寄存器的驱动本体只在目标板上编译
+
+mod mode;
+pub use mode::{QspiError, QspiMode, QspiWidth};
+
+#[cfg(target_arch = "riscv32")]
+mod driver;
+#[cfg(target_arch = "riscv32")]
+pub use driver::*;
@@ -0,0 +1,285 @@
+//! QSPI 的线宽组合，以及命令/地址阶段经 TX FIFO 发出的比特流
+//!
+//! 这里只做位运算，不访问寄存器，宿主机上也可以编译和测试
+
+// 宿主机上没有使用它们的驱动
+#![cfg_attr(not(target_arch = "riscv32"), allow(dead_code))]
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QspiError {
+    Timeout,
+    InvalidParameter,
+    TransferFailed,
+    /// 控制器不支持的线宽组合，见 [`QspiMode`]
+    Unsupported,
+    /// DMA 传输尚未完成
+    Busy,
+}
+
+// ========== 线宽 ==========
+
+/// 一个阶段的线宽，取值与 CMD 寄存器的 MODE 字段相同
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+pub enum QspiWidth {
+    /// 单线（IO0 输出，IO1 输入）
+    #[default]
+    Standard,
+    /// 双线
+    Dual,
+    /// 四线
+    Quad,
+}
+
+/// 命令、地址、数据三个阶段各自的线宽，常写作 `命令-地址-数据`，如 1-4-4
+///
+/// 控制器的 MODE 字段作用于整个传输，所以由数据阶段的线宽决定 MODE（没有数据阶段时取
+/// 最宽的阶段），命令和地址阶段只能与它相同或为单线。单线的阶段把每一位展开到 IO0 上，
+/// 随 TX FIFO 按 MODE 发出，时钟数不变；四线时 IO2 / IO3 保持高电平，不会触发器件的
+/// WP# / HOLD#。其余组合（双线与四线混用、数据阶段比命令或地址窄）返回
+/// [`QspiError::Unsupported`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct QspiMode {
+    pub cmd: QspiWidth,
+    pub addr: QspiWidth,
+    pub data: QspiWidth,
+}
+
+impl QspiMode {
+    /// 1-1-1
+    pub const STANDARD: Self = Self::new(
+        QspiWidth::Standard,
+        QspiWidth::Standard,
+        QspiWidth::Standard,
+    );
+    /// 1-1-2，如 Flash 的 0x3b
+    pub const DUAL_OUTPUT: Self =
+        Self::new(QspiWidth::Standard, QspiWidth::Standard, QspiWidth::Dual);
+    /// 1-2-2，如 Flash 的 0xbb
+    pub const DUAL_IO: Self = Self::new(QspiWidth::Standard, QspiWidth::Dual, QspiWidth::Dual);
+    /// 2-2-2
+    pub const DUAL: Self = Self::new(QspiWidth::Dual, QspiWidth::Dual, QspiWidth::Dual);
+    /// 1-1-4，如 Flash 的 0x6b、四线屏的 0x32
+    pub const QUAD_OUTPUT: Self =
+        Self::new(QspiWidth::Standard, QspiWidth::Standard, QspiWidth::Quad);
+    /// 1-4-4，如 Flash 的 0xeb
+    pub const QUAD_IO: Self = Self::new(QspiWidth::Standard, QspiWidth::Quad, QspiWidth::Quad);
+    /// 4-4-4（QPI）
+    pub const QPI: Self = Self::new(QspiWidth::Quad, QspiWidth::Quad, QspiWidth::Quad);
+
+    pub const fn new(cmd: QspiWidth, addr: QspiWidth, data: QspiWidth) -> Self {
+        Self { cmd, addr, data }
+    }
+
+    // 检查各阶段的组合，返回 MODE 字段的线宽；没有的阶段不参与检查
+    pub(super) fn bus_width(
+        &self,
+        has_cmd: bool,
+        has_addr: bool,
+        has_data: bool,
+    ) -> Result<QspiWidth, QspiError> {
+        let phases = [(has_cmd, self.cmd), (has_addr, self.addr)];
+        let width = if has_data {
+            self.data
+        } else {
+            phases
+                .iter()
+                .filter(|(present, _)| *present)
+                .map(|&(_, width)| width)
+                .max()
+                .unwrap_or_default()
+        };
+        let supported = phases
+            .iter()
+            .all(|&(present, w)| !present || w == width || w == QspiWidth::Standard);
+        if supported {
+            Ok(width)
+        } else {
+            Err(QspiError::Unsupported)
+        }
+    }
+}
+
+// ========== 命令/地址比特流 ==========
+
+// 命令、地址阶段经 TX FIFO 发出的比特流：一个字节的命令加最多 3 字节地址，
+// 四线展开后每字节占 32 位
+pub(super) struct HeaderBits {
+    words: [u32; 4],
+    pub(super) bits: u32,
+}
+
+impl HeaderBits {
+    pub(super) const fn new() -> Self {
+        Self {
+            words: [0; 4],
+            bits: 0,
+        }
+    }
+
+    // 追加 value 的低 n 位（n <= 32），高位在前
+    fn push(&mut self, value: u32, n: u32) {
+        let index = (self.bits / 32) as usize;
+        let aligned = ((value as u64) << (64 - n)) >> (self.bits % 32);
+        self.words[index] |= (aligned >> 32) as u32;
+        if self.bits % 32 + n > 32 {
+            self.words[index + 1] |= aligned as u32;
+        }
+        self.bits += n;
+    }
+
+    // 按阶段线宽追加一个字节：与 MODE 同宽时原样发出，单线时把每一位展开到 IO0 上
+    pub(super) fn push_byte(&mut self, byte: u8, phase: QspiWidth, mode: QspiWidth) {
+        if phase == mode {
+            self.push(byte as u32, 8);
+            return;
+        }
+        let (value, n) = expand_byte(byte, mode);
+        self.push(value, n);
+    }
+
+    pub(super) fn words(&self) -> &[u32] {
+        &self.words[..self.bits.div_ceil(32) as usize]
+    }
+}
+
+// 把一个单线字节展开成 mode 下的比特流，每个时钟一位：
+// 双线为 IO1=0、IO0=位，四线为 IO3=IO2=1、IO1=0、IO0=位
+fn expand_byte(byte: u8, mode: QspiWidth) -> (u32, u32) {
+    let (lines, idle) = match mode {
+        QspiWidth::Standard => return (byte as u32, 8),
+        QspiWidth::Dual => (2, 0b00),
+        QspiWidth::Quad => (4, 0b1100),
+    };
+    let value = (0..8).rev().fold(0, |value, i| {
+        (value << lines) | idle | ((byte >> i) & 1) as u32
+    });
+    (value, 8 * lines)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use QspiWidth::{Dual, Quad, Standard};
+
+    #[test]
+    fn mode_constants() {
+        let cases = [
+            (QspiMode::STANDARD, (Standard, Standard, Standard), Standard),
+            (QspiMode::DUAL_OUTPUT, (Standard, Standard, Dual), Dual),
+            (QspiMode::DUAL_IO, (Standard, Dual, Dual), Dual),
+            (QspiMode::DUAL, (Dual, Dual, Dual), Dual),
+            (QspiMode::QUAD_OUTPUT, (Standard, Standard, Quad), Quad),
+            (QspiMode::QUAD_IO, (Standard, Quad, Quad), Quad),
+            (QspiMode::QPI, (Quad, Quad, Quad), Quad),
+        ];
+        for (mode, (cmd, addr, data), width) in cases {
+            assert_eq!((mode.cmd, mode.addr, mode.data), (cmd, addr, data));
+            assert_eq!(mode.bus_width(true, true, true), Ok(width), "{:?}", mode);
+        }
+        assert_eq!(QspiMode::default(), QspiMode::STANDARD);
+    }
+
+    #[test]
+    fn bus_width_without_data() {
+        // 没有数据阶段时取最宽的阶段
+        assert_eq!(QspiMode::QUAD_IO.bus_width(true, true, false), Ok(Quad));
+        assert_eq!(QspiMode::QPI.bus_width(true, false, false), Ok(Quad));
+        assert_eq!(
+            QspiMode::QUAD_OUTPUT.bus_width(true, true, false),
+            Ok(Standard)
+        );
+        assert_eq!(QspiMode::DUAL_IO.bus_width(false, true, false), Ok(Dual));
+        assert_eq!(QspiMode::QPI.bus_width(false, false, false), Ok(Standard));
+    }
+
+    #[test]
+    fn unsupported_combinations() {
+        let rejected = [
+            // 双线与四线混用
+            QspiMode::new(Dual, Quad, Quad),
+            QspiMode::new(Standard, Dual, Quad),
+            QspiMode::new(Quad, Dual, Dual),
+            // 数据阶段比命令或地址窄
+            QspiMode::new(Quad, Quad, Standard),
+            QspiMode::new(Standard, Quad, Dual),
+            QspiMode::new(Dual, Standard, Standard),
+        ];
+        for mode in rejected {
+            assert_eq!(
+                mode.bus_width(true, true, true),
+                Err(QspiError::Unsupported),
+                "{:?}",
+                mode
+            );
+        }
+
+        // 不存在的阶段不参与检查
+        let mode = QspiMode::new(Quad, Quad, Standard);
+        assert_eq!(mode.bus_width(false, false, true), Ok(Standard));
+        assert_eq!(mode.bus_width(true, true, false), Ok(Quad));
+        let mode = QspiMode::new(Dual, Quad, Quad);
+        assert_eq!(mode.bus_width(false, true, true), Ok(Quad));
+    }
+
+    #[test]
+    fn push_across_words() {
+        let mut bits = HeaderBits::new();
+        bits.push(0x12_3456, 24);
+        bits.push(0xABCD, 16);
+        assert_eq!(bits.bits, 40);
+        assert_eq!(bits.words(), [0x1234_56AB, 0xCD00_0000]);
+
+        // 整字对齐时不写入下一个字
+        let mut bits = HeaderBits::new();
+        bits.push(0x12_3456, 24);
+        bits.push(0x78, 8);
+        assert_eq!(bits.words(), [0x1234_5678]);
+        assert_eq!(bits.words, [0x1234_5678, 0, 0, 0]);
+
+        // 32 位跨字，只取 value 的低 n 位
+        let mut bits = HeaderBits::new();
+        bits.push(0xFFFF_FF0F, 4);
+        bits.push(0xDEAD_BEEF, 32);
+        bits.push(0xDEAD_BEEF, 32);
+        assert_eq!(bits.bits, 68);
+        assert_eq!(bits.words(), [0xFDEA_DBEE, 0xFDEA_DBEE, 0xF000_0000]);
+    }
+
+    #[test]
+    fn expand_single_wire_bytes() {
+        assert_eq!(expand_byte(0x81, Standard), (0x81, 8));
+        // 双线：IO1=0、IO0=位
+        assert_eq!(expand_byte(0x81, Dual), (0b01_00_00_00_00_00_00_01, 16));
+        // 四线：IO3=IO2=1、IO1=0、IO0=位
+        assert_eq!(expand_byte(0x81, Quad), (0xDCCC_CCCD, 32));
+        assert_eq!(expand_byte(0x00, Quad), (0xCCCC_CCCC, 32));
+        assert_eq!(expand_byte(0xFF, Quad), (0xDDDD_DDDD, 32));
+
+        for byte in 0..=255u8 {
+            let (value, n) = expand_byte(byte, Quad);
+            assert_eq!(n, 32);
+            // WP# / HOLD#（IO2 / IO3）始终为高，IO1 始终为低
+            assert_eq!(value & 0xCCCC_CCCC, 0xCCCC_CCCC, "{:#04x}", byte);
+            assert_eq!(value & 0x2222_2222, 0, "{:#04x}", byte);
+            let recovered = (0..8).fold(0u8, |b, i| (b << 1) | (value >> (28 - 4 * i)) as u8 & 1);
+            assert_eq!(recovered, byte);
+        }
+    }
+
+    #[test]
+    fn quad_io_header() {
+        // 1-4-4 的 0xeb：单线命令展开为 32 位，四线地址原样发出
+        let mut bits = HeaderBits::new();
+        bits.push_byte(0xEB, Standard, Quad);
+        for byte in [0x12, 0x34, 0x56] {
+            bits.push_byte(byte, Quad, Quad);
+        }
+        assert_eq!(bits.bits, 56);
+        assert_eq!(bits.words(), [0xDDDC_DCDD, 0x1234_5600]);
+
+        // 4-4-4 不展开
+        let mut bits = HeaderBits::new();
+        bits.push_byte(0xEB, Quad, Quad);
+        assert_eq!(bits.words(), [0xEB00_0000]);
+    }
+}
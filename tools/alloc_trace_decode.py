@@ -0,0 +1,176 @@
+#!/usr/bin/env python3
+"""解码 `allocator::dump_trace()` / `allocator::dump_live_allocations()` 的串口输出
+
+用法:
+    alloc_trace_decode.py uart.log
+    alloc_trace_decode.py uart.log --elf target/riscv32imac-unknown-none-elf/debug/app
+    cat uart.log | alloc_trace_decode.py -
+
+只依赖 Python 标准库。输入可以混有其他日志，只解析
+`ECOS-ALLOC-TRACE` / `ECOS-ALLOC-LIVE` 两种段落中以 `E` / `L` 开头的行，
+每行的十六进制部分是 24 字节的小端序事件:
+
+    kind(u8) 3 字节保留 addr(u32) size(u32) old_addr(u32) caller(u32) tick(u32)
+"""
+
+import argparse
+import shutil
+import struct
+import subprocess
+import sys
+from collections import OrderedDict, defaultdict
+
+EVENT = struct.Struct("<B3x5I")
+KINDS = {1: "alloc", 2: "free", 3: "realloc", 4: "failed", 5: "live"}
+
+
+class Event:
+    def __init__(self, raw):
+        kind, self.addr, self.size, self.old_addr, self.caller, self.tick = EVENT.unpack(raw)
+        self.kind = KINDS.get(kind, "unknown({})".format(kind))
+
+
+def parse(lines):
+    """返回 (trace 段的事件列表, live 段的事件列表, 被覆盖的事件数)"""
+    trace, live, dropped = [], [], 0
+    section = None
+    for line in lines:
+        line = line.strip()
+        # 容忍串口日志里带的前缀（时间戳、颜色等）
+        for marker in ("ECOS-ALLOC-TRACE", "ECOS-ALLOC-LIVE"):
+            pos = line.find(marker)
+            if pos >= 0:
+                line = line[pos:]
+                break
+        # 开始行的 dropped= 为之前已被覆盖的事件数，结束行的为输出期间被覆盖的
+        if line.startswith("ECOS-ALLOC-TRACE"):
+            for field in line.split()[1:]:
+                if field.startswith("dropped="):
+                    dropped += int(field[len("dropped="):])
+        if line.startswith("ECOS-ALLOC-TRACE END") or line.startswith("ECOS-ALLOC-LIVE END"):
+            section = None
+        elif line.startswith("ECOS-ALLOC-TRACE v1"):
+            section = trace
+        elif line.startswith("ECOS-ALLOC-LIVE v1"):
+            section = live
+        elif section is not None and line[:2] in ("E ", "L "):
+            hexpart = line[2:].split(";", 1)[0].strip()
+            try:
+                raw = bytes.fromhex(hexpart)
+            except ValueError:
+                continue
+            if len(raw) == EVENT.size:
+                section.append(Event(raw))
+    return trace, live, dropped
+
+
+class Symbolizer:
+    """通过 addr2line 把调用者地址还原为函数和源码位置"""
+
+    def __init__(self, elf, tool):
+        self.elf = elf
+        self.tool = tool
+        self.cache = {}
+
+    def __call__(self, addr):
+        if not self.elf or addr == 0:
+            return ""
+        if addr not in self.cache:
+            try:
+                out = subprocess.run(
+                    [self.tool, "-f", "-C", "-e", self.elf, "0x{:08x}".format(addr)],
+                    capture_output=True,
+                    text=True,
+                    check=True,
+                ).stdout.split("\n")
+                self.cache[addr] = "{} ({})".format(out[0], out[1]) if len(out) > 1 else ""
+            except (OSError, subprocess.CalledProcessError):
+                self.cache[addr] = ""
+        return self.cache[addr]
+
+
+def print_events(events, symbolize):
+    print("{:>10}  {:<8} {:>10} {:>8} {:>10}  {}".format(
+        "tick", "kind", "addr", "size", "caller", ""))
+    for e in events:
+        extra = " (from 0x{:08x})".format(e.old_addr) if e.kind == "realloc" and e.old_addr else ""
+        print("{:>10}  {:<8} 0x{:08x} {:>8} 0x{:08x}  {}{}".format(
+            e.tick, e.kind, e.addr, e.size, e.caller, symbolize(e.caller), extra))
+
+
+def leaks_from_trace(events):
+    """按事件重放：没有对应 free 的 alloc / realloc 视为可能的泄漏"""
+    live = OrderedDict()
+    for e in events:
+        if e.kind == "alloc":
+            live[e.addr] = e
+        elif e.kind == "free":
+            live.pop(e.addr, None)
+        elif e.kind == "realloc":
+            live.pop(e.old_addr, None)
+            live[e.addr] = e
+    return list(live.values())
+
+
+def print_by_caller(events, symbolize):
+    groups = defaultdict(list)
+    for e in events:
+        groups[e.caller].append(e)
+    rows = sorted(groups.items(), key=lambda kv: -sum(e.size for e in kv[1]))
+    print("{:>10} {:>6} {:>10}  {}".format("caller", "count", "bytes", ""))
+    for caller, group in rows:
+        print("0x{:08x} {:>6} {:>10}  {}".format(
+            caller, len(group), sum(e.size for e in group), symbolize(caller)))
+
+
+def main():
+    parser = argparse.ArgumentParser(description=__doc__.split("\n")[0])
+    parser.add_argument("log", help="抓取的串口日志，- 表示标准输入")
+    parser.add_argument("--elf", help="固件 ELF，用于解析调用者地址")
+    parser.add_argument("--addr2line", default="riscv64-unknown-elf-addr2line",
+                        help="addr2line 工具（默认 %(default)s）")
+    parser.add_argument("--quiet", action="store_true", help="不打印逐条事件")
+    args = parser.parse_args()
+
+    if args.log == "-":
+        lines = sys.stdin.readlines()
+    else:
+        with open(args.log, errors="replace") as f:
+            lines = f.readlines()
+
+    if args.elf and not shutil.which(args.addr2line):
+        print("warning: {} not found, callers left unresolved".format(args.addr2line),
+              file=sys.stderr)
+        args.elf = None
+    symbolize = Symbolizer(args.elf, args.addr2line)
+
+    trace, live, dropped = parse(lines)
+    if not trace and not live:
+        sys.exit("no ECOS-ALLOC-TRACE / ECOS-ALLOC-LIVE section found")
+
+    if trace:
+        if not args.quiet:
+            print("== events ({}, {} dropped) ==".format(len(trace), dropped))
+            print_events(trace, symbolize)
+            print()
+        failed = [e for e in trace if e.kind == "failed"]
+        if failed:
+            print("== failed allocations ({}) ==".format(len(failed)))
+            print_events(failed, symbolize)
+            print()
+        leaks = leaks_from_trace(trace)
+        print("== not freed within trace window ({}, {} bytes) ==".format(
+            len(leaks), sum(e.size for e in leaks)))
+        if dropped:
+            print("(events before the window were dropped; frees of older blocks are not matched)")
+        print_by_caller(leaks, symbolize)
+        print()
+
+    if live:
+        print("== live allocations ({}, {} bytes) ==".format(
+            len(live), sum(e.size for e in live)))
+        print_by_caller(live, symbolize)
+
+
+if __name__ == "__main__":
+    main()
@@ -0,0 +1,331 @@
+#!/usr/bin/env python3
+"""解码 `log-deferred` 延迟日志的串口输出
+
+用法:
+    # 构建后从 ELF 中导出索引表（可以和固件一起归档）
+    log_decode.py index target/riscv32imac-unknown-none-elf/release/app -o app.logidx
+
+    # 解码串口的原始字节流（文件或 - 表示标准输入）
+    log_decode.py decode uart.bin --index app.logidx
+    log_decode.py decode uart.bin --elf target/riscv32imac-unknown-none-elf/release/app
+
+只依赖 Python 标准库。索引表来自 ELF 的 `.ecos_log_fmt` 段：每一项是以 0 结尾的
+`目标 0x1f 文件:行号 0x1f 格式化字符串`，索引为该项的地址。
+
+串口上每条记录为 `0x00 COBS(记录) 0x00`，两条记录之间的普通文本日志原样输出。
+记录为小端序:
+
+    level(u8) index(u32) tick(u32) { tag(u8) 值 }*
+
+index 为 0 的记录表示设备端因队列满丢弃的记录数。
+"""
+
+import argparse
+import json
+import struct
+import sys
+
+SECTION = ".ecos_log_fmt"
+LEVELS = ["ERROR", "WARN ", "INFO ", "DEBUG", "TRACE"]
+
+TAG_U32, TAG_I32, TAG_U64, TAG_I64 = 1, 2, 3, 4
+TAG_F32, TAG_F64, TAG_BOOL, TAG_CHAR = 5, 6, 7, 8
+TAG_STR, TAG_BYTES, TAG_TRUNCATED = 9, 10, 0xFF
+
+INTS = {TAG_U32: ("<I", 32), TAG_I32: ("<i", 32), TAG_U64: ("<Q", 64), TAG_I64: ("<q", 64)}
+FLOATS = {TAG_F32: "<f", TAG_F64: "<d"}
+
+
+# 参数值，按 Rust 的 Display (`{}`) 和 Debug (`{:?}`) 规则输出
+
+
+class Int(int):
+    """带位宽的整数，十六进制、八进制、二进制格式下负数按补码显示"""
+
+    def __new__(cls, value, bits):
+        obj = int.__new__(cls, value)
+        obj.bits = bits
+        return obj
+
+    def __format__(self, spec):
+        if spec[-1:] in ("x", "X", "o", "b") and self < 0:
+            return format(int(self) + (1 << self.bits), spec)
+        return format(int(self), spec)
+
+
+class Float(float):
+    def __new__(cls, raw, fmt):
+        value = exact = struct.unpack(fmt, raw)[0]
+        if fmt == "<f":
+            # f32 按能还原出同一个值的最短写法输出，与 Rust 一致
+            for digits in range(1, 10):
+                short = float("{:.{}g}".format(value, digits))
+                if struct.pack(fmt, short) == raw:
+                    value = short
+                    break
+        obj = float.__new__(cls, value)
+        obj.exact = exact
+        return obj
+
+    def __format__(self, spec):
+        if not spec and self.is_integer():
+            return "{:.0f}".format(self)
+        # 给出精度时按实际的值舍入；只给出精度时 Rust 按定点格式输出
+        if "." in spec:
+            if spec[-1:].isdigit():
+                spec += "f"
+            return format(self.exact, spec)
+        return format(float(self), spec)
+
+    def debug(self):
+        return repr(float(self))
+
+
+class Bool:
+    def __init__(self, value):
+        self.value = value
+
+    def __format__(self, spec):
+        return format("true" if self.value else "false", spec)
+
+    def debug(self):
+        return format(self)
+
+
+class Char(str):
+    def debug(self):
+        return repr(str(self))
+
+
+class Truncated:
+    """设备端放不下的参数"""
+
+    def __format__(self, spec):
+        return "…"
+
+    def debug(self):
+        return "…"
+
+
+# ========== 索引表 ==========
+
+
+def read_section(path, name):
+    """返回 (段地址, 段内容)，支持 32/64 位小端 ELF"""
+    with open(path, "rb") as f:
+        data = f.read()
+    if data[:4] != b"\x7fELF" or data[5] != 1:
+        raise SystemExit("{}: not a little-endian ELF file".format(path))
+    is64 = data[4] == 2
+    if is64:
+        shoff, = struct.unpack_from("<Q", data, 0x28)
+        shentsize, shnum, shstrndx = struct.unpack_from("<HHH", data, 0x3A)
+        fmt = "<IIQQQQ"
+    else:
+        shoff, = struct.unpack_from("<I", data, 0x20)
+        shentsize, shnum, shstrndx = struct.unpack_from("<HHH", data, 0x2E)
+        fmt = "<IIIIII"
+
+    def header(i):
+        # name type flags addr offset size
+        return struct.unpack_from(fmt, data, shoff + i * shentsize)
+
+    _, _, _, _, stroff, strsize = header(shstrndx)
+    strtab = data[stroff:stroff + strsize]
+    for i in range(shnum):
+        name_off, _, _, addr, offset, size = header(i)
+        end = strtab.index(b"\0", name_off)
+        if strtab[name_off:end].decode() == name:
+            return addr, data[offset:offset + size]
+    raise SystemExit("{}: no {} section (is log-deferred used?)".format(path, name))
+
+
+def build_index(elf):
+    """索引 -> (目标, 位置, 格式化字符串)"""
+    addr, data = read_section(elf, SECTION)
+    table = {}
+    pos = 0
+    while pos < len(data):
+        # 段内的项之间可能有对齐填充
+        if data[pos] == 0:
+            pos += 1
+            continue
+        end = data.index(b"\0", pos)
+        parts = data[pos:end].decode("utf-8", "replace").split("\x1f", 2)
+        if len(parts) == 3:
+            table[addr + pos] = tuple(parts)
+        pos = end + 1
+    return table
+
+
+def load_index(path):
+    with open(path, encoding="utf-8") as f:
+        return {int(k, 16): tuple(v) for k, v in json.load(f).items()}
+
+
+def save_index(table, out):
+    json.dump({"0x{:08x}".format(k): list(v) for k, v in sorted(table.items())},
+              out, ensure_ascii=False, indent=1)
+    out.write("\n")
+
+
+# ========== 记录解码 ==========
+
+
+def cobs_decode(data):
+    out = bytearray()
+    pos = 0
+    while pos < len(data):
+        code = data[pos]
+        if code == 0:
+            raise ValueError("zero byte in COBS data")
+        out += data[pos + 1:pos + code]
+        pos += code
+        if code < 0xFF and pos < len(data):
+            out.append(0)
+    return bytes(out)
+
+
+def parse_args(data):
+    args = []
+    pos = 0
+    while pos < len(data):
+        tag = data[pos]
+        pos += 1
+        if tag in INTS:
+            fmt, bits = INTS[tag]
+            value = Int(struct.unpack_from(fmt, data, pos)[0], bits)
+            pos += struct.calcsize(fmt)
+        elif tag in FLOATS:
+            fmt = FLOATS[tag]
+            size = struct.calcsize(fmt)
+            value = Float(data[pos:pos + size], fmt)
+            pos += size
+        elif tag == TAG_BOOL:
+            value = Bool(data[pos])
+            pos += 1
+        elif tag == TAG_CHAR:
+            value = Char(chr(struct.unpack_from("<I", data, pos)[0]))
+            pos += 4
+        elif tag in (TAG_STR, TAG_BYTES):
+            n, = struct.unpack_from("<H", data, pos)
+            raw = data[pos + 2:pos + 2 + n]
+            value = raw.decode("utf-8", "replace") if tag == TAG_STR else list(raw)
+            pos += 2 + n
+        elif tag == TAG_TRUNCATED:
+            args.append(Truncated())
+            break
+        else:
+            raise ValueError("unknown tag {}".format(tag))
+        args.append(value)
+    return args
+
+
+def debug_repr(value):
+    if hasattr(value, "debug"):
+        return value.debug()
+    if isinstance(value, str):
+        return json.dumps(value, ensure_ascii=False)
+    return str(value)
+
+
+def render(fmt, args):
+    """按 Rust 的格式化规则处理按位置的 `{}` / `{:spec}` 占位符"""
+    out = []
+    it = iter(args)
+    i = 0
+    while i < len(fmt):
+        c = fmt[i]
+        if c in "{}" and fmt[i + 1:i + 2] == c:
+            out.append(c)
+            i += 2
+            continue
+        if c != "{":
+            out.append(c)
+            i += 1
+            continue
+        end = fmt.find("}", i)
+        if end < 0:
+            out.append(fmt[i:])
+            break
+        spec = fmt[i + 1:end].partition(":")[2]
+        value = next(it, "<missing>")
+        if spec.endswith("?"):
+            out.append(debug_repr(value))
+        else:
+            try:
+                out.append(format(value, spec))
+            except (TypeError, ValueError):
+                out.append(str(value))
+        i = end + 1
+    return "".join(out)
+
+
+def decode_frame(frame, table):
+    """返回 (文本, 位置)"""
+    level, index, tick = struct.unpack_from("<BII", frame, 0)
+    args = parse_args(frame[9:])
+    if index == 0:
+        return "[{:08X}] [WARN ] [log] {} deferred records dropped".format(tick, args[0]), None
+    level = LEVELS[level] if level < len(LEVELS) else "?????"
+    entry = table.get(index)
+    if entry is None:
+        return "[{:08X}] [{}] <unknown index 0x{:08x}> {}".format(tick, level, index, args), None
+    target, location, fmt = entry
+    return "[{:08X}] [{}] [{}] {}".format(tick, level, target, render(fmt, args)), location
+
+
+def decode_stream(data, table, out, locations=False):
+    # 以 0x00 分段：偶数段是普通文本，奇数段是记录
+    for i, segment in enumerate(data.split(b"\0")):
+        if i % 2 == 0:
+            out.write(segment.decode("utf-8", "replace"))
+            continue
+        if not segment:
+            continue
+        try:
+            line, location = decode_frame(cobs_decode(segment), table)
+        except (ValueError, struct.error, IndexError) as err:
+            line, location = "<bad frame: {}>".format(err), None
+        if locations and location:
+            line += "  ({})".format(location)
+        out.write(line + "\n")
+
+
+def main():
+    parser = argparse.ArgumentParser(description=__doc__.splitlines()[0])
+    sub = parser.add_subparsers(dest="command", required=True)
+
+    index = sub.add_parser("index", help="export the format string table from an ELF file")
+    index.add_argument("elf")
+    index.add_argument("-o", "--output", help="output file (default: stdout)")
+
+    decode = sub.add_parser("decode", help="decode a captured UART byte stream")
+    decode.add_argument("input", help="captured bytes, or - for stdin")
+    source = decode.add_mutually_exclusive_group(required=True)
+    source.add_argument("--elf", help="firmware ELF file")
+    source.add_argument("--index", help="table written by the index command")
+    decode.add_argument("--locations", action="store_true", help="append file:line to each record")
+
+    args = parser.parse_args()
+
+    if args.command == "index":
+        table = build_index(args.elf)
+        if args.output:
+            with open(args.output, "w", encoding="utf-8") as f:
+                save_index(table, f)
+        else:
+            save_index(table, sys.stdout)
+        return
+
+    table = build_index(args.elf) if args.elf else load_index(args.index)
+    if args.input == "-":
+        data = sys.stdin.buffer.read()
+    else:
+        with open(args.input, "rb") as f:
+            data = f.read()
+    decode_stream(data, table, sys.stdout, args.locations)
+
+
+if __name__ == "__main__":
+    main()